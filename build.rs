@@ -0,0 +1,16 @@
+fn main() {
+    // Regenerate the header of the C ABI when building with the `ffi` feature
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        cbindgen::Builder::new()
+            .with_src(format!("{dir}/src/ffi.rs"))
+            .with_language(cbindgen::Language::C)
+            .with_include_guard("ETILBUDSAVIS_H")
+            .with_header("/* Generated by cbindgen from src/ffi.rs, do not edit. */")
+            .generate()
+            .expect("Could not generate the C header")
+            .write_to_file(format!("{dir}/include/etilbudsavis.h"));
+    }
+}
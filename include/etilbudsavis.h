@@ -0,0 +1,35 @@
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef ETILBUDSAVIS_H
+#define ETILBUDSAVIS_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Fetches the offers of the favorite dealers as a JSON array, like
+ * `--output json` does. Only offers matching `query` are included, unless it
+ * is null. Returns null when the offers could not be fetched.
+ *
+ * The string must be freed with `etilbudsavis_free_string`.
+ *
+ * # Safety
+ *
+ * `query` must be null or a valid, null-terminated UTF-8 string.
+ */
+char *etilbudsavis_offers_json(const char *query);
+
+/**
+ * Frees a string returned by `etilbudsavis_offers_json`. Does nothing when
+ * it is null.
+ *
+ * # Safety
+ *
+ * `string` must be null or returned by `etilbudsavis_offers_json`, and not
+ * freed before.
+ */
+void etilbudsavis_free_string(char *string);
+
+#endif  /* ETILBUDSAVIS_H */
@@ -0,0 +1,48 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+
+use crate::{locale, requests::history::History, style};
+
+/// Prints every alert that has been sent, along with whether it is silenced.
+pub fn print_alerts(history: &History) -> anyhow::Result<()> {
+    let mut table = style::table();
+    table.set_header(vec![
+        locale::text("Id", "Id"),
+        locale::text("Dealer", "Butik"),
+        locale::text("Product", "Produkt"),
+        locale::text("Notified", "Sendt"),
+        locale::text("Status", "Status"),
+    ]);
+    for alert in history.alerts()? {
+        let status = if alert.acknowledged {
+            locale::text("acknowledged", "kvitteret").to_string()
+        } else {
+            match alert.snoozed_until {
+                Some(until) if until > chrono::Utc::now().naive_utc() => {
+                    format!(
+                        "{} {}",
+                        locale::text("snoozed until", "udsat til"),
+                        local_time(until)
+                    )
+                }
+                _ => String::new(),
+            }
+        };
+        table.add_row(vec![
+            alert.offer_id,
+            alert.dealer,
+            alert.name,
+            local_time(alert.notified_at),
+            status,
+        ]);
+    }
+    println!("{table}");
+    Ok(())
+}
+
+/// Formats a UTC timestamp in local time.
+pub fn local_time(utc: NaiveDateTime) -> String {
+    Local
+        .from_utc_datetime(&utc)
+        .format("%d/%m %H:%M")
+        .to_string()
+}
@@ -0,0 +1,163 @@
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    requests::{dealer::Dealer, offer::Offer},
+    search,
+};
+
+/// Whether the offer matches a free-text shopping list item. Every word of
+/// the item has to be part of the name, in any order, so "hakket oksekød"
+/// matches "Oksekød, hakket 8-12%".
+pub fn matches_item(offer: &Offer, item: &str) -> bool {
+    let name = search::fold(&offer.name);
+    let mut words = item.split_whitespace().peekable();
+    words.peek().is_some() && words.all(|word| name.contains(&search::fold(word)))
+}
+
+/// The cheapest matching offer of every dealer with one.
+pub fn cheapest_per_dealer<'a>(offers: &'a [Offer], item: &str) -> HashMap<Dealer, &'a Offer> {
+    let mut cheapest: HashMap<Dealer, &Offer> = HashMap::new();
+    for offer in offers.iter().filter(|offer| matches_item(offer, item)) {
+        cheapest
+            .entry(offer.dealer)
+            .and_modify(|best| {
+                if offer.price < best.price {
+                    *best = offer;
+                }
+            })
+            .or_insert(offer);
+    }
+    cheapest
+}
+
+/// The matching offer with the lowest cost per unit of every dealer with one,
+/// cheapest first.
+pub fn best_unit_price_per_dealer<'a>(offers: &'a [Offer], item: &str) -> Vec<&'a Offer> {
+    let mut best: HashMap<Dealer, &Offer> = HashMap::new();
+    for offer in offers.iter().filter(|offer| matches_item(offer, item)) {
+        let current = best.entry(offer.dealer).or_insert(offer);
+        if offer.cost_per_unit < current.cost_per_unit {
+            *current = offer;
+        }
+    }
+    let mut best: Vec<&Offer> = best.into_values().collect();
+    best.sort_by(|a, b| a.cost_per_unit.total_cmp(&b.cost_per_unit));
+    best
+}
+
+#[derive(Serialize, Debug)]
+pub struct Pick<'a> {
+    pub item: &'a str,
+    /// None when none of the chosen dealers has the item
+    pub offer: Option<&'a Offer>,
+}
+
+/// Which dealers to shop at and what to buy from each of them.
+#[derive(Serialize, Debug)]
+pub struct Plan<'a> {
+    pub dealers: Vec<Dealer>,
+    pub picks: Vec<Pick<'a>>,
+    pub total: f64,
+}
+
+impl Plan<'_> {
+    pub fn missing(&self) -> usize {
+        self.picks
+            .iter()
+            .filter(|pick| pick.offer.is_none())
+            .count()
+    }
+}
+
+/// Finds the combination of at most `max_stores` dealers that has the most
+/// items of the list, and of those the lowest total price. Every item is
+/// bought once, at the price of one offer.
+pub fn optimize<'a>(
+    items: &'a [String],
+    offers: &'a [Offer],
+    max_stores: Option<usize>,
+) -> Plan<'a> {
+    let cheapest: Vec<HashMap<Dealer, &Offer>> = items
+        .iter()
+        .map(|item| cheapest_per_dealer(offers, item))
+        .collect();
+    let candidates: Vec<Dealer> = cheapest
+        .iter()
+        .flat_map(|offers| offers.keys().copied())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let plan = |dealers: &[Dealer]| {
+        let picks: Vec<Pick> = items
+            .iter()
+            .zip(&cheapest)
+            .map(|(item, offers)| Pick {
+                item,
+                offer: dealers
+                    .iter()
+                    .filter_map(|dealer| offers.get(dealer).copied())
+                    .min_by(|a, b| a.price.total_cmp(&b.price)),
+            })
+            .collect();
+        let total = picks
+            .iter()
+            .filter_map(|pick| pick.offer)
+            .map(|offer| offer.price)
+            .sum();
+        // Leave out chosen dealers nothing is bought from
+        let dealers = dealers
+            .iter()
+            .copied()
+            .filter(|dealer| {
+                picks
+                    .iter()
+                    .filter_map(|pick| pick.offer)
+                    .any(|offer| offer.dealer == *dealer)
+            })
+            .collect();
+        Plan {
+            dealers,
+            picks,
+            total,
+        }
+    };
+    let max_stores = max_stores.unwrap_or(usize::MAX);
+    if max_stores >= candidates.len() {
+        // Every item can be bought where it is cheapest
+        return plan(&candidates);
+    }
+    let mut best: Option<Plan> = None;
+    for_each_combination(&candidates, max_stores, &mut |dealers| {
+        let candidate = plan(dealers);
+        let better = best.as_ref().is_none_or(|best| {
+            (candidate.missing(), candidate.total) < (best.missing(), best.total)
+        });
+        if better {
+            best = Some(candidate);
+        }
+    });
+    best.unwrap_or_else(|| plan(&[]))
+}
+
+/// Calls `f` with every combination of exactly `size` of the dealers. More
+/// dealers never cost more, so smaller combinations are not needed.
+fn for_each_combination(dealers: &[Dealer], size: usize, f: &mut impl FnMut(&[Dealer])) {
+    fn recurse(
+        dealers: &[Dealer],
+        size: usize,
+        chosen: &mut Vec<Dealer>,
+        f: &mut impl FnMut(&[Dealer]),
+    ) {
+        if chosen.len() == size {
+            f(chosen);
+            return;
+        }
+        for (index, dealer) in dealers.iter().enumerate() {
+            chosen.push(*dealer);
+            recurse(&dealers[index + 1..], size, chosen, f);
+            chosen.pop();
+        }
+    }
+    recurse(dealers, size, &mut Vec::new(), f);
+}
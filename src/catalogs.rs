@@ -0,0 +1,165 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use comfy_table::{Cell, CellAlignment, Table};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{
+    requests::{dealer::Dealer, offer::Offer},
+    style,
+};
+
+/// A catalog of a dealer, as seen from the offers in it.
+#[derive(Serialize)]
+pub struct CatalogListing {
+    pub id: String,
+    pub dealer: Dealer,
+    pub valid_from: NaiveDate,
+    pub valid_till: NaiveDate,
+    pub offers: usize,
+}
+
+/// The catalogs the offers are from, by dealer and then by when they start.
+/// An offer in several catalogs is counted in each of them.
+pub fn listings(offers: &[Offer]) -> Vec<CatalogListing> {
+    let mut catalogs: BTreeMap<&str, CatalogListing> = BTreeMap::new();
+    for offer in offers {
+        for source in offer.sources.iter() {
+            let listing = catalogs
+                .entry(&source.catalog_id)
+                .or_insert_with(|| CatalogListing {
+                    id: source.catalog_id.clone(),
+                    dealer: offer.dealer,
+                    valid_from: offer.run_from,
+                    valid_till: offer.run_till,
+                    offers: 0,
+                });
+            listing.valid_from = listing.valid_from.min(offer.run_from);
+            listing.valid_till = listing.valid_till.max(offer.run_till);
+            listing.offers += 1;
+        }
+    }
+    let mut listings: Vec<CatalogListing> = catalogs.into_values().collect();
+    listings.sort_by(|a, b| (a.dealer, a.valid_from, &a.id).cmp(&(b.dealer, b.valid_from, &b.id)));
+    listings
+}
+
+/// Whether the offer is in the catalog with the id.
+pub fn contains(offer: &Offer, id: &str) -> bool {
+    offer.sources.iter().any(|source| source.catalog_id == id)
+}
+
+pub fn listings_table(listings: &[CatalogListing]) -> Table {
+    let mut table = style::table();
+    table.set_header(vec!["Id", "Dealer", "Valid", "Offers"]);
+    for listing in listings {
+        table.add_row(vec![
+            Cell::new(&listing.id),
+            Cell::new(listing.dealer.to_string()),
+            Cell::new(format!(
+                "{} - {}",
+                listing.valid_from.format("%d/%m"),
+                listing.valid_till.format("%d/%m")
+            )),
+            Cell::new(listing.offers).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    table
+}
+
+/// Saves the page images of a catalog in the directory as `page-01.jpg` and
+/// so on.
+pub fn save_pages(pages: &[Vec<u8>], dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    for (i, page) in pages.iter().enumerate() {
+        let path = dir.join(format!("page-{:02}.jpg", i + 1));
+        std::fs::write(&path, page)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Width of the PDF pages, with the height following from the images.
+const PAGE_WIDTH: f32 = 595.0;
+
+const IMAGE: Name = Name(b"Im1");
+
+/// A PDF with a page for every JPEG page image of a catalog. The images are
+/// embedded as they are, so nothing is lost to compressing them again.
+pub fn pages_pdf(pages: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let mut pdf = Pdf::new();
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    // Every page takes a page, a content stream and an image
+    let page_ids: Vec<Ref> = (0..pages.len() as i32)
+        .map(|i| Ref::new(3 + 3 * i))
+        .collect();
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+
+    for (i, (image, page_id)) in pages.iter().zip(page_ids).enumerate() {
+        let Some((width, height, components)) = jpeg_size(image) else {
+            bail!("Page {} is not a JPEG image", i + 1);
+        };
+        let content_id = Ref::new(page_id.get() + 1);
+        let image_id = Ref::new(page_id.get() + 2);
+        let page_height = PAGE_WIDTH * height as f32 / width as f32;
+
+        let mut xobject = pdf.image_xobject(image_id, image);
+        xobject.filter(Filter::DctDecode);
+        xobject.width(width as i32);
+        xobject.height(height as i32);
+        xobject.bits_per_component(8);
+        match components {
+            1 => xobject.color_space().device_gray(),
+            4 => xobject.color_space().device_cmyk(),
+            _ => xobject.color_space().device_rgb(),
+        }
+        xobject.finish();
+
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, page_height));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().x_objects().pair(IMAGE, image_id);
+        page.finish();
+
+        let mut content = Content::new();
+        content.save_state();
+        content.transform([PAGE_WIDTH, 0.0, 0.0, page_height, 0.0, 0.0]);
+        content.x_object(IMAGE);
+        content.restore_state();
+        pdf.stream(content_id, &content.finish());
+    }
+
+    Ok(pdf.finish())
+}
+
+/// Width, height and number of color components of a JPEG image, read from
+/// its start of frame.
+fn jpeg_size(image: &[u8]) -> Option<(u16, u16, u8)> {
+    if !image.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut at = 2;
+    while at + 4 <= image.len() {
+        if image[at] != 0xFF {
+            return None;
+        }
+        let marker = image[at + 1];
+        let length = u16::from_be_bytes([image[at + 2], image[at + 3]]) as usize;
+        // Start of frame markers, leaving out DHT, JPG and DAC
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            let frame = image.get(at + 5..at + 10)?;
+            let height = u16::from_be_bytes([frame[0], frame[1]]);
+            let width = u16::from_be_bytes([frame[2], frame[3]]);
+            return (width > 0 && height > 0).then_some((width, height, frame[4]));
+        }
+        at += 2 + length;
+    }
+    None
+}
@@ -0,0 +1,76 @@
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+
+use super::{print_json, table::offer_table, Options};
+use crate::{
+    catalogs, locale,
+    requests::{dealer::Dealer, images, offer::retrieve_offers, userdata::UserData},
+};
+
+/// Lists the catalogs of the favorite dealers, or of one of them.
+pub async fn list(userdata: &mut UserData, dealer: Option<Dealer>, options: &Options) {
+    let offers = retrieve_offers(userdata, false).await;
+    let mut listings = catalogs::listings(&offers);
+    listings.retain(|listing| dealer.is_none_or(|dealer| listing.dealer == dealer));
+    if options.prints_json() {
+        print_json(&listings);
+    } else if listings.is_empty() {
+        println!(
+            "{}",
+            locale::text(
+                "No catalogs of the favorite dealers",
+                "Ingen kataloger fra favoritbutikkerne"
+            )
+        );
+    } else {
+        println!("{}", catalogs::listings_table(&listings));
+    }
+}
+
+/// Lists the offers of the catalog with the id.
+pub async fn show(userdata: &mut UserData, id: &str, options: &Options) -> Result<()> {
+    let mut offers = retrieve_offers(userdata, false).await;
+    offers.retain(|offer| catalogs::contains(offer, id));
+    if offers.is_empty() {
+        bail!("No current catalog with id {id}");
+    }
+    if options.prints_json() {
+        print_json(&offers);
+        return Ok(());
+    }
+    offers.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut table = offer_table();
+    for offer in offers.iter() {
+        table.add_row(offer.to_table_entry(options.warn_days));
+    }
+    println!("{table}");
+    println!(
+        "{}: {}",
+        locale::text("Amount of offers", "Antal tilbud"),
+        offers.len()
+    );
+    Ok(())
+}
+
+/// Saves the pages of the catalog as images in a directory, or as one PDF.
+pub async fn download(id: &str, pdf: bool, output: Option<PathBuf>) -> Result<()> {
+    let pages = images::download_catalog(id)
+        .await
+        .with_context(|| format!("Could not download catalog {id}"))?;
+    if pages.is_empty() {
+        bail!("Catalog {id} has no pages");
+    }
+    let path = if pdf {
+        let path = output.unwrap_or_else(|| PathBuf::from(format!("catalog-{id}.pdf")));
+        let pdf = catalogs::pages_pdf(&pages)?;
+        std::fs::write(&path, pdf)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        path
+    } else {
+        let dir = output.unwrap_or_else(|| PathBuf::from(format!("catalog-{id}")));
+        catalogs::save_pages(&pages, &dir)?;
+        dir
+    };
+    println!("Saved {} pages to {}", pages.len(), path.display());
+    Ok(())
+}
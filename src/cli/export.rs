@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::Options;
+use crate::{
+    config,
+    export::{self, sheets::SheetTarget},
+    import, locale,
+    report::{Report, ReportFormat},
+    requests::{branding, history::History, images, offer::retrieve_offers, userdata::UserData},
+    share, snapshot,
+    week::Week,
+};
+
+/// Writes a report of the week's offers matching the shopping list.
+pub async fn report(
+    userdata: &mut UserData,
+    format: ReportFormat,
+    output: Option<PathBuf>,
+    with_images: bool,
+    search: &[String],
+    options: &Options,
+) -> Result<()> {
+    let offers = retrieve_offers(userdata, false).await;
+    let report = Report::new(&offers, search, options.week.unwrap_or_else(Week::current));
+    let branding = branding::get_branding(&report.dealers()).await;
+    let mut report = report.with_branding(branding);
+    if with_images {
+        let images = images::download_images(&report.offers()).await;
+        report = report.with_images(images);
+    }
+    let path = output.unwrap_or_else(|| PathBuf::from(format!("report.{}", format.extension())));
+    report.write(format, &path)?;
+    println!("Report written to {}", path.display());
+    Ok(())
+}
+
+/// Prints links to the cheapest offers of the items, or uploads a report of
+/// them with `upload`.
+pub async fn share(
+    userdata: &mut UserData,
+    upload: bool,
+    search: &[String],
+    options: &Options,
+) -> Result<()> {
+    let offers = retrieve_offers(userdata, false).await;
+    let week = options.week.unwrap_or_else(Week::current);
+    let shared = Report::new(&offers, search, week);
+    if !upload {
+        for (term, offer) in shared.shopping_list() {
+            match offer {
+                Some(offer) => println!("{offer}\n  {}", share::offer_link(offer)),
+                None => println!(
+                    "{term}: {} {}",
+                    locale::text("no offers", "ingen tilbud"),
+                    week.to_string().to_lowercase()
+                ),
+            }
+        }
+        return Ok(());
+    }
+    let branding = branding::get_branding(&shared.dealers()).await;
+    let shared = shared.with_branding(branding);
+    let path = std::env::temp_dir().join("etilbudsavis-report.html");
+    shared.write(ReportFormat::Html, &path)?;
+    println!("{}", share::upload(&config::get_config().share, &path)?);
+    Ok(())
+}
+
+/// Archives every offer of the favorite dealers as this week's snapshot.
+pub async fn snapshot(userdata: &mut UserData, keep: Option<usize>) -> Result<()> {
+    let config = config::get_config();
+    let offers = retrieve_offers(userdata, false).await;
+    let keep = keep.unwrap_or(config.snapshot.keep);
+    let (path, deleted) = snapshot::archive(&config.snapshot, &offers, Week::current(), keep)?;
+    println!("Archived {} offers to {}", offers.len(), path.display());
+    if deleted > 0 {
+        println!("Deleted {deleted} old snapshots");
+    }
+    Ok(())
+}
+
+/// Adds the reference prices of a CSV file to the price history, tagged with
+/// the source or else the file name.
+pub fn import_prices(file: &Path, source: Option<String>) -> Result<()> {
+    let source = source.unwrap_or_else(|| {
+        file.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "import".to_string())
+    });
+    let count = import::import_prices(file, &source)?;
+    println!("Imported {count} prices from {source}");
+    Ok(())
+}
+
+/// Writes the price history as anonymous CSV for the community dataset.
+pub fn community(output: &Path) -> Result<()> {
+    let points = History::open().and_then(|history| history.price_points())?;
+    export::community::write(&points, output)?;
+    println!("Wrote {} prices to {}", points.len(), output.display());
+    Ok(())
+}
+
+/// Writes an RSS feed of the offers matching the watchlist.
+pub async fn rss(userdata: &mut UserData, output: &Path) -> Result<()> {
+    if userdata.watchlist.is_empty() {
+        anyhow::bail!("The watchlist is empty, add products with `watch add`");
+    }
+    let offers = retrieve_offers(userdata, false).await;
+    let count = export::rss::write(&userdata.watchlist, &offers, output)?;
+    println!("Wrote {count} offers to {}", output.display());
+    Ok(())
+}
+
+/// Pushes the offers matching the items to a Google Sheet.
+pub async fn sheets(
+    userdata: &mut UserData,
+    spreadsheet: Option<String>,
+    sheet: Option<String>,
+    append: bool,
+    search: &[String],
+) -> Result<()> {
+    let mut offers = retrieve_offers(userdata, false).await;
+    let sheets = &config::get_config().sheets;
+    let credentials = sheets
+        .credentials
+        .as_ref()
+        .context("No service account credentials set, add sheets.credentials to the config")?;
+    let spreadsheet_id = spreadsheet
+        .or_else(|| sheets.spreadsheet_id.clone())
+        .context("No spreadsheet given, pass --spreadsheet or set sheets.spreadsheet_id")?;
+    let sheet = sheet
+        .or_else(|| sheets.sheet.clone())
+        .unwrap_or_else(|| "Offers".to_string());
+    if !search.is_empty() {
+        offers.retain(|offer| search.iter().any(|term| offer.matches(term)));
+    }
+    let target = SheetTarget {
+        spreadsheet_id: &spreadsheet_id,
+        sheet: &sheet,
+        append,
+    };
+    export::sheets::push(credentials, &target, &offers).await?;
+    println!("Pushed {} offers to sheet {sheet}", offers.len());
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use comfy_table::{Cell, CellAlignment};
+use serde::Serialize;
+
+use super::{confirm, print_json, save_favorites, Options};
+use crate::{
+    config,
+    requests::{dealer::Dealer, registry, stores, userdata::UserData},
+    style,
+};
+
+#[derive(Serialize)]
+struct DealerListing {
+    name: &'static str,
+    slug: String,
+    id: &'static str,
+}
+
+/// Adds the dealers to the favorites.
+pub fn add(userdata: &mut UserData, dealers: &[Dealer]) {
+    if !userdata.add_favorites(dealers) {
+        println!("Already in favorites");
+    }
+    save_favorites(userdata);
+}
+
+/// Removes the dealers from the favorites once confirmed, returning whether
+/// it was.
+pub fn remove(userdata: &mut UserData, dealers: &[Dealer], options: &Options) -> bool {
+    let names: Vec<String> = dealers.iter().map(Dealer::to_string).collect();
+    let question = format!("Remove {} from favorites?", names.join(", "));
+    if !confirm(&question, options) {
+        return false;
+    }
+    if !userdata.remove_favorites(dealers) {
+        println!("Not in favorites");
+    }
+    save_favorites(userdata);
+    true
+}
+
+pub fn list(userdata: &UserData, options: &Options) {
+    let mut favorites: Vec<Dealer> = userdata.favorites.iter().copied().collect();
+    favorites.sort();
+    if options.prints_json() {
+        print_json(&favorites);
+        return;
+    }
+    let mut table = style::table();
+    table.set_header(vec!["Favorites"]);
+    for favorite in favorites {
+        table.add_row(vec![favorite.to_string()]);
+    }
+    println!("{}", table);
+}
+
+/// Syncs the favorites with the eTilbudsavis account from the config.
+#[cfg(feature = "account")]
+pub async fn sync(userdata: &mut UserData) -> Result<()> {
+    let config = &config::get_config().account;
+    let summary = crate::requests::account::sync_favorites(config, userdata).await?;
+    for dealer in summary.added.iter() {
+        println!("Added {dealer} from the account");
+    }
+    for dealer in summary.removed.iter() {
+        println!("Removed {dealer}, as it was removed from the account");
+    }
+    println!(
+        "Synced favorites, {} added to and {} removed from the account",
+        summary.pushed, summary.deleted
+    );
+    save_favorites(userdata);
+    Ok(())
+}
+
+#[cfg(not(feature = "account"))]
+pub async fn sync(_userdata: &mut UserData) -> Result<()> {
+    anyhow::bail!("Built without account support, build with `--features account` for it")
+}
+
+/// Lists the known dealers, after fetching every dealer from the API with
+/// `update`.
+pub async fn dealers(update: bool, options: &Options) -> Result<()> {
+    if update {
+        let added = registry::update()
+            .await
+            .context("Could not fetch dealers")?;
+        println!("Found {added} new dealers");
+    }
+    if options.prints_json() {
+        let dealers: Vec<DealerListing> = Dealer::all()
+            .into_iter()
+            .map(|dealer| DealerListing {
+                name: dealer.name(),
+                slug: registry::slug(dealer.name()),
+                id: dealer.id(),
+            })
+            .collect();
+        print_json(&dealers);
+    } else {
+        Dealer::list_known_dealers();
+    }
+    Ok(())
+}
+
+/// Lists the nearby stores of the favorite dealers, or of every dealer with
+/// `all`.
+pub async fn stores(userdata: &UserData, all: bool, options: &Options) -> Result<()> {
+    let config = config::get_config();
+    let Some(location) = options.near(&config.stores) else {
+        anyhow::bail!("No location, use --near or set stores.near in the config");
+    };
+    let dealers: Vec<Dealer> = if all {
+        Dealer::all()
+    } else {
+        userdata.favorites.iter().copied().collect()
+    };
+    let radius = options.radius.unwrap_or(config.stores.radius);
+    let stores = stores::nearby_stores(&dealers, &location, radius)
+        .await
+        .context("Could not fetch stores")?;
+    if options.prints_json() {
+        print_json(&stores);
+        return Ok(());
+    }
+    let mut table = style::table();
+    table.set_header(vec!["Dealer", "Address", "Distance"]);
+    for store in stores.iter() {
+        table.add_row(vec![
+            Cell::new(store.dealer),
+            Cell::new(format!(
+                "{}, {} {}",
+                store.street, store.zip_code, store.city
+            )),
+            Cell::new(format!("{:.1} km", store.distance)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    println!("{table}");
+    Ok(())
+}
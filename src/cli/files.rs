@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use comfy_table::{Cell, CellAlignment};
+use std::path::PathBuf;
+
+use super::{confirm, print_json, Options};
+use crate::{config, requests::cache, style};
+
+fn config_path() -> Result<PathBuf> {
+    config::config_path().context("Could not find config dir")
+}
+
+/// Prints where the config file is read from.
+pub fn config_location() -> Result<()> {
+    println!("{}", config_path()?.display());
+    Ok(())
+}
+
+/// Opens the config file in the editor.
+pub fn edit_config() -> Result<()> {
+    config::edit(&config_path()?)
+}
+
+pub fn cache_location() -> Result<()> {
+    println!("{}", cache::root_dir()?.display());
+    Ok(())
+}
+
+/// Shows how old and how big the cached offers of every dealer are.
+pub fn cache_status(options: &Options) -> Result<()> {
+    let root = cache::root_dir()?;
+    let ttl = config::get_config().cache.ttl.map(|ttl| ttl.0);
+    let entries = cache::entries(ttl)?;
+    if options.prints_json() {
+        print_json(&entries);
+        return Ok(());
+    }
+    let mut table = style::table();
+    table.set_header(vec!["Dealer", "Offers", "Fetched", "Fresh", "Size"]);
+    for entry in entries.iter() {
+        table.add_row(vec![
+            Cell::new(&entry.dealer),
+            Cell::new(entry.offers).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{} ago", entry.age)),
+            Cell::new(if entry.fresh { "yes" } else { "no" }),
+            Cell::new(human_size(entry.bytes)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    if !entries.is_empty() {
+        println!("{table}");
+    }
+    println!(
+        "{} offers of {} dealers cached, {} in total in {}",
+        entries.iter().map(|entry| entry.offers).sum::<usize>(),
+        entries.len(),
+        human_size(cache::size_of(&root)),
+        root.display()
+    );
+    Ok(())
+}
+
+/// Removes the cached offers, images, branding and nutrition once confirmed.
+pub fn clear_cache(options: &Options) -> Result<()> {
+    let root = cache::root_dir()?;
+    let question = format!(
+        "Remove the cached offers, images, branding and nutrition from {}?",
+        root.display()
+    );
+    if !confirm(&question, options) {
+        return Ok(());
+    }
+    let freed = cache::clear()?;
+    println!("Cleared the cache, freeing {}", human_size(freed));
+    Ok(())
+}
+
+/// A size in bytes like "12.3 MB".
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        bytes if bytes >= 1_000_000 => format!("{:.1} MB", bytes as f64 / 1_000_000.0),
+        bytes if bytes >= 1_000 => format!("{:.1} kB", bytes as f64 / 1_000.0),
+        bytes => format!("{bytes} B"),
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{bail, Result};
+use comfy_table::{Cell, CellAlignment, Color};
+use std::collections::BTreeMap;
+
+use super::{confirm, print_json, Options};
+use crate::{
+    alerts, diff,
+    duration::Span,
+    locale,
+    requests::{
+        dealer::Dealer,
+        history::{History, PricePoint},
+        offer::{self, retrieve_offers, Column, Offer},
+        userdata::UserData,
+    },
+    style, unit,
+};
+
+/// Shows the past prices of the product and its historical low.
+pub fn price_history(product: &[String], options: &Options) -> Result<()> {
+    if product.is_empty() {
+        bail!("Give a product to show the price history of");
+    }
+    let product = product.join(" ");
+    let points = History::open().and_then(|history| history.prices_of(&product))?;
+    if options.prints_json() {
+        print_json(&points);
+    } else {
+        print_price_history(&product, &points);
+    }
+    Ok(())
+}
+
+/// Removes the prices of offers that ended longer ago than `older_than` once
+/// confirmed.
+pub fn prune(older_than: Span, options: &Options) -> Result<()> {
+    let before = chrono::Utc::now().date_naive() - older_than.0;
+    let question =
+        format!("Remove the prices of offers that ended before {before} from the price history?");
+    if !confirm(&question, options) {
+        return Ok(());
+    }
+    let removed = History::open().and_then(|mut history| history.prune(before))?;
+    println!("Removed {removed} prices");
+    Ok(())
+}
+
+/// Lists the offers of every favorite dealer that are new, removed or changed
+/// since the fetch before the last.
+pub async fn diff(userdata: &mut UserData, options: &Options) {
+    let json = options.prints_json();
+    let offers = retrieve_offers(userdata, false).await;
+    let mut dealers: Vec<Dealer> = userdata.favorites.iter().copied().collect();
+    dealers.sort();
+    let mut previous = Vec::new();
+    for dealer in dealers {
+        match offer::retrieve_previous_offers(dealer) {
+            Ok(offers) => previous.push((dealer, offers)),
+            Err(err) if !json => println!("{err}"),
+            Err(_) => {}
+        }
+    }
+    let current: Vec<(Dealer, Vec<Offer>)> = previous
+        .iter()
+        .map(|(dealer, _)| {
+            let offers = offers
+                .iter()
+                .filter(|offer| offer.dealer == *dealer)
+                .cloned()
+                .collect();
+            (*dealer, offers)
+        })
+        .collect();
+    let diffs: Vec<_> = previous
+        .iter()
+        .zip(&current)
+        .map(|((dealer, before), (_, after))| diff::diff(*dealer, before, after))
+        .collect();
+    if json {
+        print_json(&diffs);
+    } else {
+        print_diffs(&diffs);
+    }
+}
+
+pub fn list_alerts(options: &Options) -> Result<()> {
+    let history = History::open()?;
+    if options.prints_json() {
+        print_json(&history.alerts()?);
+        return Ok(());
+    }
+    alerts::print_alerts(&history)
+}
+
+/// Stops alerting about the offers.
+pub fn acknowledge_alerts(ids: &[String]) -> Result<()> {
+    let mut history = History::open()?;
+    for id in ids {
+        if history.acknowledge_alert(id)? {
+            println!("Acknowledged {id}");
+        } else {
+            eprintln!("No alert for {id}");
+        }
+    }
+    Ok(())
+}
+
+/// Stops alerting about the offers for the duration.
+pub fn snooze_alerts(duration: Span, ids: &[String]) -> Result<()> {
+    let mut history = History::open()?;
+    let until = chrono::Utc::now().naive_utc() + duration.0;
+    for id in ids {
+        if history.snooze_alert(id, until)? {
+            println!("Snoozed {id} until {}", alerts::local_time(until));
+        } else {
+            eprintln!("No alert for {id}");
+        }
+    }
+    Ok(())
+}
+
+fn print_diffs(diffs: &[diff::DealerDiff]) {
+    let offer_line = |offer: &Offer| {
+        format!(
+            "{} ({}, {} - {})",
+            offer.name,
+            locale::money(offer.price),
+            offer.run_from.format("%d/%m"),
+            offer.run_till.format("%d/%m")
+        )
+    };
+    for diff in diffs {
+        if diff.is_empty() {
+            println!(
+                "{}: {}",
+                diff.dealer,
+                locale::text("no changes", "ingen ændringer")
+            );
+            continue;
+        }
+        println!("{}:", diff.dealer);
+        for offer in diff.added.iter() {
+            println!("  + {}", offer_line(offer));
+        }
+        for offer in diff.removed.iter() {
+            println!("  - {}", offer_line(offer));
+        }
+        for change in diff.changed.iter() {
+            let arrow = style::symbol("→", "->");
+            println!(
+                "  ~ {}: {} {arrow} {}",
+                change.after.name,
+                locale::money(change.before.price),
+                locale::money(change.after.price)
+            );
+        }
+    }
+}
+
+/// Prints the past prices along with the lowest cost per unit for every unit.
+fn print_price_history(product: &str, points: &[PricePoint]) {
+    if points.is_empty() {
+        println!(
+            "{} '{product}'",
+            locale::text("No recorded prices for", "Ingen gemte priser på")
+        );
+        return;
+    }
+    let mut lowest: BTreeMap<&str, &PricePoint> = BTreeMap::new();
+    for point in points {
+        let low = lowest.entry(&point.unit).or_insert(point);
+        if point.cost_per_unit < low.cost_per_unit {
+            *low = point;
+        }
+    }
+    let mut table = style::table();
+    table.set_header(vec![
+        locale::text("From", "Fra"),
+        locale::text("Till", "Til"),
+        Column::Dealer.header(),
+        Column::Product.header(),
+        Column::Price.header(),
+        Column::CostPerUnit.header(),
+    ]);
+    for point in points {
+        let is_lowest = lowest
+            .get(point.unit.as_str())
+            .is_some_and(|low| std::ptr::eq(*low, point));
+        let cost = unit::cost(point.cost_per_unit, &point.unit);
+        let cost = if !is_lowest {
+            Cell::new(cost)
+        } else if style::is_ascii() {
+            Cell::new(format!("{cost} ({})", locale::text("lowest", "lavest")))
+        } else {
+            Cell::new(cost).fg(Color::Green)
+        };
+        table.add_row(vec![
+            Cell::new(&point.run_from),
+            Cell::new(&point.run_till),
+            Cell::new(&point.dealer),
+            Cell::new(&point.name),
+            Cell::new(locale::money(point.price)).set_alignment(CellAlignment::Right),
+            cost.set_alignment(CellAlignment::Right),
+        ]);
+    }
+    println!("{table}");
+    for low in lowest.values() {
+        println!(
+            "{}: {} {} {} {} {} ({} - {})",
+            locale::text("Historical low", "Laveste pris"),
+            unit::cost(low.cost_per_unit, &low.unit),
+            locale::text("for", "for"),
+            low.name,
+            locale::text("at", "hos"),
+            low.dealer,
+            low.run_from,
+            low.run_till
+        );
+    }
+}
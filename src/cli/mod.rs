@@ -0,0 +1,297 @@
+pub mod catalogs;
+pub mod export;
+pub mod favorites;
+pub mod files;
+pub mod history;
+pub mod offers;
+pub mod prompt;
+pub mod shopping;
+pub mod stats;
+pub mod table;
+pub mod watch;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use clap::Args;
+use serde::Serialize;
+use std::{borrow::Cow, path::PathBuf};
+
+use crate::{
+    config::Config,
+    dedup::DedupPolicy,
+    density::Basis,
+    diet::{Diet, DietMode},
+    duration::Span,
+    export::{template::Template, OutputFormat},
+    locale::{self, Lang},
+    requests::{
+        offer::Column,
+        stores::{Location, StoresConfig},
+        userdata::{self, UserData},
+    },
+    sort::SortKey,
+    style::{self, ColorChoice},
+    unit::{self, UnitBasis},
+    week::Week,
+};
+
+use table::GroupBy;
+
+/// The flags of the binary, shared by the listing of offers and the
+/// subcommands.
+#[derive(Args, Debug)]
+pub struct Options {
+    pub search: Vec<Cow<'static, str>>,
+    #[arg(short, long, default_value_t = false)]
+    /// Always print offers
+    pub print: bool,
+    /// Output offers as JSON (cannot be combined with other options)
+    #[arg(short, long, global = true)]
+    pub json: bool,
+    /// Output format of the offers
+    #[arg(long, value_enum, global = true, conflicts_with = "json")]
+    pub output: Option<OutputFormat>,
+    /// File to write to for file based output formats [default: offers.<format>]
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+    /// Field delimiter of CSV output. With `;` numbers get decimal commas, for
+    /// spreadsheets using them
+    #[arg(long, default_value_t = ',')]
+    pub delimiter: char,
+
+    /// Search by dealer
+    #[arg(short)]
+    pub dealer: bool,
+    /// Only show offers running in this ISO week (a number, `current` or `next`)
+    #[arg(long, global = true)]
+    pub week: Option<Week>,
+    /// Show the offers that ran on this date (YYYY-MM-DD) according to the
+    /// price history
+    #[arg(long, value_name = "DATE")]
+    pub as_of: Option<NaiveDate>,
+    /// Refresh once, notify about offers matching the search terms (or
+    /// notify.terms) or the watchlist that have not been notified about
+    /// before, and exit
+    #[arg(long)]
+    pub notify_new: bool,
+    /// Only show offers first fetched this week, hiding long-running ones
+    #[arg(long)]
+    pub first_seen_this_week: bool,
+    /// Split the offer table into groups
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+    /// Sort the offers by this key, in ascending order [default: by how well
+    /// they match the search and then cost per unit, with the best last]
+    #[arg(long, value_enum)]
+    pub sort: Option<SortKey>,
+    /// Sort in descending order
+    #[arg(long, requires = "sort")]
+    pub reverse: bool,
+    /// Columns of the offer table, separated by commas [default: columns or
+    /// every column]
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub columns: Vec<Column>,
+    /// Print every offer on a line through a template like
+    /// "{dealer}\t{name}\t{unit_price}". Placeholders: id, dealer, name,
+    /// price, pre_price, unit_price, deposit, unit, size, amount, from, till,
+    /// link
+    #[arg(long, conflicts_with_all = ["json", "output"])]
+    pub format: Option<Template>,
+    /// Leave bottle deposit (pant) included in prices out of the cost per
+    /// unit [default: exclude_deposit]
+    #[arg(long)]
+    pub exclude_deposit: bool,
+    /// Leave out offers with this in the name, on top of the exclude list
+    /// in the config. Can be given more than once
+    #[arg(long, value_name = "TERM")]
+    pub exclude: Vec<String>,
+    /// Only show offers costing at least this many kr
+    #[arg(long, value_name = "KR")]
+    pub min_price: Option<f64>,
+    /// Only show offers ending within this long, e.g. 1d for today and tomorrow
+    #[arg(long, value_name = "DURATION")]
+    pub ends_within: Option<Span>,
+    /// Only show offers still running on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<NaiveDate>,
+    /// Only show offers already running on or before this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    pub until: Option<NaiveDate>,
+    /// Only show offers costing at most this many kr
+    #[arg(long, value_name = "KR")]
+    pub max_price: Option<f64>,
+    /// Only show offers costing at most this many kr per unit (kg, l or
+    /// piece, after --basis)
+    #[arg(long, value_name = "KR")]
+    pub max_unit_price: Option<f64>,
+    /// Only show the best N offers, in the order they are sorted in
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+    /// Only show the best N offers of every dealer, e.g. the 3 cheapest
+    #[arg(long, visible_alias = "top", value_name = "N")]
+    pub top_per_dealer: Option<usize>,
+    /// Dietary constraint to apply, overriding diet.constraints in the config
+    #[arg(long, value_enum)]
+    pub diet: Vec<Diet>,
+    /// Whether to hide or flag offers conflicting with the diet
+    #[arg(long, value_enum)]
+    pub diet_mode: Option<DietMode>,
+    /// Look up product data on Open Food Facts for the dietary check
+    #[arg(long)]
+    pub diet_lookup: bool,
+    /// Show how the cost per unit of every offer was calculated
+    #[arg(long)]
+    pub explain: bool,
+    /// Compare offers sold by weight and by volume in this unit, for products
+    /// with a known density
+    #[arg(long, value_enum)]
+    pub basis: Option<Basis>,
+    /// When offers found by several search terms are merged, overriding dedup
+    /// in the config
+    #[arg(long, value_enum)]
+    pub dedup: Option<DedupPolicy>,
+    /// Show offer names in English
+    #[arg(long)]
+    pub translate: bool,
+    /// Highlight offers ending within this many days
+    #[arg(long, global = true, value_name = "DAYS")]
+    pub warn_days: Option<i64>,
+    /// Only show dealers with a store near coordinates like 55.68,12.57 or a
+    /// zip code [default: stores.near]
+    #[arg(long, global = true, value_name = "LOCATION")]
+    pub near: Option<Location>,
+    /// How far away a store can be in km [default: stores.radius or 5]
+    #[arg(long, global = true, value_name = "KM")]
+    pub radius: Option<f64>,
+    /// What to show unit prices per, separated by commas, e.g. 100g for kr/100g
+    /// on offers sold by weight [default: unit_basis or kg, l and stk]
+    #[arg(long, global = true, value_enum, value_delimiter = ',')]
+    pub unit_basis: Vec<UnitBasis>,
+    /// Plain ASCII output without box drawing or colors, for screen readers
+    #[arg(long, global = true)]
+    pub ascii: bool,
+    /// When to color the output. NO_COLOR turns it off in auto mode
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    pub color: ColorChoice,
+    /// Draw table borders with ASCII characters, for terminals without box
+    /// drawing [default: when TERM is dumb]
+    #[arg(long, global = true)]
+    pub ascii_borders: bool,
+    /// Language of labels, dates and numbers [default: lang or en]
+    #[arg(long, global = true, value_enum)]
+    pub lang: Option<Lang>,
+    /// Show offer periods like "ends in 2 days" or "starts Saturday", and
+    /// flag offers ending today
+    #[arg(long, global = true)]
+    pub relative_dates: bool,
+    /// Never wrap table cells, however wide the table gets
+    #[arg(long, global = true, conflicts_with = "narrow")]
+    pub wide: bool,
+    /// Keep tables within 80 columns, cutting long offer names short
+    #[arg(long, global = true)]
+    pub narrow: bool,
+    /// Print fetching progress as a line per dealer update instead of a
+    /// table, and log HTTP requests, cache decisions and parse warnings
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
+    pub verbose: bool,
+    /// Print nothing but the result, without progress, warnings or notes
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    /// Note how many offers of every fetched catalog were left out while
+    /// parsing, and why
+    #[arg(long, global = true)]
+    pub show_skipped: bool,
+    /// Keep fetched offers that lack a name or dates, using the description
+    /// and the dates of the catalog instead
+    #[arg(long, global = true)]
+    pub lenient: bool,
+    /// Skip confirmation prompts for destructive operations
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+    /// Fetch the offers even when the cache is fresh
+    #[arg(long, global = true, conflicts_with = "offline")]
+    pub refresh: bool,
+    /// Never use the network, only cached offers and data
+    #[arg(long, global = true)]
+    pub offline: bool,
+}
+
+impl Options {
+    /// Fills in the flags that were not given from the config, and applies
+    /// the language, dates and unit bases to the output.
+    pub fn apply_config(&mut self, defaults: &Config) {
+        if self.output.is_none() && !self.json {
+            self.output = defaults.output;
+        }
+        if self.sort.is_none() {
+            self.sort = defaults.sort;
+            self.reverse = defaults.reverse;
+        }
+        if self.columns.is_empty() {
+            self.columns = defaults.columns.clone();
+        }
+        style::set_relative_dates(self.relative_dates || defaults.relative_dates);
+        locale::set_lang(self.lang.or(defaults.lang).unwrap_or_default());
+        unit::set_bases(if self.unit_basis.is_empty() {
+            defaults.unit_basis.clone()
+        } else {
+            std::mem::take(&mut self.unit_basis)
+        });
+    }
+
+    /// Whether to print JSON, by `--json` or `--output json`.
+    pub fn prints_json(&self) -> bool {
+        self.json || matches!(self.output, Some(OutputFormat::Json))
+    }
+
+    /// The location from `--near`, or else from the config.
+    fn near(&self, config: &StoresConfig) -> Option<Location> {
+        if self.near.is_some() {
+            return self.near.clone();
+        }
+        let near = config.near.as_ref()?;
+        match near.parse() {
+            Ok(location) => Some(location),
+            Err(err) => {
+                tracing::warn!("Ignoring stores.near in config: {err:#}");
+                None
+            }
+        }
+    }
+}
+
+/// The user data, with the favorites from the config when none are set.
+pub fn load_userdata() -> Result<UserData> {
+    let mut userdata = userdata::get_userdata()?;
+    if userdata.favorites.is_empty() {
+        userdata
+            .favorites
+            .extend(crate::config::get_config().favorites.iter().copied());
+    }
+    Ok(userdata)
+}
+
+pub fn print_json(value: &impl Serialize) {
+    println!(
+        "{}",
+        serde_json::to_string(value).expect("output serializes to JSON")
+    );
+}
+
+/// Saves changed favorites right away, so they are kept even when refreshing
+/// the offers afterwards fails.
+fn save_favorites(userdata: &UserData) {
+    if let Err(err) = userdata.save() {
+        tracing::error!("Failed to save favorites: {err:#}");
+    }
+}
+
+/// Asks to confirm a destructive operation, printing that it was aborted
+/// otherwise.
+fn confirm(question: &str, options: &Options) -> bool {
+    let confirmed = prompt::confirm(question, options.yes);
+    if !confirmed {
+        println!("Aborted.");
+    }
+    confirmed
+}
@@ -0,0 +1,570 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use comfy_table::{Cell, CellAlignment};
+use serde::Serialize;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use super::{
+    print_json,
+    table::{self, offer_header, offer_table, TableOptions},
+    Options,
+};
+use crate::{
+    config,
+    dedup::{self, DedupPolicy},
+    density,
+    diet::{self, DietMode},
+    export::{self, OutputFormat},
+    locale,
+    requests::{
+        dealer::{self, Dealer},
+        history::History,
+        nutrition,
+        offer::{self, retrieve_offers, Column, Offer},
+        stores,
+        userdata::UserData,
+    },
+    score, search, share, style, suggest, translate,
+    week::Week,
+};
+
+#[derive(Serialize)]
+struct ScoredOffer<'a> {
+    #[serde(flatten)]
+    offer: &'a Offer,
+    score: f64,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum NutritionMetric {
+    /// Kr per 100 g protein
+    Protein,
+    /// Kr per 1000 kcal
+    Kcal,
+}
+
+/// Lists the offers matching the search terms and the flags, as a table or
+/// in the output format.
+pub async fn list(userdata: &mut UserData, options: &Options) -> Result<()> {
+    let config = config::get_config();
+    let mut offers = search(
+        userdata,
+        &options.search,
+        options.dealer,
+        options.dedup.unwrap_or(config.dedup),
+        options.as_of,
+    )
+    .await?;
+    let excluded: Vec<String> = config
+        .exclude
+        .iter()
+        .chain(&options.exclude)
+        .cloned()
+        .collect();
+    offers.retain(|offer| !offer.matches_any(&excluded));
+    if let Some(location) = options.near(&config.stores) {
+        let radius = options.radius.unwrap_or(config.stores.radius);
+        let dealers: Vec<Dealer> = userdata.favorites.iter().copied().collect();
+        match stores::nearby_stores(&dealers, &location, radius).await {
+            Ok(stores) => {
+                let nearby = stores::dealers_with_stores(&stores);
+                offers.retain(|offer| nearby.contains(&offer.dealer));
+            }
+            Err(err) => {
+                tracing::warn!("Could not find nearby stores, showing every dealer: {err:#}")
+            }
+        }
+    }
+    if options.exclude_deposit || config.exclude_deposit {
+        let adjusted = offers
+            .iter_mut()
+            .map(|offer| offer.exclude_deposit())
+            .filter(|&adjusted| adjusted)
+            .count();
+        if adjusted > 0 {
+            tracing::info!("Left the deposit out of the cost per unit of {adjusted} offers");
+        }
+    }
+    if let Some(basis) = options.basis {
+        let converted = density::convert_offers(&mut offers, basis, &config.density);
+        if converted > 0 {
+            tracing::info!(
+                "Converted the cost per unit of {converted} offers using product densities"
+            );
+        }
+    }
+    match options.sort {
+        Some(key) => key.sort(&mut offers, options.reverse),
+        // Best matches and then the cheapest last, so they are closest to
+        // the prompt
+        None if !options.search.is_empty() && !options.dealer => {
+            search::rank(&mut offers, &options.search);
+            offers.reverse();
+        }
+        // Cheapest last, so it is closest to the prompt
+        None => {
+            offers.sort_unstable_by(|a, b| a.cost_per_unit.total_cmp(&b.cost_per_unit).reverse())
+        }
+    }
+    offers.retain(|offer| {
+        options.min_price.is_none_or(|min| offer.price >= min)
+            && options.max_price.is_none_or(|max| offer.price <= max)
+            && options
+                .max_unit_price
+                .is_none_or(|max| offer.cost_per_unit <= max)
+    });
+    if let Some(within) = &options.ends_within {
+        let today = chrono::Utc::now().date_naive();
+        offers.retain(|offer| offer.run_till >= today && offer.ends_within(within.0.num_days()));
+    }
+    offers.retain(|offer| {
+        options.since.is_none_or(|since| offer.run_till >= since)
+            && options.until.is_none_or(|until| offer.run_from <= until)
+    });
+    if let Some(week) = options.week {
+        offers.retain(|offer| week.overlaps(offer));
+        if offers.is_empty() && week > Week::current() {
+            tracing::info!(
+                "No offers for {week} yet, catalogs are usually published a few days ahead."
+            );
+        }
+    }
+
+    if options.first_seen_this_week {
+        let week_start = Week::current().first_day();
+        match History::open().and_then(|history| history.first_seen()) {
+            Ok(first_seen) => offers.retain(|offer| {
+                first_seen
+                    .get(&offer.id)
+                    .is_none_or(|&first_seen| first_seen >= week_start)
+            }),
+            Err(err) => tracing::error!("Could not read price history: {err:#}"),
+        }
+    }
+
+    let diet = &config.diet;
+    let diets = if options.diet.is_empty() {
+        diet.constraints.clone()
+    } else {
+        options.diet.clone()
+    };
+    let mut flags = HashMap::new();
+    if !diets.is_empty() {
+        let products = if options.diet_lookup {
+            nutrition::lookup_nutrition(&offers).await
+        } else {
+            nutrition::cached_products()
+        };
+        let conflicts = diet::conflicts(&offers, &diets, &products);
+        match options.diet_mode.unwrap_or(diet.mode) {
+            DietMode::Filter => offers.retain(|offer| !conflicts.contains_key(&offer.id)),
+            DietMode::Flag => flags = conflicts,
+        }
+    }
+    if options.limit.is_some() || options.top_per_dealer.is_some() {
+        // The best offers are the first ones with --sort, and otherwise the
+        // last ones, closest to the prompt
+        let best_last = options.sort.is_none();
+        if best_last {
+            offers.reverse();
+        }
+        if let Some(top) = options.top_per_dealer {
+            let mut kept: BTreeMap<Dealer, usize> = BTreeMap::new();
+            offers.retain(|offer| {
+                let kept = kept.entry(offer.dealer).or_default();
+                *kept += 1;
+                *kept <= top
+            });
+        }
+        if let Some(limit) = options.limit {
+            offers.truncate(limit);
+        }
+        if best_last {
+            offers.reverse();
+        }
+    }
+    if options.translate {
+        translate::translate_offers(&mut offers, &config.translate);
+    }
+    let table_options = TableOptions {
+        columns: if options.columns.is_empty() {
+            Column::ALL.to_vec()
+        } else {
+            options.columns.clone()
+        },
+        group_by: options.group_by,
+        warn_days: options.warn_days,
+        flags,
+        explain: options.explain,
+        highlight: if options.dealer || !style::use_color() {
+            Vec::new()
+        } else {
+            options.search.iter().map(|term| term.to_string()).collect()
+        },
+        usual_prices: if style::use_color() {
+            score::usual_unit_prices(&offers)
+        } else {
+            HashMap::new()
+        },
+    };
+
+    if let Some(template) = &options.format {
+        for offer in offers.iter() {
+            println!("{}", template.render(offer));
+        }
+        return Ok(());
+    }
+
+    let file = |extension: &str| {
+        options
+            .file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("offers.{extension}")))
+    };
+    match options.output {
+        Some(OutputFormat::Csv) => {
+            let path = file("csv");
+            export::csv::write(&offers, &path, options.delimiter)?;
+            println!("Wrote {} offers to {}", offers.len(), path.display());
+            return Ok(());
+        }
+        Some(OutputFormat::Xlsx) => {
+            let path = file("xlsx");
+            export::xlsx::write(&offers, &path)?;
+            println!("Wrote {} offers to {}", offers.len(), path.display());
+            return Ok(());
+        }
+        Some(OutputFormat::Alfred) => {
+            println!("{}", export::alfred::to_json(&offers));
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let print = options.print || matches!(options.output, Some(OutputFormat::Table));
+    match (options.prints_json(), print) {
+        (true, true) => bail!("`json` and other options are mutually exclusive"),
+        (true, false) => {
+            println!("{}", serde_json::to_string(&offers).expect("dude what?"));
+        }
+        (false, true) => table::print_offers(&offers, &table_options),
+        (false, false) if offers.is_empty() && !options.search.is_empty() => {}
+        (false, false) if !options.search.is_empty() => {
+            table::print_offers(&offers, &table_options)
+        }
+        (false, false) => {
+            println!(
+                "{}: {}",
+                locale::text("Amount of offers", "Antal tilbud"),
+                offers.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The offers matching the search items, or every offer without any. With a
+/// date they are the offers of that date from the price history.
+async fn search(
+    userdata: &mut UserData,
+    search_items: &[Cow<'_, str>],
+    search_by_dealer: bool,
+    dedup_policy: DedupPolicy,
+    as_of: Option<NaiveDate>,
+) -> Result<Vec<Offer>> {
+    if let Some(date) = as_of {
+        let offers = History::open()
+            .and_then(|history| history.offers_on(date))
+            .context("Could not read price history")?;
+        return Ok(if search_items.is_empty() {
+            offers
+        } else {
+            filter_search(offers, search_items, search_by_dealer, dedup_policy)
+        });
+    }
+    let offers = retrieve_offers(userdata, false).await;
+    Ok(if search_items.is_empty() {
+        offers
+    } else {
+        filter_search(offers, search_items, search_by_dealer, dedup_policy)
+    })
+}
+
+/// The offers matching any of the search items.
+fn filter_search(
+    all_offers: Vec<Offer>,
+    search_items: &[Cow<'_, str>],
+    search_by_dealer: bool,
+    dedup_policy: DedupPolicy,
+) -> Vec<Offer> {
+    let mut offers = Vec::new();
+    for search in search_items {
+        let mut temp = all_offers.clone();
+        if search_by_dealer {
+            if let Ok(dealer) = Dealer::from_str(search) {
+                temp.retain(|offer| offer.dealer == dealer);
+            } else {
+                tracing::warn!(
+                    "Search term did not match any known dealers: {search}, \
+                    `dealers` lists the known ones"
+                );
+            }
+        } else {
+            let (matching, rest): (Vec<Offer>, Vec<Offer>) =
+                temp.into_iter().partition(|offer| offer.matches(search));
+            temp = matching;
+            if temp.is_empty() {
+                let suggestions = suggest::did_you_mean(search, &rest);
+                if suggestions.is_empty() {
+                    tracing::info!("No results for '{search}'");
+                } else {
+                    tracing::info!(
+                        "No results for '{search}' {} did you mean {}?",
+                        style::symbol("—", "-"),
+                        suggestions
+                            .iter()
+                            .map(|suggestion| format!("'{suggestion}'"))
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    );
+                }
+            }
+        }
+        offers.extend(temp);
+    }
+    dedup::dedup(&mut offers, dedup_policy);
+    offers
+}
+
+/// Prints the matching offers as JSON lines while they are being fetched.
+pub async fn stream_ndjson(userdata: &mut UserData, options: &Options) {
+    let excluded: Vec<String> = config::get_config()
+        .exclude
+        .iter()
+        .chain(&options.exclude)
+        .cloned()
+        .collect();
+    let search = &options.search;
+    let search_by_dealer = options.dealer;
+    let dealers: Vec<Dealer> = if search_by_dealer {
+        search
+            .iter()
+            .filter_map(|search| Dealer::from_str(search).ok())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let wanted = |offer: &Offer| {
+        let matches_search = if search_by_dealer {
+            dealers.contains(&offer.dealer)
+        } else {
+            search.is_empty() || search.iter().any(|term| offer.matches(term))
+        };
+        matches_search
+            && options.week.is_none_or(|week| week.overlaps(offer))
+            && !offer.matches_any(&excluded)
+    };
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<Vec<Offer>>();
+    let print = async {
+        // Catalogs are sent before the offers repeated across them are merged
+        let mut duplicates = offer::CatalogDuplicates::default();
+        let mut printed = 0;
+        while let Some(offers) = receiver.recv().await {
+            for offer in offers.iter().filter(|offer| wanted(offer)) {
+                if duplicates.check(offer, printed).is_some() {
+                    continue;
+                }
+                printed += 1;
+                println!(
+                    "{}",
+                    serde_json::to_string(offer).expect("offers serialize to JSON")
+                );
+            }
+        }
+    };
+    tokio::join!(
+        offer::retrieve_offers_streaming(userdata, false, Some(sender)),
+        print
+    );
+}
+
+/// Prints everything known about the current offer with the id.
+pub async fn show(userdata: &mut UserData, id: &str, options: &Options) -> Result<()> {
+    let offers = retrieve_offers(userdata, false).await;
+    match offers.iter().find(|offer| offer.id == id) {
+        Some(offer) if options.prints_json() => print_json(offer),
+        Some(offer) => table::print_offer_details(offer),
+        None => bail!("No current offer with id {id}"),
+    }
+    Ok(())
+}
+
+/// Opens the current offer with the id, or the catalog page it is on, in the
+/// browser.
+pub async fn open(userdata: &mut UserData, id: &str, catalog: bool) -> Result<()> {
+    let offers = retrieve_offers(userdata, false).await;
+    let Some(offer) = offers.iter().find(|offer| offer.id == id) else {
+        bail!("No current offer with id {id}");
+    };
+    let url = if catalog {
+        match offer.sources.first() {
+            Some(source) => share::catalog_link(source),
+            None => bail!("Not known which catalog {} is in", offer.name),
+        }
+    } else {
+        share::offer_link(offer)
+    };
+    share::open(&url).with_context(|| format!("Could not open {url}"))
+}
+
+/// Lists the offers ending within the days, soonest first.
+pub async fn expiring(
+    userdata: &mut UserData,
+    days: i64,
+    search: &[String],
+    options: &Options,
+) -> Result<()> {
+    let mut offers = retrieve_offers(userdata, false).await;
+    let today = chrono::Utc::now().date_naive();
+    offers.retain(|offer| {
+        offer.run_till >= today
+            && offer.ends_within(days)
+            && (search.is_empty() || search.iter().any(|term| offer.matches(term)))
+    });
+    offers.sort_by(|a, b| (a.run_till, &a.name).cmp(&(b.run_till, &b.name)));
+    if options.prints_json() {
+        print_json(&offers);
+        return Ok(());
+    }
+    let mut table = offer_table();
+    for offer in offers.iter() {
+        table.add_row(offer.to_table_entry(options.warn_days.or(Some(1))));
+    }
+    println!("{table}");
+    println!(
+        "{} {days} {}: {}",
+        locale::text("Offers ending within", "Tilbud der slutter inden for"),
+        locale::text("days", "dage"),
+        offers.len()
+    );
+    Ok(())
+}
+
+/// Lists the best deals across the favorites with their score.
+pub async fn top(userdata: &mut UserData, count: usize, options: &Options) -> Result<()> {
+    let offers = retrieve_offers(userdata, false).await;
+    if options.prints_json() {
+        let scored: Vec<ScoredOffer> = score::top_offers(&offers, count)
+            .into_iter()
+            .map(|(offer, score)| ScoredOffer { offer, score })
+            .collect();
+        print_json(&scored);
+        return Ok(());
+    }
+    let mut table = offer_table();
+    table.set_header(
+        offer_header()
+            .into_iter()
+            .chain([locale::text("Score", "Point")]),
+    );
+    for (offer, score) in score::top_offers(&offers, count) {
+        let mut row = offer.to_table_entry(options.warn_days);
+        row.push(Cell::new(format!("{score:.0}")).set_alignment(CellAlignment::Right));
+        table.add_row(row);
+    }
+    println!("{table}");
+    Ok(())
+}
+
+/// Lists the most viewed offers of the favorite dealers.
+pub async fn popular(userdata: &UserData, count: usize, options: &Options) -> Result<()> {
+    let favorites: Vec<Dealer> = userdata.favorites.iter().copied().collect();
+    let offers = dealer::retrieve_popular_offers(&favorites, count)
+        .await
+        .context("Could not fetch popular offers")?;
+    if options.prints_json() {
+        print_json(&offers);
+        return Ok(());
+    }
+    let mut table = offer_table();
+    for offer in offers.iter() {
+        table.add_row(offer.to_table_entry(options.warn_days));
+    }
+    println!("{table}");
+    Ok(())
+}
+
+/// Ranks the offers matching the items by the price of their protein or
+/// energy.
+pub async fn nutrition(
+    userdata: &mut UserData,
+    by: NutritionMetric,
+    max: Option<f64>,
+    search: &[String],
+    options: &Options,
+) -> Result<()> {
+    let mut offers = retrieve_offers(userdata, false).await;
+    offers.retain(|offer| search.iter().any(|term| offer.matches(term)));
+    let nutrition = nutrition::lookup_nutrition(&offers).await;
+    let mut rows: Vec<(&Offer, Option<f64>, Option<f64>, &str)> = offers
+        .iter()
+        .filter_map(|offer| {
+            let facts = nutrition.get(&offer.name)?;
+            Some((
+                offer,
+                facts.kr_per_100g_protein(offer),
+                facts.kr_per_1000_kcal(offer),
+                facts.product_name.as_deref().unwrap_or_default(),
+            ))
+        })
+        .filter(|(_, protein, kcal, _)| {
+            let metric = match by {
+                NutritionMetric::Protein => protein,
+                NutritionMetric::Kcal => kcal,
+            };
+            metric.is_some_and(|metric| max.is_none_or(|max| metric <= max))
+        })
+        .collect();
+    rows.sort_by(|a, b| match by {
+        NutritionMetric::Protein => a.1.unwrap_or(f64::MAX).total_cmp(&b.1.unwrap_or(f64::MAX)),
+        NutritionMetric::Kcal => a.2.unwrap_or(f64::MAX).total_cmp(&b.2.unwrap_or(f64::MAX)),
+    });
+
+    let mut table = offer_table();
+    table.set_header(offer_header().into_iter().chain([
+        locale::text("kr/100 g protein", "kr/100 g protein"),
+        locale::text("kr/1000 kcal", "kr/1000 kcal"),
+        locale::text("Nutrition from", "Næringsindhold fra"),
+    ]));
+    let format_metric = |metric: Option<f64>| {
+        Cell::new(
+            metric
+                .map(|metric| locale::decimal(metric, 2))
+                .unwrap_or_default(),
+        )
+        .set_alignment(CellAlignment::Right)
+    };
+    for (offer, protein, kcal, product_name) in rows.iter() {
+        let mut row = offer.to_table_entry(options.warn_days);
+        row.push(format_metric(*protein));
+        row.push(format_metric(*kcal));
+        row.push(Cell::new(product_name));
+        table.add_row(row);
+    }
+    println!("{table}");
+    println!(
+        "{}: {} {} {}",
+        locale::text("Offers with nutrition facts", "Tilbud med næringsindhold"),
+        rows.len(),
+        locale::text("of", "af"),
+        offers.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use std::io::{IsTerminal, Write};
+
+/// Asks the user to confirm a destructive operation.
+///
+/// Returns `true` straight away when `assume_yes` is set. When stdin is not a
+/// terminal (e.g. when run from cron) nothing can be answered, so the operation
+/// is refused unless `--yes` was given.
+pub fn confirm(question: &str, assume_yes: bool) -> bool {
+    if assume_yes {
+        return true;
+    }
+    if !std::io::stdin().is_terminal() {
+        eprintln!("{question} Refusing without confirmation, pass `--yes` to proceed.");
+        return false;
+    }
+    print!("{question} [y/N] ");
+    if std::io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
@@ -0,0 +1,180 @@
+use anyhow::{bail, Result};
+use comfy_table::{Cell, CellAlignment, Color};
+use std::num::NonZeroUsize;
+
+use super::{confirm, print_json, save_favorites, Options};
+use crate::{
+    basket, locale,
+    requests::{
+        dealer::Dealer,
+        offer::{retrieve_offers, Column, Offer},
+        userdata::UserData,
+    },
+    style, unit,
+};
+
+/// Finds the favorite dealers where the items, or the shopping list without
+/// any, are cheapest.
+pub async fn basket(
+    userdata: &mut UserData,
+    mut items: Vec<String>,
+    max_stores: Option<NonZeroUsize>,
+    options: &Options,
+) -> Result<()> {
+    if items.is_empty() {
+        items = userdata.shopping_list.clone();
+    }
+    if items.is_empty() {
+        bail!("No items given and the shopping list is empty, add some with `list add`");
+    }
+    let offers = retrieve_offers(userdata, false).await;
+    let plan = basket::optimize(&items, &offers, max_stores.map(|max| max.get()));
+    if options.prints_json() {
+        print_json(&plan);
+    } else {
+        print_basket(&plan);
+    }
+    Ok(())
+}
+
+/// Compares the best offer of the product at every dealer.
+pub async fn compare(userdata: &mut UserData, product: &[String], options: &Options) {
+    let product = product.join(" ");
+    let offers = retrieve_offers(userdata, false).await;
+    let best = basket::best_unit_price_per_dealer(&offers, &product);
+    if options.prints_json() {
+        print_json(&best);
+    } else if best.is_empty() {
+        println!(
+            "{} '{product}'",
+            locale::text("No offers of", "Ingen tilbud på")
+        );
+    } else {
+        print_comparison(&best);
+    }
+}
+
+pub fn show_list(userdata: &UserData, options: &Options) {
+    if options.prints_json() {
+        print_json(&userdata.shopping_list);
+        return;
+    }
+    for item in userdata.shopping_list.iter() {
+        println!("{item}");
+    }
+}
+
+pub fn add_to_list(userdata: &mut UserData, items: Vec<String>) {
+    for item in items {
+        if !userdata.shopping_list.contains(&item) {
+            userdata.shopping_list.push(item);
+        }
+    }
+    save_favorites(userdata);
+}
+
+pub fn remove_from_list(userdata: &mut UserData, items: &[String]) -> Result<()> {
+    let before = userdata.shopping_list.len();
+    userdata.shopping_list.retain(|item| !items.contains(item));
+    if userdata.shopping_list.len() == before {
+        bail!("Not on the shopping list");
+    }
+    save_favorites(userdata);
+    Ok(())
+}
+
+/// Removes every item from the shopping list once confirmed.
+pub fn clear_list(userdata: &mut UserData, options: &Options) {
+    if userdata.shopping_list.is_empty() {
+        return;
+    }
+    let question = format!(
+        "Clear the {} items of the shopping list?",
+        userdata.shopping_list.len()
+    );
+    if !confirm(&question, options) {
+        return;
+    }
+    userdata.shopping_list.clear();
+    save_favorites(userdata);
+}
+
+fn print_basket(plan: &basket::Plan) {
+    let mut table = style::table();
+    table.set_header(vec![
+        locale::text("Item", "Vare"),
+        Column::Dealer.header(),
+        Column::Product.header(),
+        Column::Price.header(),
+        Column::CostPerUnit.header(),
+    ]);
+    for pick in plan.picks.iter() {
+        match pick.offer {
+            Some(offer) => table.add_row(vec![
+                Cell::new(pick.item),
+                Cell::new(offer.dealer),
+                Cell::new(&offer.name),
+                Cell::new(locale::money(offer.price)).set_alignment(CellAlignment::Right),
+                Cell::new(unit::cost(offer.cost_per_unit, offer.unit))
+                    .set_alignment(CellAlignment::Right),
+            ]),
+            None => table.add_row(vec![Cell::new(pick.item), Cell::new("-")]),
+        };
+    }
+    println!("{table}");
+    let dealers: Vec<String> = plan.dealers.iter().map(Dealer::to_string).collect();
+    println!(
+        "{}: {}",
+        locale::text("Dealers", "Butikker"),
+        dealers.join(", ")
+    );
+    println!(
+        "{}: {}",
+        locale::text("Total", "I alt"),
+        locale::money(plan.total)
+    );
+    let missing = plan.missing();
+    if missing > 0 {
+        println!(
+            "{missing} {}",
+            locale::text("items not on offer", "varer er ikke på tilbud")
+        );
+    }
+}
+
+/// Prints the best offer of every dealer, highlighting the cheapest one.
+fn print_comparison(best: &[&Offer]) {
+    let mut table = style::table();
+    table.set_header(
+        [
+            Column::Dealer,
+            Column::Product,
+            Column::Price,
+            Column::CostPerUnit,
+            Column::Period,
+        ]
+        .map(|column| column.header()),
+    );
+    for (index, offer) in best.iter().enumerate() {
+        let cost = unit::cost(offer.cost_per_unit, offer.unit);
+        let cost = if index > 0 {
+            Cell::new(cost)
+        } else if style::is_ascii() {
+            Cell::new(format!("{cost} ({})", locale::text("cheapest", "billigst")))
+        } else {
+            Cell::new(cost).fg(Color::Green)
+        };
+        table.add_row(vec![
+            Cell::new(offer.dealer),
+            Cell::new(&offer.name),
+            Cell::new(locale::money(offer.price)).set_alignment(CellAlignment::Right),
+            cost.set_alignment(CellAlignment::Right),
+            Cell::new(format!(
+                "{} - {}",
+                offer.run_from.format("%d/%m"),
+                offer.run_till.format("%d/%m")
+            )),
+        ]);
+    }
+    println!("{table}");
+}
@@ -0,0 +1,66 @@
+use anyhow::{bail, Result};
+
+use super::{print_json, Options};
+use crate::{
+    config, locale,
+    requests::{cache, history::History, offer::retrieve_offers, userdata::UserData},
+    stats,
+};
+
+/// Shows offer counts, unit prices, catalogs and cache freshness.
+pub async fn summary(userdata: &mut UserData, options: &Options) {
+    let offers = retrieve_offers(userdata, false).await;
+    let ttl = config::get_config().cache.ttl.map(|ttl| ttl.0);
+    let entries = cache::entries(ttl).unwrap_or_else(|err| {
+        tracing::warn!("Could not read the offer cache: {err:#}");
+        Vec::new()
+    });
+    let summary = stats::summary(&offers, &entries);
+    if options.prints_json() {
+        print_json(&summary);
+        return;
+    }
+    let (dealers, unit_prices) = stats::summary_tables(&summary);
+    if !summary.dealers.is_empty() {
+        println!("{dealers}");
+    }
+    if !summary.unit_prices.is_empty() {
+        println!("{unit_prices}");
+    }
+    println!(
+        "{} {} {} {} {} {}",
+        summary.offers,
+        locale::text("offers in", "tilbud i"),
+        summary.catalogs,
+        locale::text("catalogs from", "kataloger fra"),
+        summary.dealers.len(),
+        locale::text("dealers", "butikker")
+    );
+}
+
+/// Summarizes the active offers of every favorite dealer.
+pub async fn dealers(userdata: &mut UserData, options: &Options) {
+    let offers = retrieve_offers(userdata, false).await;
+    let stats = stats::dealer_stats(&offers);
+    if options.prints_json() {
+        print_json(&stats);
+    } else {
+        println!("{}", stats::dealer_stats_table(&stats));
+    }
+}
+
+/// Shows a price index of the basket from the config per dealer and month.
+pub fn inflation(options: &Options) -> Result<()> {
+    let config = config::get_config();
+    if config.basket.is_empty() {
+        bail!("No basket, add products and weights under [basket] in the config");
+    }
+    let points = History::open().and_then(|history| history.price_points())?;
+    let indices = stats::inflation_index(&points, &config.basket);
+    if options.prints_json() {
+        print_json(&indices);
+    } else {
+        println!("{}", stats::inflation_table(&indices, config.basket.len()));
+    }
+    Ok(())
+}
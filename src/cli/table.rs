@@ -0,0 +1,250 @@
+use clap::ValueEnum;
+use comfy_table::{Cell, Color, Table};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    locale, logging,
+    requests::{
+        dealer::Dealer,
+        history::History,
+        offer::{Column, Offer},
+    },
+    score, search, share,
+    style::{self, TableWidth},
+    unit,
+    week::Week,
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum GroupBy {
+    /// One table per ISO week the offers start in
+    Week,
+    /// One table per dealer, with the cheapest offer of each
+    Dealer,
+}
+
+pub(super) struct TableOptions {
+    pub(super) columns: Vec<Column>,
+    pub(super) group_by: Option<GroupBy>,
+    pub(super) warn_days: Option<i64>,
+    /// Warnings to show below offer names, by offer id
+    pub(super) flags: HashMap<String, String>,
+    /// Print how the cost per unit of every offer was calculated
+    pub(super) explain: bool,
+    /// Search terms to highlight in offer names
+    pub(super) highlight: Vec<String>,
+    /// Average unit prices of earlier offers, by offer id, to color unit
+    /// prices by
+    pub(super) usual_prices: HashMap<String, f64>,
+}
+
+impl TableOptions {
+    fn row(&self, offer: &Offer) -> Vec<Cell> {
+        let mut row = offer.to_table_entry(self.warn_days);
+        let name = match style::table_width() {
+            TableWidth::Narrow => style::truncate(&offer.name, style::NARROW_NAME_LENGTH),
+            _ => offer.name.clone(),
+        };
+        let name = style::highlight(&name, &search::match_ranges(&name, &self.highlight));
+        if let Some(&usual) = self.usual_prices.get(&offer.id) {
+            // Within 5% of the average is the usual price
+            if offer.cost_per_unit < usual * 0.95 {
+                row[5] = row[5].clone().fg(Color::Green);
+            } else if offer.cost_per_unit > usual * 1.05 {
+                row[5] = row[5].clone().fg(Color::Red);
+            }
+        }
+        if let Some(flag) = self.flags.get(&offer.id) {
+            let name = if style::is_ascii() {
+                format!("{name} (warning: {flag})")
+            } else {
+                format!("{name}\n⚠ {flag}")
+            };
+            row[2] = Cell::new(name).fg(Color::Red);
+        } else if name != offer.name {
+            row[2] = Cell::new(name);
+        }
+        self.columns
+            .iter()
+            .map(|column| row[column.index()].clone())
+            .collect()
+    }
+
+    fn table(&self) -> Table {
+        offer_table_with(self.columns.iter().map(Column::header))
+    }
+}
+
+pub(super) fn print_offers(offers: &[Offer], options: &TableOptions) {
+    print_anomalies(offers);
+    match options.group_by {
+        None => {
+            let mut table = options.table();
+            for offer in offers {
+                table.add_row(options.row(offer));
+            }
+            println!("{}", table);
+        }
+        Some(GroupBy::Week) => {
+            let mut weeks: BTreeMap<Week, Vec<&Offer>> = BTreeMap::new();
+            for offer in offers {
+                weeks
+                    .entry(Week::of(offer.run_from))
+                    .or_default()
+                    .push(offer);
+            }
+            for (week, offers) in weeks {
+                let mut table = options.table();
+                for offer in offers.iter() {
+                    table.add_row(options.row(offer));
+                }
+                println!(
+                    "{week}: {} {}",
+                    offers.len(),
+                    locale::text("offers", "tilbud")
+                );
+                println!("{}", table);
+            }
+        }
+        Some(GroupBy::Dealer) => {
+            let mut dealers: BTreeMap<Dealer, Vec<&Offer>> = BTreeMap::new();
+            for offer in offers {
+                dealers.entry(offer.dealer).or_default().push(offer);
+            }
+            for (dealer, offers) in dealers {
+                let mut table = options.table();
+                for offer in offers.iter() {
+                    table.add_row(options.row(offer));
+                }
+                println!(
+                    "{dealer}: {} {}",
+                    offers.len(),
+                    locale::text("offers", "tilbud")
+                );
+                println!("{}", table);
+                if let Some(cheapest) = offers
+                    .iter()
+                    .min_by(|a, b| a.cost_per_unit.total_cmp(&b.cost_per_unit))
+                {
+                    println!(
+                        "{} {dealer}: {}, {}",
+                        locale::text("Cheapest at", "Billigst hos"),
+                        cheapest.name,
+                        unit::cost(cheapest.cost_per_unit, cheapest.unit)
+                    );
+                }
+            }
+        }
+    }
+    if options.explain {
+        println!("{}:", locale::text("Cost per unit", "Pris pr. enhed"));
+        for offer in offers {
+            println!("  {} ({}): {}", offer.name, offer.dealer, offer.explain());
+        }
+    }
+    println!(
+        "{}: {}",
+        locale::text("Amount of offers", "Antal tilbud"),
+        offers.len()
+    );
+}
+
+pub(super) fn print_offer_details(offer: &Offer) {
+    let row = offer.to_table_entry(None);
+    let mut table = style::table();
+    let label = |en, da| locale::text(en, da).to_string();
+    table.add_row(vec![label("Product", "Produkt"), offer.name.clone()]);
+    table.add_row(vec![label("Dealer", "Butik"), offer.dealer.to_string()]);
+    table.add_row(vec![
+        label("Period", "Periode"),
+        format!(
+            "{} - {}",
+            offer.run_from.format("%d/%m/%Y"),
+            offer.run_till.format("%d/%m/%Y")
+        ),
+    ]);
+    table.add_row(vec![label("Price", "Pris"), row[4].content()]);
+    if let Some(deposit) = offer.deposit {
+        table.add_row(vec![label("Deposit", "Pant"), locale::money(deposit)]);
+    }
+    if let Some(pre_price) = offer.pre_price {
+        table.add_row(vec![
+            label("Price before", "Førpris"),
+            locale::money(pre_price),
+        ]);
+    }
+    table.add_row(vec![label("Count", "Antal"), row[3].content()]);
+    table.add_row(vec![label("Weight", "Vægt"), row[6].content()]);
+    table.add_row(vec![label("Cost/unit", "Pris/enhed"), offer.explain()]);
+    let sources: Vec<String> = offer.sources.iter().map(ToString::to_string).collect();
+    if !sources.is_empty() {
+        table.add_row(vec![label("Found in", "Fundet i"), sources.join("\n")]);
+    }
+    table.add_row(vec![label("Link", "Link"), share::offer_link(offer)]);
+    if let Some(image_url) = &offer.image_url {
+        table.add_row(vec![label("Image", "Billede"), image_url.clone()]);
+    }
+    println!("{table}");
+}
+
+/// Prints offers far below their usual price, except for acknowledged and
+/// snoozed alerts.
+pub(super) fn print_anomalies(offers: &[Offer]) {
+    if logging::is_quiet() {
+        return;
+    }
+    let Ok(mut history) = History::open() else {
+        return;
+    };
+    let mut anomalies = score::price_anomalies(offers, &history);
+    anomalies.retain(|anomaly| !history.is_silenced(&anomaly.offer.id).unwrap_or(false));
+    if anomalies.is_empty() {
+        return;
+    }
+    println!(
+        "{}",
+        locale::text(
+            "Unusually cheap compared to earlier offers, possibly pricing errors:",
+            "Usædvanligt billigt i forhold til tidligere tilbud, måske en prisfejl:"
+        )
+    );
+    for anomaly in anomalies.iter() {
+        let offer = anomaly.offer;
+        println!(
+            "  ! {} ({}): {} {} {} [{}]",
+            offer.name,
+            offer.dealer,
+            unit::cost(offer.cost_per_unit, offer.unit),
+            locale::text("instead of usually", "i stedet for normalt"),
+            unit::cost(anomaly.usual_unit_price, offer.unit),
+            offer.id
+        );
+    }
+    println!(
+        "{}",
+        locale::text(
+            "Silence these with `alerts ack <id>` or `alerts snooze <duration> <id>`.",
+            "Slå dem fra med `alerts ack <id>` eller `alerts snooze <duration> <id>`."
+        )
+    );
+    let alerted: Vec<&Offer> = anomalies.iter().map(|anomaly| anomaly.offer).collect();
+    if let Err(err) = history.mark_alerted(&alerted) {
+        tracing::error!("Could not record alerts: {err:#}");
+    }
+}
+
+/// The headers of a row from `Offer::to_table_entry`.
+pub(super) fn offer_header() -> [&'static str; 7] {
+    Column::ALL.map(|column| column.header())
+}
+
+pub(super) fn offer_table() -> Table {
+    offer_table_with(offer_header())
+}
+
+pub(super) fn offer_table_with<T: Into<Cell>>(header: impl IntoIterator<Item = T>) -> Table {
+    let mut table = style::table();
+    style::fit(&mut table);
+    table.set_header(header);
+    table
+}
@@ -0,0 +1,129 @@
+use anyhow::{bail, Result};
+use comfy_table::Cell;
+
+use super::{
+    print_json, save_favorites,
+    table::{offer_header, offer_table, print_anomalies},
+    Options,
+};
+use crate::{
+    config, locale, notify,
+    requests::{
+        offer::{self, retrieve_offers, Offer},
+        userdata::UserData,
+    },
+    watch::{self, Watch},
+};
+
+/// Watches the product, replacing an earlier watch of it.
+pub fn add(userdata: &mut UserData, term: String, max: f64, unit: Option<String>) {
+    userdata.watchlist.retain(|watch| watch.term != term);
+    println!(
+        "Watching {term} at {}/{} or less",
+        locale::money(max),
+        unit.as_deref().unwrap_or("unit")
+    );
+    userdata.watchlist.push(Watch { term, max, unit });
+    save_favorites(userdata);
+}
+
+pub fn remove(userdata: &mut UserData, term: &str) -> Result<()> {
+    let before = userdata.watchlist.len();
+    userdata.watchlist.retain(|watch| watch.term != term);
+    if userdata.watchlist.len() == before {
+        bail!("Not watching {term}");
+    }
+    save_favorites(userdata);
+    Ok(())
+}
+
+pub fn list(userdata: &UserData, options: &Options) {
+    if options.prints_json() {
+        print_json(&userdata.watchlist);
+        return;
+    }
+    let mut table = crate::style::table();
+    table.set_header(vec![
+        locale::text("Product", "Produkt"),
+        locale::text("Max cost/unit", "Maks. pris/enhed"),
+    ]);
+    for watch in userdata.watchlist.iter() {
+        let unit = watch.unit.as_deref().unwrap_or("unit");
+        table.add_row(vec![
+            watch.term.clone(),
+            format!("{}/{unit}", locale::money(watch.max)),
+        ]);
+    }
+    println!("{table}");
+}
+
+/// Prints the current offers of watched products at or below their target,
+/// returning whether there are any.
+pub async fn check(userdata: &mut UserData, options: &Options) -> bool {
+    let watchlist = userdata.watchlist.clone();
+    let offers = retrieve_offers(userdata, false).await;
+    let matches = watch::check(&watchlist, &offers);
+    if options.prints_json() {
+        let offers: Vec<&Offer> = matches.iter().map(|(_, offer)| *offer).collect();
+        print_json(&offers);
+    } else if !matches.is_empty() {
+        let mut table = offer_table();
+        table.set_header(
+            offer_header()
+                .into_iter()
+                .chain([locale::text("Watch", "Overvågning")]),
+        );
+        for (watch, offer) in matches.iter() {
+            let mut row = offer.to_table_entry(options.warn_days);
+            row.push(Cell::new(&watch.term));
+            table.add_row(row);
+        }
+        println!("{table}");
+        // An offer can match several watches, but is flagged once
+        let mut matched: Vec<Offer> = matches.iter().map(|(_, offer)| (*offer).clone()).collect();
+        matched.sort_by(|a, b| a.id.cmp(&b.id));
+        matched.dedup_by(|a, b| a.id == b.id);
+        print_anomalies(&matched);
+    }
+    !matches.is_empty()
+}
+
+/// Fetches the offers again and notifies about new deals on watched products.
+pub async fn notify(userdata: &mut UserData) -> Result<()> {
+    let config = config::get_config();
+    if userdata.watchlist.is_empty() {
+        bail!("Nothing to notify about, add products with watch add");
+    }
+    notify::notify_new_catalogs();
+    let previous = offer::retrieve_cached_offers().unwrap_or_default();
+    let watchlist = userdata.watchlist.clone();
+    let offers = retrieve_offers(userdata, true).await;
+    let deals = notify::new_watch_deals(&watchlist, &previous, &offers);
+    if !deals.is_empty() {
+        notify::notify_deals(&config.notify, &deals).await?;
+    }
+    tracing::info!("Found {} new deals", deals.len());
+    Ok(())
+}
+
+/// Refreshes once and notifies about offers matching the search terms, or
+/// notify.terms, or the watchlist, that have not been notified about before.
+pub async fn notify_new(userdata: &mut UserData, options: &Options) -> Result<()> {
+    let config = config::get_config();
+    let terms: Vec<String> = if options.search.is_empty() {
+        config.notify.terms.clone()
+    } else {
+        options.search.iter().map(|term| term.to_string()).collect()
+    };
+    if terms.is_empty() && userdata.watchlist.is_empty() {
+        bail!(
+            "Nothing to notify about, give search terms, set notify.terms in the config \
+            or add products with `watch add`"
+        );
+    }
+    notify::notify_new_catalogs();
+    let offers = retrieve_offers(userdata, true).await;
+    let count = notify::notify_new(&config.notify, &offers, &terms, &userdata.watchlist).await?;
+    tracing::info!("Sent {count} notifications");
+    Ok(())
+}
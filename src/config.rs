@@ -0,0 +1,230 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use crate::{
+    dedup::DedupPolicy,
+    diet::{Diet, DietMode},
+    export::OutputFormat,
+    locale::Lang,
+    notify::NotifyConfig,
+    requests::{
+        cache::CacheConfig, dealer::Dealer, offer::Column, stores::StoresConfig, HttpConfig,
+    },
+    share::ShareConfig,
+    snapshot::SnapshotConfig,
+    sort::SortKey,
+    translate::TranslateConfig,
+    unit::UnitBasis,
+};
+
+/// Settings read from `config.toml` in the `etilbudsavis` config directory.
+/// Every key is optional.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Config {
+    #[cfg(feature = "account")]
+    pub account: crate::requests::account::AccountConfig,
+    pub sheets: SheetsConfig,
+    pub diet: DietConfig,
+    pub translate: TranslateConfig,
+    pub share: ShareConfig,
+    pub dedup: DedupPolicy,
+    pub http: HttpConfig,
+    pub cache: CacheConfig,
+    pub notify: NotifyConfig,
+    pub snapshot: SnapshotConfig,
+    pub stores: StoresConfig,
+    /// Extra densities in kg per liter by product name, e.g. `havredrik = 1.03`
+    pub density: HashMap<String, f64>,
+    /// Products in the basket for `stats inflation` along with their weight,
+    /// e.g. `mælk = 4`
+    pub basket: BTreeMap<String, f64>,
+    /// Dealers to use when no favorites have been added
+    pub favorites: Vec<Dealer>,
+    /// Sort order of offers when `--sort` is not given
+    pub sort: Option<SortKey>,
+    /// Reverse the sort order from `sort`
+    pub reverse: bool,
+    /// Output format when neither `--output` nor `--json` is given
+    pub output: Option<OutputFormat>,
+    /// Columns of the offer table when `--columns` is not given, e.g.
+    /// `["dealer", "product", "price"]` [default: every column]
+    pub columns: Vec<Column>,
+    /// Language of the output when `--lang` is not given, `en` or `da`
+    pub lang: Option<Lang>,
+    /// Show offer periods like "ends tomorrow", like `--relative-dates`
+    pub relative_dates: bool,
+    /// Leave bottle deposit (pant) out of the cost per unit, like
+    /// `--exclude-deposit`
+    pub exclude_deposit: bool,
+    /// What to show unit prices per when `--unit-basis` is not given, e.g.
+    /// `["100g", "l"]`
+    pub unit_basis: Vec<UnitBasis>,
+    /// Saved searches to run with `query <name>`, e.g.
+    /// `coffee = "kaffe OR espresso --max-unit-price 80"`
+    pub queries: BTreeMap<String, String>,
+    /// Offers never to show, by terms in their name like `--exclude`, e.g.
+    /// `["tobak", "vin"]`
+    pub exclude: Vec<String>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct SheetsConfig {
+    /// Path to the JSON key of a Google service account
+    pub credentials: Option<PathBuf>,
+    pub spreadsheet_id: Option<String>,
+    /// Name of the sheet (tab) to write to
+    pub sheet: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct DietConfig {
+    /// e.g. `["gluten-free", "lactose-free", "vegetarian"]`
+    pub constraints: Vec<Diet>,
+    pub mode: DietMode,
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("etilbudsavis/config.toml"))
+}
+
+/// Written by `config edit` when there is no config yet.
+const TEMPLATE: &str = r#"# Every key is optional, flags given on the command line win.
+
+# Dealers to use when no favorites have been added
+# favorites = ["Netto", "Rema1000"]
+
+# sort = "unit-price"
+# reverse = false
+# output = "table"
+# columns = ["period", "dealer", "product", "price", "cost-per-unit"]
+# relative_dates = false
+# lang = "en"
+# exclude = ["tobak", "cigaretter"]
+
+# [cache]
+# ttl = "6h"
+
+# Saved searches, run with `query coffee`
+# [queries]
+# coffee = "kaffe OR espresso --max-unit-price 80"
+"#;
+
+/// The words of a saved search the way a shell would split them, so quoted
+/// terms like `"hel kylling"` stay one term. `OR` between terms is left out,
+/// as offers matching any of the terms are shown anyway.
+pub fn query_args(query: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in query.chars() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        anyhow::bail!("Unclosed quote in {query}");
+    }
+    words.extend(word);
+    words.retain(|word| word != "OR");
+    Ok(words)
+}
+
+/// Opens the config in `$VISUAL` or `$EDITOR`, or `vi` without either,
+/// creating it from a commented template first when it does not exist.
+pub fn edit(path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, TEMPLATE)
+            .with_context(|| format!("Could not create {}", path.display()))?;
+    }
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Run through the shell, so editors with arguments like "code -w" work
+    let status = Command::new("sh")
+        .args(["-c", &format!("{editor} \"$1\""), "sh"])
+        .arg(path)
+        .status()
+        .with_context(|| format!("Could not start {editor}"))?;
+    if !status.success() {
+        anyhow::bail!("{editor} exited with {status}");
+    }
+    if let Ok(Err(err)) = std::fs::read_to_string(path).map(|data| toml::from_str::<Config>(&data))
+    {
+        eprintln!("The config is not valid: {err}");
+    }
+    Ok(())
+}
+
+/// The config as read the first time it is needed. Without a valid config
+/// every key has its default.
+pub fn get_config() -> &'static Config {
+    &load().0
+}
+
+/// Logs why the config is ignored, if it is not valid. Reading the config
+/// does not, as it can happen before logging is set up.
+pub fn warn_if_invalid() {
+    if let Some(err) = &load().1 {
+        tracing::warn!("{err}");
+    }
+}
+
+/// The config, along with why it was ignored.
+fn load() -> &'static (Config, Option<String>) {
+    static CONFIG: OnceLock<(Config, Option<String>)> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let Some(path) = config_path() else {
+            return (Config::default(), None);
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(data) => match toml::from_str(&data) {
+                Ok(config) => (config, None),
+                Err(err) => (
+                    Config::default(),
+                    Some(format!("Ignoring invalid config {}: {err}", path.display())),
+                ),
+            },
+            Err(_) => (Config::default(), None),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_args_splits_like_a_shell() {
+        assert_eq!(query_args("kaffe  mælk").unwrap(), ["kaffe", "mælk"]);
+        assert_eq!(
+            query_args("\"hel kylling\" OR 'and bryst'").unwrap(),
+            ["hel kylling", "and bryst"]
+        );
+        assert_eq!(query_args("--dealer netto").unwrap(), ["--dealer", "netto"]);
+        assert!(query_args("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn query_args_rejects_unclosed_quotes() {
+        assert!(query_args("\"hel kylling").is_err());
+    }
+}
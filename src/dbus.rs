@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use zbus::{interface, object_server::SignalEmitter, Connection};
+
+use etilbudsavis_cli::{
+    watch::{self, Watch},
+    Offer,
+};
+
+use crate::serve::Shared;
+
+/// Well-known name the service is registered under on the session bus.
+const NAME: &str = "dk.etilbudsavis.Offers";
+const PATH: &str = "/dk/etilbudsavis/Offers";
+
+/// The served offers, as seen by desktop applets.
+struct Offers {
+    served: Shared,
+}
+
+#[interface(name = "dk.etilbudsavis.Offers1")]
+impl Offers {
+    /// The offers matching the query as JSON, cheapest per unit first.
+    #[zbus(out_args("offers"))]
+    async fn search(&self, query: &str) -> zbus::fdo::Result<String> {
+        let served = self.served.read().await;
+        let mut offers: Vec<&Offer> = served
+            .offers
+            .iter()
+            .filter(|offer| offer.matches(query))
+            .collect();
+        offers.sort_by(|a, b| a.cost_per_unit.total_cmp(&b.cost_per_unit));
+        serde_json::to_string(&offers).map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Offers matching the watchlist that were not there before the offers
+    /// were refreshed, as JSON.
+    #[zbus(signal)]
+    async fn new_matches(emitter: &SignalEmitter<'_>, offers: &str) -> zbus::Result<()>;
+}
+
+/// The service on the session bus, along with the watchlist matches that
+/// have been announced.
+pub(crate) struct Bus {
+    connection: Connection,
+    announced: HashSet<String>,
+}
+
+impl Bus {
+    /// Registers the service on the session bus. The watchlist matches among
+    /// the offers served now are not announced.
+    pub(crate) async fn register(served: Shared, watchlist: &[Watch]) -> Result<Self> {
+        let announced = watch::check(watchlist, &served.read().await.offers)
+            .into_iter()
+            .map(|(_, offer)| offer.id.clone())
+            .collect();
+        let connection = zbus::connection::Builder::session()?
+            .name(NAME)?
+            .serve_at(PATH, Offers { served })?
+            .build()
+            .await
+            .context("Could not register on the D-Bus session bus")?;
+        tracing::info!("Registered {NAME} on the session bus");
+        Ok(Bus {
+            connection,
+            announced,
+        })
+    }
+
+    /// Sends `NewMatches` with the watchlist matches among the refreshed
+    /// offers that have not been announced yet.
+    pub(crate) async fn refreshed(&mut self, watchlist: &[Watch], offers: &[Offer]) {
+        let new: Vec<&Offer> = watch::check(watchlist, offers)
+            .into_iter()
+            .map(|(_, offer)| offer)
+            .filter(|offer| self.announced.insert(offer.id.clone()))
+            .collect();
+        if new.is_empty() {
+            return;
+        }
+        let result = async {
+            let json = serde_json::to_string(&new)?;
+            let emitter = SignalEmitter::new(&self.connection, PATH)?;
+            Offers::new_matches(&emitter, &json).await?;
+            anyhow::Ok(())
+        };
+        if let Err(err) = result.await {
+            tracing::warn!("Could not announce new matches on D-Bus: {err:#}");
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::requests::offer::Offer;
+
+/// When two offers found by different search terms count as the same offer.
+#[derive(Clone, Copy, Debug, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DedupPolicy {
+    /// Same id, or same dealer, name and period
+    #[default]
+    Auto,
+    /// Same id only
+    Id,
+    /// Same dealer, name and period, even with different ids
+    Name,
+    /// Never merge offers
+    Off,
+}
+
+impl DedupPolicy {
+    fn same(&self, a: &Offer, b: &Offer) -> bool {
+        let same_name = || {
+            a.dealer == b.dealer
+                && a.name == b.name
+                && a.run_from == b.run_from
+                && a.run_till == b.run_till
+        };
+        match self {
+            DedupPolicy::Auto => a == b,
+            DedupPolicy::Id => a.id == b.id,
+            DedupPolicy::Name => same_name(),
+            DedupPolicy::Off => false,
+        }
+    }
+}
+
+/// Sorts the offers by name and dealer and removes duplicates.
+pub fn dedup(offers: &mut Vec<Offer>, policy: DedupPolicy) {
+    offers.sort_unstable_by(|a, b| {
+        (&a.name, &a.dealer, a.run_from, a.run_till, &a.id)
+            .cmp(&(&b.name, &b.dealer, b.run_from, b.run_till, &b.id))
+    });
+    offers.dedup_by(|a, b| policy.same(a, b));
+}
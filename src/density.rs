@@ -0,0 +1,96 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+use crate::{requests::offer::Offer, unit::Unit};
+
+/// Unit to compare the cost of offers sold by weight and by volume in.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Basis {
+    /// Kr per kg
+    Kg,
+    /// Kr per liter
+    L,
+}
+
+/// Densities in kg per liter of common products sold by volume.
+const DENSITIES: &[(&str, f64)] = &[
+    ("mælk", 1.03),
+    ("kærnemælk", 1.03),
+    ("fløde", 1.01),
+    ("yoghurt", 1.04),
+    ("skyr", 1.05),
+    ("olie", 0.92),
+    ("juice", 1.05),
+    ("saft", 1.25),
+    ("sodavand", 1.04),
+    ("cola", 1.04),
+    ("vand", 1.0),
+    ("øl", 1.01),
+    ("vin", 0.99),
+    ("eddike", 1.01),
+    ("sirup", 1.33),
+    ("honning", 1.42),
+    ("ketchup", 1.15),
+    ("mayonnaise", 0.91),
+    ("is", 0.55),
+];
+
+/// The density of the product, using the longest matching word. `extra`
+/// comes from the config and takes precedence over the built-in table.
+fn density(name: &str, extra: &HashMap<String, f64>) -> Option<f64> {
+    let name = name.to_lowercase();
+    let words: Vec<&str> = name.split(|c: char| !c.is_alphanumeric()).collect();
+    let matches = |keyword: &str| {
+        if keyword.chars().count() > 3 {
+            name.contains(keyword)
+        } else {
+            // Short words like "is" only count at the start of a word, e.g.
+            // "isvafler" but not "ris".
+            words.iter().any(|word| word.starts_with(keyword))
+        }
+    };
+    longest_match(
+        extra
+            .iter()
+            .map(|(keyword, &density)| (keyword.as_str(), density)),
+        matches,
+    )
+    .or_else(|| longest_match(DENSITIES.iter().copied(), matches))
+}
+
+fn longest_match<'a>(
+    densities: impl Iterator<Item = (&'a str, f64)>,
+    matches: impl Fn(&str) -> bool,
+) -> Option<f64> {
+    densities
+        .filter(|(keyword, _)| matches(keyword))
+        .max_by_key(|(keyword, _)| keyword.chars().count())
+        .map(|(_, density)| density)
+}
+
+/// Converts the cost per unit of offers sold in the other unit to the basis,
+/// when the density of the product is known. Returns how many were converted.
+pub fn convert_offers(offers: &mut [Offer], basis: Basis, extra: &HashMap<String, f64>) -> usize {
+    let (from, to) = match basis {
+        Basis::Kg => (Unit::L, Unit::Kg),
+        Basis::L => (Unit::Kg, Unit::L),
+    };
+    let mut converted = 0;
+    for offer in offers.iter_mut().filter(|offer| offer.unit == from) {
+        let Some(density) = density(&offer.name, extra) else {
+            continue;
+        };
+        // Amount of the new unit per old unit
+        let factor = match basis {
+            Basis::Kg => density,
+            Basis::L => 1.0 / density,
+        };
+        offer.min_size *= factor;
+        offer.max_size *= factor;
+        offer.cost_per_unit /= factor;
+        offer.si_factor = offer.si_factor.map(|si_factor| si_factor * factor);
+        offer.unit = to;
+        converted += 1;
+    }
+    converted
+}
@@ -0,0 +1,198 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::requests::{nutrition::Nutrition, offer::Offer};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Diet {
+    GlutenFree,
+    LactoseFree,
+    Vegetarian,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DietMode {
+    /// Hide offers that conflict with the diet
+    #[default]
+    Filter,
+    /// Show offers that conflict with the diet with a warning
+    Flag,
+}
+
+impl Diet {
+    /// Words in offer names suggesting a product does not fit the diet.
+    fn conflicting_words(&self) -> &'static [&'static str] {
+        match self {
+            Diet::GlutenFree => &[
+                "hvede",
+                "rug",
+                "byg",
+                "brød",
+                "bolle",
+                "pasta",
+                "spaghetti",
+                "lasagne",
+                "kage",
+                "kiks",
+                "toast",
+                "pizza",
+                "øl",
+                "müsli",
+                "havregryn",
+                "couscous",
+                "bulgur",
+            ],
+            Diet::LactoseFree => &[
+                "mælk",
+                "fløde",
+                "ost",
+                "smør",
+                "yoghurt",
+                "skyr",
+                "creme fraiche",
+                "cremefraiche",
+                "kvark",
+                "ymer",
+                "kærnemælk",
+                "flødeis",
+                "mozzarella",
+                "feta",
+            ],
+            Diet::Vegetarian => &[
+                "kød",
+                "okse",
+                "svin",
+                "gris",
+                "kylling",
+                "kalkun",
+                "and",
+                "lam",
+                "kalv",
+                "bacon",
+                "skinke",
+                "pølse",
+                "salami",
+                "frikadelle",
+                "leverpostej",
+                "paté",
+                "fisk",
+                "laks",
+                "torsk",
+                "sild",
+                "makrel",
+                "tun",
+                "rejer",
+                "hamburgerryg",
+                "medister",
+                "mørbrad",
+                "bøf",
+                "filet",
+            ],
+        }
+    }
+
+    /// Words marking a product as made for the diet, overriding the above.
+    fn fitting_words(&self) -> &'static [&'static str] {
+        match self {
+            Diet::GlutenFree => &["glutenfri"],
+            Diet::LactoseFree => &[
+                "laktosefri",
+                "plantebaseret",
+                "havredrik",
+                "sojadrik",
+                "vegansk",
+            ],
+            Diet::Vegetarian => &["vegetar", "vegansk", "plantebaseret", "veggie"],
+        }
+    }
+
+    fn allergen(&self) -> Option<&'static str> {
+        match self {
+            Diet::GlutenFree => Some("en:gluten"),
+            Diet::LactoseFree => Some("en:milk"),
+            Diet::Vegetarian => None,
+        }
+    }
+
+    fn labels(&self) -> &'static [&'static str] {
+        match self {
+            Diet::GlutenFree => &["en:gluten-free", "en:no-gluten"],
+            Diet::LactoseFree => &["en:lactose-free", "en:no-lactose"],
+            Diet::Vegetarian => &["en:vegetarian", "en:vegan"],
+        }
+    }
+
+    /// Why the offer conflicts with the diet, if it does. Product data from
+    /// Open Food Facts takes precedence over the name heuristics.
+    pub fn conflict(&self, offer: &Offer, product: Option<&Nutrition>) -> Option<String> {
+        if let Some(product) = product {
+            if self
+                .labels()
+                .iter()
+                .any(|label| product.labels.iter().any(|l| l == label))
+            {
+                return None;
+            }
+            if let Some(allergen) = self.allergen() {
+                if product.allergens.iter().any(|a| a == allergen) {
+                    return Some(format!("contains {}", allergen.trim_start_matches("en:")));
+                }
+            }
+        }
+
+        let name = offer.name.to_lowercase();
+        let words: Vec<&str> = name
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        if self.fitting_words().iter().any(|word| name.contains(word)) {
+            return None;
+        }
+        self.conflicting_words()
+            .iter()
+            .find(|&&conflicting| {
+                if conflicting.chars().count() > 3 {
+                    name.contains(conflicting)
+                } else {
+                    // Short words like "and" or "rug" only count at the start of
+                    // a word, e.g. "rugbrød" but not "brugsen".
+                    words.iter().any(|word| word.starts_with(conflicting))
+                }
+            })
+            .map(|word| format!("{self} ({word})"))
+    }
+}
+
+impl std::fmt::Display for Diet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Diet::GlutenFree => "not gluten-free",
+            Diet::LactoseFree => "not lactose-free",
+            Diet::Vegetarian => "not vegetarian",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The reason every offer conflicting with one of the diets does so, by
+/// offer id.
+pub fn conflicts(
+    offers: &[Offer],
+    diets: &[Diet],
+    products: &HashMap<String, Nutrition>,
+) -> HashMap<String, String> {
+    offers
+        .iter()
+        .filter_map(|offer| {
+            let product = products.get(&offer.name);
+            let reasons: Vec<String> = diets
+                .iter()
+                .filter_map(|diet| diet.conflict(offer, product))
+                .collect();
+            (!reasons.is_empty()).then(|| (offer.id.clone(), reasons.join(", ")))
+        })
+        .collect()
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::requests::{dealer::Dealer, offer::Offer};
+
+#[derive(Serialize, Debug)]
+pub struct PriceChange<'a> {
+    pub before: &'a Offer,
+    pub after: &'a Offer,
+}
+
+/// How the offers of a dealer changed between two fetches.
+#[derive(Serialize, Debug)]
+pub struct DealerDiff<'a> {
+    pub dealer: Dealer,
+    pub added: Vec<&'a Offer>,
+    pub removed: Vec<&'a Offer>,
+    pub changed: Vec<PriceChange<'a>>,
+}
+
+impl DealerDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares the offers of a dealer from two fetches. Offers are the same when
+/// they have the same id, or otherwise the same name, as a new catalog gives
+/// the offers that return from the last one new ids.
+pub fn diff<'a>(dealer: Dealer, before: &'a [Offer], after: &'a [Offer]) -> DealerDiff<'a> {
+    let mut unmatched: HashMap<&str, &Offer> = before
+        .iter()
+        .map(|offer| (offer.id.as_str(), offer))
+        .collect();
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    let mut by_name = Vec::new();
+    for offer in after {
+        match unmatched.remove(offer.id.as_str()) {
+            Some(earlier) => compare(earlier, offer, &mut changed),
+            None => by_name.push(offer),
+        }
+    }
+    // Several offers can have the same name, e.g. in different sizes
+    let mut unmatched_names: HashMap<String, Vec<&Offer>> = HashMap::new();
+    for offer in unmatched.into_values() {
+        unmatched_names
+            .entry(offer.name.to_lowercase())
+            .or_default()
+            .push(offer);
+    }
+    for offer in by_name {
+        let earlier = unmatched_names
+            .get_mut(&offer.name.to_lowercase())
+            .and_then(Vec::pop);
+        match earlier {
+            Some(earlier) => compare(earlier, offer, &mut changed),
+            None => added.push(offer),
+        }
+    }
+    let mut removed: Vec<&Offer> = unmatched_names.into_values().flatten().collect();
+    added.sort_by(|a, b| a.name.cmp(&b.name));
+    removed.sort_by(|a, b| a.name.cmp(&b.name));
+    changed.sort_by(|a, b| a.after.name.cmp(&b.after.name));
+    DealerDiff {
+        dealer,
+        added,
+        removed,
+        changed,
+    }
+}
+
+fn compare<'a>(before: &'a Offer, after: &'a Offer, changed: &mut Vec<PriceChange<'a>>) {
+    // Prices are in kr with øre, so anything smaller is rounding
+    if (before.price - after.price).abs() >= 0.005 {
+        changed.push(PriceChange { before, after });
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{anyhow, Context};
+use chrono::Duration;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// A length of time written as a number and a unit, e.g. `3d`, `12h` or `2w`.
+/// Spans are always positive.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Span(pub Duration);
+
+impl TryFrom<String> for Span {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for Span {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.len() - s.chars().last().map_or(0, char::len_utf8);
+        let (amount, unit) = s.split_at(split);
+        let amount: i64 = amount
+            .parse()
+            .with_context(|| format!("Invalid duration: {s}"))?;
+        if amount <= 0 {
+            return Err(anyhow!("Invalid duration: {s}, it must be longer than 0"));
+        }
+        let duration = match unit {
+            "h" => Duration::try_hours(amount),
+            "d" => Duration::try_days(amount),
+            "w" => Duration::try_weeks(amount),
+            _ => return Err(anyhow!("Invalid duration: {s}, use e.g. 12h, 3d or 2w")),
+        };
+        duration
+            .map(Span)
+            .ok_or_else(|| anyhow!("Invalid duration: {s} is too long"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spans() {
+        assert_eq!("12h".parse::<Span>().unwrap().0, Duration::hours(12));
+        assert_eq!(" 3d ".parse::<Span>().unwrap().0, Duration::days(3));
+        assert_eq!("2w".parse::<Span>().unwrap().0, Duration::weeks(2));
+    }
+
+    #[test]
+    fn rejects_spans_that_are_not_positive() {
+        for input in ["0d", "-1d", "-3w", "0h"] {
+            assert!(input.parse::<Span>().is_err(), "{input} parsed");
+        }
+    }
+
+    #[test]
+    fn rejects_spans_that_are_too_long() {
+        for input in ["99999999999w", "9999999999999999d", "99999999999999999999h"] {
+            assert!(input.parse::<Span>().is_err(), "{input} parsed");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_spans() {
+        for input in ["", "d", "3", "3x", "3 d", "1.5d", "tod"] {
+            assert!(input.parse::<Span>().is_err(), "{input} parsed");
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use reqwest::StatusCode;
+
+/// What went wrong talking to the API. The library returns `anyhow::Error`s,
+/// which have one of these in their chain when the API was the problem, see
+/// [`api_error`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Network(#[from] reqwest::Error),
+    #[error("API returned {0}")]
+    Status(StatusCode),
+    #[error("API returned invalid JSON")]
+    Json(#[from] serde_json::Error),
+    #[error("API did not return a list, it may have changed")]
+    NotAList,
+    /// Every element of a list lacked fields or had them in another shape
+    #[error("none of the {skipped} {what} could be read, the API may have changed")]
+    MissingFields { what: &'static str, skipped: usize },
+    #[error("Network access is disabled by --offline")]
+    Offline,
+}
+
+impl Error {
+    /// Exit code of the CLI when failing because of this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Network(_) => 3,
+            Error::Status(_) => 4,
+            Error::Json(_) | Error::NotAList => 5,
+            Error::MissingFields { .. } => 6,
+            Error::Offline => 7,
+        }
+    }
+}
+
+/// The API error that caused the error, if any.
+pub fn api_error(err: &anyhow::Error) -> Option<&Error> {
+    err.chain().find_map(|cause| cause.downcast_ref::<Error>())
+}
+
+/// Exit code of the CLI when failing with the error, 1 when the API was not
+/// the problem.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    api_error(err).map_or(1, Error::exit_code)
+}
@@ -0,0 +1,39 @@
+use serde::Serialize;
+
+use crate::{locale, requests::offer::Offer, share, unit};
+
+#[derive(Serialize)]
+struct ScriptFilter<'a> {
+    items: Vec<Item<'a>>,
+}
+
+/// An item in the format Alfred script filters (and Raycast) read.
+#[derive(Serialize)]
+struct Item<'a> {
+    uid: &'a str,
+    title: &'a str,
+    subtitle: String,
+    arg: String,
+}
+
+/// The offers as Alfred script filter JSON, opening the offer on etilbudsavis.dk
+/// when an item is selected.
+pub fn to_json(offers: &[Offer]) -> String {
+    let items = offers
+        .iter()
+        .map(|offer| Item {
+            uid: &offer.id,
+            title: &offer.name,
+            subtitle: format!(
+                "{} · {} · {} · {} - {}",
+                offer.dealer,
+                locale::money(offer.price),
+                unit::cost(offer.cost_per_unit, offer.unit),
+                offer.run_from.format("%d/%m"),
+                offer.run_till.format("%d/%m")
+            ),
+            arg: share::offer_link(offer),
+        })
+        .collect();
+    serde_json::to_string(&ScriptFilter { items }).expect("offers serialize to JSON")
+}
@@ -0,0 +1,37 @@
+use anyhow::{Context, Result};
+use std::{fmt::Write, path::Path};
+
+use super::csv_field;
+use crate::requests::history::PricePoint;
+
+const HEADER: [&str; 7] = [
+    "dealer",
+    "product",
+    "price",
+    "cost_per_unit",
+    "unit",
+    "valid_from",
+    "valid_till",
+];
+
+/// Writes the price history as CSV for the community price dataset. Only
+/// public offer data is included: no offer ids, favorites, searches or when
+/// the offers were fetched.
+pub fn write(points: &[PricePoint], path: &Path) -> Result<()> {
+    let mut csv = HEADER.join(",");
+    csv.push('\n');
+    for point in points {
+        writeln!(
+            csv,
+            "{},{},{:.2},{:.2},{},{},{}",
+            csv_field(&point.dealer, ','),
+            csv_field(&point.name, ','),
+            point.price,
+            point.cost_per_unit,
+            csv_field(&point.unit, ','),
+            point.run_from,
+            point.run_till
+        )?;
+    }
+    std::fs::write(path, csv).with_context(|| format!("Could not write {}", path.display()))
+}
@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use std::{fmt::Write, path::Path};
+
+use super::csv_field;
+use crate::requests::offer::Offer;
+
+const HEADER: [&str; 15] = [
+    "id",
+    "dealer",
+    "product",
+    "price",
+    "price_before",
+    "cost_per_unit",
+    "deposit",
+    "unit",
+    "min_size",
+    "max_size",
+    "min_count",
+    "max_count",
+    "valid_from",
+    "valid_till",
+    "image_url",
+];
+
+/// Writes every field of the offers as CSV with a header row, separating
+/// fields with `delimiter`. With `;` numbers are written with decimal
+/// commas, the way spreadsheets in Danish read them.
+pub fn write(offers: &[Offer], path: &Path, delimiter: char) -> Result<()> {
+    let mut csv = HEADER.join(&delimiter.to_string());
+    csv.push('\n');
+    let decimal = |number: String| {
+        if delimiter == ';' {
+            number.replace('.', ",")
+        } else {
+            number
+        }
+    };
+    let money = |amount: f64| decimal(format!("{amount:.2}"));
+    for offer in offers {
+        let fields = [
+            csv_field(&offer.id, delimiter),
+            csv_field(&offer.dealer.to_string(), delimiter),
+            csv_field(&offer.name, delimiter),
+            money(offer.price),
+            offer.pre_price.map(money).unwrap_or_default(),
+            money(offer.cost_per_unit),
+            offer.deposit.map(money).unwrap_or_default(),
+            csv_field(offer.unit.symbol(), delimiter),
+            decimal(offer.min_size.to_string()),
+            decimal(offer.max_size.to_string()),
+            offer.min_amount.to_string(),
+            offer.max_amount.to_string(),
+            offer.run_from.to_string(),
+            offer.run_till.to_string(),
+            csv_field(offer.image_url.as_deref().unwrap_or_default(), delimiter),
+        ];
+        writeln!(csv, "{}", fields.join(&delimiter.to_string()))?;
+    }
+    std::fs::write(path, csv).with_context(|| format!("Could not write {}", path.display()))
+}
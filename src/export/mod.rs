@@ -0,0 +1,37 @@
+pub mod alfred;
+pub mod community;
+pub mod csv;
+pub mod rss;
+pub mod sheets;
+pub mod template;
+pub mod xlsx;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Quotes a CSV field when it contains the delimiter, quotes or newlines.
+pub fn csv_field(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Print offers as a table
+    Table,
+    /// Print offers, dealers and other listings as JSON
+    Json,
+    /// Write offers to a CSV file with every field of the offers
+    Csv,
+    /// Write offers to an Excel workbook with one sheet per dealer
+    Xlsx,
+    /// Print every offer as a line of JSON as soon as its catalog is fetched.
+    /// Only the search terms and --week are applied
+    Ndjson,
+    /// Print offers as Alfred/Raycast script filter items linking to the offer
+    Alfred,
+}
@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use std::{collections::HashSet, fmt::Write, path::Path};
+
+use crate::{
+    locale,
+    report::html::escape,
+    requests::offer::Offer,
+    share,
+    watch::{self, Watch},
+};
+
+/// Writes an RSS feed with an item for every offer matching the watchlist,
+/// newest offers first. Feed readers tell items apart by offer id, so
+/// writing the feed again only shows the offers that are new.
+pub fn write(watchlist: &[Watch], offers: &[Offer], path: &Path) -> Result<usize> {
+    let mut matches = watch::check(watchlist, offers);
+    let mut seen = HashSet::new();
+    matches.retain(|(_, offer)| seen.insert(&offer.id));
+    matches.sort_by(|(_, a), (_, b)| b.run_from.cmp(&a.run_from).then(a.name.cmp(&b.name)));
+
+    let mut feed = String::new();
+    writeln!(feed, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(feed, r#"<rss version="2.0">"#)?;
+    writeln!(feed, "<channel>")?;
+    writeln!(feed, "<title>eTilbudsavis watchlist</title>")?;
+    writeln!(feed, "<link>https://etilbudsavis.dk</link>")?;
+    writeln!(
+        feed,
+        "<description>Offers at or below the prices on the watchlist</description>"
+    )?;
+    writeln!(
+        feed,
+        "<lastBuildDate>{}</lastBuildDate>",
+        Utc::now().to_rfc2822()
+    )?;
+    for (watch, offer) in matches.iter() {
+        writeln!(feed, "<item>")?;
+        writeln!(
+            feed,
+            "<title>{} at {}: {}</title>",
+            escape(&offer.name),
+            escape(offer.dealer.name()),
+            locale::money(offer.price)
+        )?;
+        writeln!(feed, "<link>{}</link>", escape(&share::offer_link(offer)))?;
+        writeln!(
+            feed,
+            "<description>{}/{}, watching {} at most {}. Valid {} - {}.</description>",
+            locale::money(offer.cost_per_unit),
+            escape(offer.unit.symbol()),
+            escape(&watch.term),
+            locale::money(watch.max),
+            offer.run_from.format("%d/%m"),
+            offer.run_till.format("%d/%m")
+        )?;
+        writeln!(
+            feed,
+            r#"<guid isPermaLink="false">{}</guid>"#,
+            escape(&offer.id)
+        )?;
+        writeln!(feed, "<pubDate>{}</pubDate>", rfc2822(offer.run_from))?;
+        writeln!(feed, "</item>")?;
+    }
+    writeln!(feed, "</channel>")?;
+    writeln!(feed, "</rss>")?;
+    std::fs::write(path, feed).with_context(|| format!("Could not write {}", path.display()))?;
+    Ok(matches.len())
+}
+
+/// Midnight UTC of the date, in the date format of RSS.
+fn rfc2822(date: NaiveDate) -> String {
+    date.and_hms_opt(0, 0, 0)
+        .expect("midnight exists")
+        .and_utc()
+        .to_rfc2822()
+}
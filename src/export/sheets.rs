@@ -0,0 +1,249 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::requests::{offer::Offer, SendRetrying};
+
+const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+const SHEETS_API: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+#[derive(Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct Values {
+    #[serde(default)]
+    values: Vec<Vec<Value>>,
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+const HEADER: [&str; 10] = [
+    "Fetched",
+    "From",
+    "Till",
+    "Dealer",
+    "Product",
+    "Count",
+    "Price",
+    "Cost/unit",
+    "Unit",
+    "Id",
+];
+
+/// Where and how to write the offers in a Google Sheet.
+pub struct SheetTarget<'a> {
+    pub spreadsheet_id: &'a str,
+    pub sheet: &'a str,
+    /// Keep the existing rows, updating the ones of the offers and adding the
+    /// other offers below them, instead of replacing the sheet contents.
+    pub append: bool,
+}
+
+/// Pushes offers to a Google Sheet, authenticating as a service account. The
+/// spreadsheet has to be shared with the service account's email address.
+pub async fn push(credentials: &Path, target: &SheetTarget<'_>, offers: &[Offer]) -> Result<()> {
+    let account: ServiceAccount = serde_json::from_str(
+        &std::fs::read_to_string(credentials)
+            .with_context(|| format!("Could not read {}", credentials.display()))?,
+    )
+    .context("Invalid service account credentials")?;
+    let client = crate::requests::client()?;
+    let token = access_token(&client, &account).await?;
+
+    let today = Utc::now().date_naive().to_string();
+    let rows: Vec<Value> = offers
+        .iter()
+        .map(|offer| {
+            json!([
+                today,
+                offer.run_from.to_string(),
+                offer.run_till.to_string(),
+                offer.dealer.to_string(),
+                offer.name,
+                offer.max_amount,
+                offer.price,
+                offer.cost_per_unit,
+                offer.unit,
+                offer.id,
+            ])
+        })
+        .collect();
+
+    let base = format!("{SHEETS_API}/{}/values", target.spreadsheet_id);
+    let range = quoted(target.sheet);
+    let rows = if target.append {
+        let response = client
+            .get(values_url(&base, &range, "")?)
+            .query(&[("valueRenderOption", "UNFORMATTED_VALUE")])
+            .bearer_auth(&token)
+            .send_retrying()
+            .await?;
+        let existing: Values = check_status(response).await?.json().await?;
+        merge(existing.values, rows)
+    } else {
+        let clear = client
+            .post(values_url(&base, &range, ":clear")?)
+            .bearer_auth(&token)
+            .send_retrying()
+            .await?;
+        check_status(clear).await?;
+        std::iter::once(json!(HEADER)).chain(rows).collect()
+    };
+    let response = client
+        .put(values_url(&base, &format!("{range}!A1"), "")?)
+        .query(&[("valueInputOption", "RAW")])
+        .bearer_auth(&token)
+        .json(&json!({ "values": rows }))
+        .send_retrying()
+        .await?;
+    check_status(response).await?;
+    Ok(())
+}
+
+/// The rows already in the sheet with the ones of offers in it replaced by
+/// their new row, followed by the rows of the other offers. Rows are matched
+/// by the offer id in the `Id` column.
+fn merge(existing: Vec<Vec<Value>>, rows: Vec<Value>) -> Vec<Value> {
+    if existing.is_empty() {
+        return std::iter::once(json!(HEADER)).chain(rows).collect();
+    }
+    let id_column = existing[0].iter().position(|cell| cell == "Id");
+    let id_of = |row: &Value| row[HEADER.len() - 1].clone();
+    let mut new: Vec<Option<Value>> = rows.into_iter().map(Some).collect();
+    let mut merged: Vec<Value> = existing
+        .into_iter()
+        .map(|row| {
+            let id = id_column.and_then(|column| row.get(column));
+            let updated = id.and_then(|id| {
+                new.iter_mut()
+                    .find(|new| new.as_ref().is_some_and(|new| id_of(new) == *id))?
+                    .take()
+            });
+            updated.unwrap_or_else(|| Value::from(row))
+        })
+        .collect();
+    merged.extend(new.into_iter().flatten());
+    merged
+}
+
+/// The sheet name as a range, quoted so names with spaces and other
+/// characters work.
+fn quoted(sheet: &str) -> String {
+    format!("'{}'", sheet.replace('\'', "''"))
+}
+
+/// The url of the values of the range, with the range escaped as a path
+/// segment.
+fn values_url(base: &str, range: &str, action: &str) -> Result<Url> {
+    let mut url = Url::parse(base)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("Invalid Google Sheets url"))?
+        .push(&format!("{range}{action}"));
+    Ok(url)
+}
+
+async fn access_token(client: &Client, account: &ServiceAccount) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let claims = Claims {
+        iss: &account.client_email,
+        scope: SCOPE,
+        aud: &account.token_uri,
+        iat: now,
+        exp: now + 3600,
+    };
+    let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())
+        .context("Service account has an invalid private key")?;
+    let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &key)?;
+    let response = client
+        .post(&account.token_uri)
+        .form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ])
+        .send_retrying()
+        .await?;
+    if !response.status().is_success() {
+        bail!(
+            "Could not authenticate with Google, StatusCode: {}",
+            response.status()
+        );
+    }
+    Ok(response.json::<Token>().await?.access_token)
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow!(
+        "Google Sheets request failed, StatusCode: {status}\n{body}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: &str, price: f64) -> Value {
+        json!(["", "", "", "", "", 1, price, price, "stk", id])
+    }
+
+    #[test]
+    fn quotes_sheet_names() {
+        assert_eq!(quoted("Offers"), "'Offers'");
+        assert_eq!(quoted("Week 20"), "'Week 20'");
+        assert_eq!(quoted("Bob's"), "'Bob''s'");
+    }
+
+    #[test]
+    fn escapes_ranges_in_urls() {
+        let url = values_url(SHEETS_API, "'A/B #1'!A1", "").unwrap();
+        assert_eq!(
+            url.as_str(),
+            format!("{SHEETS_API}/'A%2FB%20%231'!A1").as_str()
+        );
+    }
+
+    #[test]
+    fn merge_starts_empty_sheets_with_the_header() {
+        let merged = merge(Vec::new(), vec![row("a", 1.0)]);
+        assert_eq!(merged, vec![json!(HEADER), row("a", 1.0)]);
+    }
+
+    #[test]
+    fn merge_updates_rows_by_offer_id() {
+        let existing = vec![
+            HEADER.iter().map(|cell| json!(cell)).collect(),
+            row("a", 2.0).as_array().unwrap().clone(),
+            vec![json!("note")],
+        ];
+        let merged = merge(existing, vec![row("b", 3.0), row("a", 1.0)]);
+        assert_eq!(
+            merged,
+            vec![json!(HEADER), row("a", 1.0), json!(["note"]), row("b", 3.0)]
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::{bail, Result};
+use std::{fmt::Write, str::FromStr};
+
+use crate::{locale, requests::offer::Offer, share};
+
+/// The placeholders a template can use.
+pub const PLACEHOLDERS: [&str; 13] = [
+    "id",
+    "dealer",
+    "name",
+    "price",
+    "pre_price",
+    "unit_price",
+    "deposit",
+    "unit",
+    "size",
+    "amount",
+    "from",
+    "till",
+    "link",
+];
+
+#[derive(Clone, Debug)]
+enum Part {
+    Text(String),
+    /// Index into `PLACEHOLDERS`
+    Field(usize),
+}
+
+/// A line like `{dealer}\t{name}\t{unit_price}` every offer is printed
+/// through. `\t`, `\n` and `\\` are escapes, and `{{` and `}}` are braces.
+#[derive(Clone, Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl FromStr for Template {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let Some((name, rest)) = chars.as_str().split_once('}') else {
+                        bail!("Unclosed {{ in format, use {{{{ for a brace");
+                    };
+                    let name = name.trim();
+                    let Some(field) = PLACEHOLDERS.iter().position(|known| *known == name) else {
+                        bail!(
+                            "Unknown placeholder {{{name}}}, use one of {}",
+                            PLACEHOLDERS.join(", ")
+                        );
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                    chars = rest.chars();
+                }
+                '}' => bail!("Unmatched }} in format, use }}}} for a brace"),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+}
+
+impl Template {
+    /// The offer with the placeholders filled in. Prices are in kr with two
+    /// decimals, with a decimal comma in Danish, and dates are `YYYY-MM-DD`,
+    /// so the output sorts and parses.
+    pub fn render(&self, offer: &Offer) -> String {
+        let mut line = String::new();
+        for part in self.parts.iter() {
+            let _ = match part {
+                Part::Text(text) => write!(line, "{text}"),
+                Part::Field(field) => match PLACEHOLDERS[*field] {
+                    "id" => write!(line, "{}", offer.id),
+                    "dealer" => write!(line, "{}", offer.dealer),
+                    "name" => write!(line, "{}", offer.name),
+                    "price" => write!(line, "{}", locale::decimal(offer.price, 2)),
+                    "pre_price" => match offer.pre_price {
+                        Some(pre_price) => write!(line, "{}", locale::decimal(pre_price, 2)),
+                        None => Ok(()),
+                    },
+                    "unit_price" => write!(line, "{}", locale::decimal(offer.cost_per_unit, 2)),
+                    "deposit" => match offer.deposit {
+                        Some(deposit) => write!(line, "{}", locale::decimal(deposit, 2)),
+                        None => Ok(()),
+                    },
+                    "unit" => write!(line, "{}", offer.unit),
+                    "size" if offer.is_sized() => write!(line, "{}", offer.max_size),
+                    "size" => Ok(()),
+                    "amount" => write!(line, "{}", offer.max_amount),
+                    "from" => write!(line, "{}", offer.run_from),
+                    "till" => write!(line, "{}", offer.run_till),
+                    "link" => write!(line, "{}", share::offer_link(offer)),
+                    _ => unreachable!("placeholders are checked when parsing"),
+                },
+            };
+        }
+        line
+    }
+}
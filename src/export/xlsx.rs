@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use rust_xlsxwriter::{Format, FormatAlign, Workbook};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use crate::requests::{dealer::Dealer, offer::Offer};
+
+/// Longest worksheet name Excel allows.
+const MAX_SHEET_NAME: usize = 31;
+
+const HEADER: [&str; 10] = [
+    "From",
+    "Till",
+    "Product",
+    "Min count",
+    "Max count",
+    "Price (kr)",
+    "Cost/unit (kr)",
+    "Unit",
+    "Min size",
+    "Max size",
+];
+
+/// Writes the offers to an Excel workbook with one worksheet per dealer.
+/// Prices, sizes and dates are written as proper numeric cells.
+pub fn write(offers: &[Offer], path: &Path) -> Result<()> {
+    let mut by_dealer: BTreeMap<Dealer, Vec<&Offer>> = BTreeMap::new();
+    for offer in offers {
+        by_dealer.entry(offer.dealer).or_default().push(offer);
+    }
+
+    let header_format = Format::new().set_bold();
+    let date_format = Format::new()
+        .set_num_format("dd/mm/yyyy")
+        .set_align(FormatAlign::Left);
+    let money_format = Format::new().set_num_format("0.00");
+    let size_format = Format::new().set_num_format("0.###");
+
+    let mut workbook = Workbook::new();
+    let mut names = HashSet::new();
+    for (dealer, offers) in by_dealer {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(sheet_name(dealer.name(), &mut names))?;
+        for (col, title) in HEADER.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *title, &header_format)?;
+        }
+        for (i, offer) in offers.iter().enumerate() {
+            let row = i as u32 + 1;
+            sheet
+                .write_datetime_with_format(row, 0, offer.run_from, &date_format)?
+                .write_datetime_with_format(row, 1, offer.run_till, &date_format)?
+                .write_string(row, 2, &offer.name)?
+                .write_number(row, 3, offer.min_amount)?
+                .write_number(row, 4, offer.max_amount)?
+                .write_number_with_format(row, 5, offer.price, &money_format)?
+                .write_number_with_format(row, 6, offer.cost_per_unit, &money_format)?
+                .write_string(row, 7, offer.unit.symbol())?
+                .write_number_with_format(row, 8, offer.min_size, &size_format)?
+                .write_number_with_format(row, 9, offer.max_size, &size_format)?;
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        sheet.autofit();
+    }
+    if offers.is_empty() {
+        workbook.add_worksheet().set_name("Offers")?;
+    }
+
+    workbook
+        .save(path)
+        .with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// The dealer's name as a worksheet name Excel accepts: without `[]:*?/\\`,
+/// at most 31 characters and different from the names in `taken`, which it
+/// is added to. Excel compares the names ignoring case.
+fn sheet_name(dealer: &str, taken: &mut HashSet<String>) -> String {
+    let cleaned: String = dealer
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { ' ' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('\'').trim();
+    let cleaned = if cleaned.is_empty() || cleaned.eq_ignore_ascii_case("history") {
+        "Dealer"
+    } else {
+        cleaned
+    };
+    let mut number = 1;
+    loop {
+        let suffix = if number == 1 {
+            String::new()
+        } else {
+            format!(" ({number})")
+        };
+        let base: String = cleaned
+            .chars()
+            .take(MAX_SHEET_NAME - suffix.chars().count())
+            .collect();
+        let name = format!("{}{suffix}", base.trim_end());
+        if taken.insert(name.to_lowercase()) {
+            return name;
+        }
+        number += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sheet_names_are_valid() {
+        let mut taken = HashSet::new();
+        assert_eq!(sheet_name("Netto", &mut taken), "Netto");
+        assert_eq!(
+            sheet_name("Bog & idé: Køge/Roskilde", &mut taken),
+            "Bog & idé  Køge Roskilde"
+        );
+        assert_eq!(sheet_name("[Outlet]", &mut taken), "Outlet");
+        assert_eq!(sheet_name("'Quoted'", &mut taken), "Quoted");
+        assert_eq!(sheet_name("???", &mut taken), "Dealer");
+        assert_eq!(sheet_name("History", &mut taken), "Dealer (2)");
+    }
+
+    #[test]
+    fn long_names_are_truncated() {
+        let mut taken = HashSet::new();
+        let name = sheet_name("Harald Nyborg Byggemarked og Isenkram", &mut taken);
+        assert_eq!(name, "Harald Nyborg Byggemarked og Is");
+        assert_eq!(name.chars().count(), MAX_SHEET_NAME);
+    }
+
+    #[test]
+    fn colliding_names_are_numbered() {
+        let mut taken = HashSet::new();
+        let long = "Harald Nyborg Byggemarked og Isenkram";
+        assert_eq!(
+            sheet_name(long, &mut taken),
+            "Harald Nyborg Byggemarked og Is"
+        );
+        assert_eq!(
+            sheet_name(long, &mut taken),
+            "Harald Nyborg Byggemarked o (2)"
+        );
+        assert_eq!(sheet_name("netto", &mut taken), "netto");
+        assert_eq!(sheet_name("NETTO", &mut taken), "NETTO (2)");
+    }
+}
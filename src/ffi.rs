@@ -0,0 +1,63 @@
+//! A C ABI for fetching offers from other languages, built into the shared
+//! library with the `ffi` feature. The functions are declared in
+//! `include/etilbudsavis.h`, which is generated when building with it.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use crate::{retrieve_offers, userdata};
+
+/// Fetches the offers of the favorite dealers as a JSON array, like
+/// `--output json` does. Only offers matching `query` are included, unless it
+/// is null. Returns null when the offers could not be fetched.
+///
+/// The string must be freed with `etilbudsavis_free_string`.
+///
+/// # Safety
+///
+/// `query` must be null or a valid, null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn etilbudsavis_offers_json(query: *const c_char) -> *mut c_char {
+    let query = if query.is_null() {
+        None
+    } else {
+        match unsafe { CStr::from_ptr(query) }.to_str() {
+            Ok(query) => Some(query),
+            Err(_) => return ptr::null_mut(),
+        }
+    };
+    let result = offers_json(query);
+    match result {
+        Ok(json) => json.into_raw(),
+        Err(err) => {
+            tracing::error!("{err:#}");
+            ptr::null_mut()
+        }
+    }
+}
+
+fn offers_json(query: Option<&str>) -> anyhow::Result<CString> {
+    let mut userdata = userdata::get_userdata()?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    let mut offers = runtime.block_on(retrieve_offers(&mut userdata, false));
+    if let Some(query) = query {
+        offers.retain(|offer| offer.matches(query));
+    }
+    Ok(CString::new(serde_json::to_string(&offers)?)?)
+}
+
+/// Frees a string returned by `etilbudsavis_offers_json`. Does nothing when
+/// it is null.
+///
+/// # Safety
+///
+/// `string` must be null or returned by `etilbudsavis_offers_json`, and not
+/// freed before.
+#[no_mangle]
+pub unsafe extern "C" fn etilbudsavis_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Name of the Danish public holiday on the date, if any. Supermarkets are
+/// closed on these days by the closing hours act.
+pub fn holiday(date: NaiveDate) -> Option<&'static str> {
+    let from_easter = (date - easter(date.year())).num_days();
+    let name = match from_easter {
+        -3 => "Skærtorsdag",
+        -2 => "Langfredag",
+        0 => "Påskedag",
+        1 => "2. påskedag",
+        // Abolished as a holiday from 2024
+        26 if date.year() < 2024 => "Store bededag",
+        39 => "Kristi himmelfartsdag",
+        49 => "Pinsedag",
+        50 => "2. pinsedag",
+        _ => match (date.month(), date.day()) {
+            (1, 1) => "Nytårsdag",
+            (12, 25) => "Juledag",
+            (12, 26) => "2. juledag",
+            _ => return None,
+        },
+    };
+    Some(name)
+}
+
+/// Holidays between `from` and `till`, both included.
+pub fn closing_days(from: NaiveDate, till: NaiveDate) -> Vec<NaiveDate> {
+    from.iter_days()
+        .take_while(|date| *date <= till)
+        .filter(|date| holiday(*date).is_some())
+        .collect()
+}
+
+/// The last day stores are open in the period, which is earlier than `till`
+/// when the period ends on a holiday.
+pub fn last_open_day(from: NaiveDate, till: NaiveDate) -> NaiveDate {
+    let mut day = till;
+    while day > from && holiday(day).is_some() {
+        day -= Duration::days(1);
+    }
+    day
+}
+
+/// Easter Sunday of the year, using the anonymous Gregorian algorithm.
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).expect("easter is a valid date")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn easter_dates() {
+        assert_eq!(easter(2023), date(2023, 4, 9));
+        assert_eq!(easter(2024), date(2024, 3, 31));
+        assert_eq!(easter(2025), date(2025, 4, 20));
+        assert_eq!(easter(2038), date(2038, 4, 25));
+    }
+
+    #[test]
+    fn movable_holidays() {
+        assert_eq!(holiday(date(2025, 4, 17)), Some("Skærtorsdag"));
+        assert_eq!(holiday(date(2025, 4, 18)), Some("Langfredag"));
+        assert_eq!(holiday(date(2025, 4, 21)), Some("2. påskedag"));
+        assert_eq!(holiday(date(2025, 5, 29)), Some("Kristi himmelfartsdag"));
+        assert_eq!(holiday(date(2025, 6, 9)), Some("2. pinsedag"));
+        assert_eq!(holiday(date(2025, 4, 19)), None);
+    }
+
+    #[test]
+    fn store_bededag_until_2023() {
+        assert_eq!(holiday(date(2023, 5, 5)), Some("Store bededag"));
+        assert_eq!(holiday(date(2024, 4, 26)), None);
+        assert_eq!(holiday(date(2025, 5, 16)), None);
+    }
+
+    #[test]
+    fn last_open_day_skips_holidays_at_the_end() {
+        assert_eq!(
+            last_open_day(date(2025, 4, 14), date(2025, 4, 21)),
+            date(2025, 4, 19)
+        );
+        assert_eq!(
+            last_open_day(date(2025, 12, 22), date(2025, 12, 26)),
+            date(2025, 12, 24)
+        );
+        assert_eq!(
+            last_open_day(date(2025, 4, 15), date(2025, 4, 16)),
+            date(2025, 4, 16)
+        );
+    }
+
+    #[test]
+    fn last_open_day_of_only_holidays_is_the_start() {
+        assert_eq!(
+            last_open_day(date(2025, 12, 25), date(2025, 12, 26)),
+            date(2025, 12, 25)
+        );
+    }
+
+    #[test]
+    fn closing_days_in_easter_week() {
+        assert_eq!(
+            closing_days(date(2025, 4, 14), date(2025, 4, 20)),
+            vec![date(2025, 4, 17), date(2025, 4, 18), date(2025, 4, 20)]
+        );
+    }
+}
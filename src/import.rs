@@ -0,0 +1,93 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use std::{collections::HashMap, path::Path};
+
+use crate::requests::history::{History, PricePoint};
+
+/// Reads reference prices from a CSV file and records them in the price
+/// history tagged with `source`. Returns how many prices were new.
+///
+/// The file needs a header with at least `product`, `price` and `unit`
+/// columns. `cost_per_unit` defaults to the price (i.e. a price per unit),
+/// `dealer` to the source and `date` (or `valid_from`/`valid_till`) to today.
+/// Files written by `export community` can be imported as they are.
+pub fn import_prices(path: &Path, source: &str) -> Result<usize> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let mut rows = parse_csv(&data).into_iter();
+    let header = rows.next().context("The file is empty")?;
+    let columns: HashMap<String, usize> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.trim().to_lowercase(), i))
+        .collect();
+
+    let today = Utc::now().date_naive().to_string();
+    let mut points = Vec::new();
+    for (line, row) in rows.enumerate() {
+        let line = line + 2;
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| columns.get(*name))
+                .and_then(|&i| row.get(i))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let number = |names: &[&str]| -> Result<Option<f64>> {
+            field(names)
+                .map(|value| value.replace(',', ".").parse::<f64>())
+                .transpose()
+                .map_err(|_| anyhow!("Invalid number on line {line}"))
+        };
+        let name = field(&["product", "name"])
+            .with_context(|| format!("Missing product on line {line}"))?;
+        let price = number(&["price"])?.with_context(|| format!("Missing price on line {line}"))?;
+        let unit = field(&["unit"]).with_context(|| format!("Missing unit on line {line}"))?;
+        let run_from = field(&["valid_from", "date"]).unwrap_or(&today);
+        if chrono::NaiveDate::parse_from_str(run_from, "%Y-%m-%d").is_err() {
+            bail!("Invalid date on line {line}, use YYYY-MM-DD");
+        }
+        points.push(PricePoint {
+            dealer: field(&["dealer"]).unwrap_or(source).to_string(),
+            name: name.to_string(),
+            price,
+            cost_per_unit: number(&["cost_per_unit", "unit_price"])?.unwrap_or(price),
+            unit: unit.to_string(),
+            run_from: run_from.to_string(),
+            run_till: field(&["valid_till"]).unwrap_or(run_from).to_string(),
+        });
+    }
+    History::open()?.import(source, &points)
+}
+
+/// Splits CSV into rows of fields, handling quoted fields.
+fn parse_csv(data: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            '\r' if !quoted => {}
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
@@ -0,0 +1,82 @@
+//! Fetching, caching and searching the offers of Danish dealers from the
+//! eTilbudsavis (Tjek) API, as used by the `etilbudsavis-cli` binary.
+//!
+//! [`retrieve_offers`] is the entry point: it returns the offers of the
+//! favorite dealers in [`UserData`], from the cache of each dealer while it
+//! is fresh and from the API otherwise.
+//!
+//! ```no_run
+//! use etilbudsavis_cli::{retrieve_offers, userdata};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let mut userdata = userdata::get_userdata()?;
+//! for offer in retrieve_offers(&mut userdata, false).await {
+//!     if offer.matches("kaffe") {
+//!         println!("{} at {}: {:.2} kr", offer.name, offer.dealer, offer.price);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "native")]
+pub mod alerts;
+pub mod basket;
+#[cfg(feature = "native")]
+pub mod catalogs;
+#[cfg(feature = "native")]
+pub mod cli;
+#[cfg(feature = "native")]
+pub mod config;
+pub mod dedup;
+#[cfg(feature = "native")]
+pub mod density;
+#[cfg(feature = "native")]
+pub mod diet;
+pub mod diff;
+pub mod duration;
+pub mod error;
+#[cfg(feature = "native")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod holidays;
+#[cfg(feature = "native")]
+pub mod import;
+pub mod locale;
+#[cfg(feature = "native")]
+pub mod logging;
+#[cfg(feature = "native")]
+pub mod notify;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "native")]
+pub mod report;
+pub mod requests;
+pub mod score;
+pub mod search;
+pub mod share;
+#[cfg(feature = "native")]
+pub mod snapshot;
+pub mod sort;
+#[cfg(feature = "native")]
+pub mod stats;
+pub mod style;
+pub mod suggest;
+#[cfg(feature = "native")]
+pub mod translate;
+pub mod unit;
+#[cfg(feature = "native")]
+pub mod watch;
+pub mod week;
+
+pub use requests::{
+    dealer::{Catalog, Dealer},
+    offer::Offer,
+};
+#[cfg(feature = "native")]
+pub use requests::{
+    history::History,
+    offer::retrieve_offers,
+    userdata::{self, UserData},
+};
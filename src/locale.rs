@@ -0,0 +1,61 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// The language of labels, dates and numbers in the output. Machine readable
+/// output like JSON and CSV is the same in every language.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    En,
+    /// Danish, with decimal commas
+    Da,
+}
+
+/// Set once from `--lang` or the config.
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or_default()
+}
+
+/// The English or the Danish text, in the chosen language.
+pub fn text(en: &'static str, da: &'static str) -> &'static str {
+    match lang() {
+        Lang::En => en,
+        Lang::Da => da,
+    }
+}
+
+/// The number with this many decimals, like "12.50" or in Danish "12,50".
+pub fn decimal(value: f64, decimals: usize) -> String {
+    let number = format!("{value:.decimals$}");
+    match lang() {
+        Lang::En => number,
+        Lang::Da => number.replace('.', ","),
+    }
+}
+
+/// The amount in kroner, like "12.50 kr" or in Danish "12,50 kr".
+pub fn money(value: f64) -> String {
+    format!("{} kr", decimal(value, 2))
+}
+
+/// The name of the day of the week of the date, like "Saturday" or "lørdag".
+pub fn weekday(date: NaiveDate) -> &'static str {
+    match date.weekday() {
+        Weekday::Mon => text("Monday", "mandag"),
+        Weekday::Tue => text("Tuesday", "tirsdag"),
+        Weekday::Wed => text("Wednesday", "onsdag"),
+        Weekday::Thu => text("Thursday", "torsdag"),
+        Weekday::Fri => text("Friday", "fredag"),
+        Weekday::Sat => text("Saturday", "lørdag"),
+        Weekday::Sun => text("Sunday", "søndag"),
+    }
+}
@@ -0,0 +1,37 @@
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tracing_subscriber::EnvFilter;
+
+use crate::style;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether `--quiet` asked for nothing but the result.
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Sets up logging to stderr. Warnings and notes are shown by default,
+/// `verbose` adds HTTP requests, cache decisions and parse warnings, and
+/// `quiet` leaves only errors. `RUST_LOG` overrides both.
+pub fn init(verbose: bool, quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.is_empty() => EnvFilter::new(directives),
+        _ if quiet => EnvFilter::new("error"),
+        _ if verbose => EnvFilter::new("warn,etilbudsavis_cli=debug"),
+        _ => EnvFilter::new("error,etilbudsavis_cli=info"),
+    };
+    // Plain messages normally, levels only where there are debug lines too
+    let detailed = verbose || std::env::var_os("RUST_LOG").is_some();
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal() && !style::is_ascii())
+        .without_time()
+        .with_level(detailed)
+        .with_target(detailed)
+        .init();
+}
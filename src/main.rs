@@ -1,152 +1,758 @@
-mod requests;
-use clap::{Parser, Subcommand};
-use comfy_table::{modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL, ContentArrangement, Table};
+#[cfg(feature = "dbus")]
+mod dbus;
+mod serve;
+mod tui;
 
-use crate::requests::{
+use clap::{Parser, Subcommand};
+use etilbudsavis_cli::cli::{
+    self, catalogs, export, favorites, files, history, offers, offers::NutritionMetric, shopping,
+    stats, watch, Options,
+};
+use etilbudsavis_cli::duration::Span;
+use etilbudsavis_cli::export::OutputFormat;
+use etilbudsavis_cli::report::ReportFormat;
+use etilbudsavis_cli::requests::{
+    self, branding,
     dealer::Dealer,
-    offer::{retrieve_offers, Offer},
-    userdata,
+    deserialize,
+    offer::{self, retrieve_offers},
+    progress,
     userdata::UserData,
 };
-use std::{borrow::Cow, process::exit, str::FromStr};
+use etilbudsavis_cli::style::{self, TableWidth};
+use etilbudsavis_cli::{config, logging};
+use std::{borrow::Cow, net::SocketAddr, path::PathBuf, process::exit, time::Duration};
 
 #[tokio::main]
 async fn main() {
     let runtime = std::time::Instant::now();
-    let args = Cli::parse();
-    run(args).await;
-    dbg!(runtime.elapsed());
+    let mut args = Cli::parse();
+    if let Some(Commands::Query { name }) = &args.command {
+        args = expand_query(name.as_deref());
+    }
+    let options = &args.options;
+    style::set_ascii(options.ascii);
+    style::set_color(options.color);
+    style::set_ascii_borders(options.ascii_borders);
+    style::set_table_width(if options.wide {
+        TableWidth::Wide
+    } else if options.narrow {
+        TableWidth::Narrow
+    } else {
+        TableWidth::Fit
+    });
+    logging::init(options.verbose, options.quiet);
+    config::warn_if_invalid();
+    progress::set_verbose(options.verbose);
+    deserialize::set_show_skipped(options.show_skipped);
+    deserialize::set_lenient(options.lenient);
+    requests::set_offline(options.offline);
+    offer::set_force_refresh(options.refresh);
+    if let Err(err) = run(args).await {
+        fail(err);
+    }
+    tracing::debug!("Finished in {:?}", runtime.elapsed());
 }
 
-async fn run(args: Cli) {
-    let mut userdata = userdata::get_userdata();
+async fn run(args: Cli) -> anyhow::Result<()> {
+    let Cli {
+        mut options,
+        command,
+    } = args;
+    let mut userdata = cli::load_userdata()?;
+    options.apply_config(config::get_config());
 
-    let favorites_changed = match args.favorites {
-        Some(FavoriteCommands::Add { dealers }) => userdata.add_favorites(&dealers),
-        Some(FavoriteCommands::Remove { dealers }) => userdata.remove_favorites(&dealers),
-        Some(FavoriteCommands::Dealers) => {
-            Dealer::list_known_dealers();
-            exit(0);
-        }
-        Some(FavoriteCommands::Favorites) => {
-            let mut table = Table::new();
-            table.load_preset(UTF8_FULL);
-            table.apply_modifier(UTF8_ROUND_CORNERS);
-            table.set_header(vec!["Favorites"]);
-            for favorite in userdata.favorites {
-                table.add_row(vec![favorite]);
+    // Offers of new favorites are fetched when there are no cached offers of
+    // them, without refetching the other favorites
+    match command {
+        Some(Commands::Add { dealers })
+        | Some(Commands::Favorites {
+            command: Some(FavoritesCommands::Add { dealers }),
+        }) => favorites::add(&mut userdata, &dealers),
+        Some(Commands::Remove { dealers })
+        | Some(Commands::Favorites {
+            command: Some(FavoritesCommands::Remove { dealers }),
+        }) => {
+            let confirmed = favorites::remove(&mut userdata, &dealers, &options);
+            if !confirmed {
+                return Ok(());
             }
-            println!("{}", table);
-            exit(0);
         }
-        None => false,
-    };
+        Some(Commands::Search { terms }) => {
+            options.search.extend(terms.into_iter().map(Cow::Owned));
+        }
+        Some(Commands::Serve {
+            addr,
+            interval,
+            dbus,
+        }) => {
+            let interval = interval.0.to_std().unwrap_or(Duration::from_secs(3600));
+            return serve::run(userdata, addr, interval, dbus).await;
+        }
+        Some(command) => return run_command(command, &mut userdata, &options).await,
+        None => {}
+    }
 
-    let mut offers =
-        handle_search(&mut userdata, &args.search, favorites_changed, args.dealer).await;
-    offers.sort_unstable_by(|a, b| a.cost_per_unit.total_cmp(&b.cost_per_unit).reverse());
-
-    let mut table = Table::new();
-    table
-        .load_preset(UTF8_FULL)
-        .apply_modifier(UTF8_ROUND_CORNERS)
-        .set_content_arrangement(ContentArrangement::Dynamic)
-        .set_width(100)
-        .set_header(vec![
-            "Period",
-            "Dealer",
-            "Product",
-            "Count",
-            "Price",
-            "Cost/unit",
-            "Weight",
-        ]);
-
-    match (args.json, args.print) {
-        (true, true) => {
-            println!("`json` and other options are mutually exclusive");
-            exit(1);
+    if options.notify_new {
+        watch::notify_new(&mut userdata, &options).await
+    } else if let Some(OutputFormat::Ndjson) = options.output {
+        offers::stream_ndjson(&mut userdata, &options).await;
+        Ok(())
+    } else {
+        offers::list(&mut userdata, &options).await
+    }
+}
+
+/// Runs the subcommands other than those listing offers afterwards.
+async fn run_command(
+    command: Commands,
+    userdata: &mut UserData,
+    options: &Options,
+) -> anyhow::Result<()> {
+    match command {
+        Commands::Add { .. }
+        | Commands::Remove { .. }
+        | Commands::Search { .. }
+        | Commands::Serve { .. } => unreachable!("these are run before"),
+        Commands::Favorites {
+            command: Some(FavoritesCommands::Add { .. }) | Some(FavoritesCommands::Remove { .. }),
+        } => unreachable!("these are run before"),
+        Commands::Query { .. } => unreachable!("queries are expanded before running"),
+        Commands::Config { command } => match command {
+            ConfigCommands::Path => files::config_location(),
+            ConfigCommands::Edit => files::edit_config(),
+        },
+        Commands::Cache { command } => match command {
+            CacheCommands::Path => files::cache_location(),
+            CacheCommands::Status => files::cache_status(options),
+            CacheCommands::Clear => files::clear_cache(options),
+        },
+        Commands::Dealers { update } => favorites::dealers(update, options).await,
+        Commands::Favorites {
+            command: None | Some(FavoritesCommands::List),
+        } => {
+            favorites::list(userdata, options);
+            Ok(())
+        }
+        Commands::Favorites {
+            command: Some(FavoritesCommands::Sync),
+        } => favorites::sync(userdata).await,
+        Commands::Report {
+            format,
+            output,
+            images,
+            search,
+        } => export::report(userdata, format, output, images, &search, options).await,
+        Commands::Basket { items, max_stores } => {
+            shopping::basket(userdata, items, max_stores, options).await
+        }
+        Commands::Diff => {
+            history::diff(userdata, options).await;
+            Ok(())
+        }
+        Commands::Compare { product } => {
+            shopping::compare(userdata, &product, options).await;
+            Ok(())
+        }
+        Commands::Stores { all } => favorites::stores(userdata, all, options).await,
+        Commands::History {
+            command: Some(HistoryCommands::Prune { older_than }),
+            ..
+        } => history::prune(older_than, options),
+        Commands::History {
+            command: None,
+            product,
+        } => history::price_history(&product, options),
+        Commands::List { command } => match command.unwrap_or(ListCommands::Show) {
+            ListCommands::Show => {
+                shopping::show_list(userdata, options);
+                Ok(())
+            }
+            ListCommands::Add { items } => {
+                shopping::add_to_list(userdata, items);
+                Ok(())
+            }
+            ListCommands::Remove { items } => shopping::remove_from_list(userdata, &items),
+            ListCommands::Clear => {
+                shopping::clear_list(userdata, options);
+                Ok(())
+            }
+        },
+        Commands::Watch { command } => match command {
+            WatchCommands::Add { term, max, unit } => {
+                watch::add(userdata, term, max, unit);
+                Ok(())
+            }
+            WatchCommands::Remove { term } => watch::remove(userdata, &term),
+            WatchCommands::List => {
+                watch::list(userdata, options);
+                Ok(())
+            }
+            WatchCommands::Check => {
+                if watch::check(userdata, options).await {
+                    exit(1);
+                }
+                Ok(())
+            }
+        },
+        Commands::Notify => watch::notify(userdata).await,
+        Commands::Tui => {
+            let offers = retrieve_offers(userdata, false).await;
+            let mut dealers: Vec<Dealer> = offers.iter().map(|offer| offer.dealer).collect();
+            dealers.sort();
+            dealers.dedup();
+            let branding = branding::get_branding(&dealers).await;
+            tui::run(offers, branding)
+        }
+        Commands::Snapshot { keep } => export::snapshot(userdata, keep).await,
+        Commands::Import {
+            command: ImportCommands::Prices { file, source },
+        } => export::import_prices(&file, source),
+        Commands::Export { command } => match command {
+            ExportCommands::Community { output } => export::community(&output),
+            ExportCommands::Rss { output } => export::rss(userdata, &output).await,
+            ExportCommands::Sheets {
+                spreadsheet,
+                sheet,
+                append,
+                search,
+            } => export::sheets(userdata, spreadsheet, sheet, append, &search).await,
+        },
+        Commands::Share { report, search } => {
+            export::share(userdata, report, &search, options).await
         }
-        (true, false) => {
-            println!("{}", serde_json::to_string(&offers).expect("dude what?"));
+        Commands::Open { id, catalog } => offers::open(userdata, &id, catalog).await,
+        Commands::Show { id } => offers::show(userdata, &id, options).await,
+        Commands::Alerts { command } => match command.unwrap_or(AlertsCommands::List) {
+            AlertsCommands::List => history::list_alerts(options),
+            AlertsCommands::Ack { ids } => history::acknowledge_alerts(&ids),
+            AlertsCommands::Snooze { duration, ids } => history::snooze_alerts(duration, &ids),
+        },
+        Commands::Expiring { days, search } => {
+            offers::expiring(userdata, days, &search, options).await
         }
-        (false, true) => {
-            for offer in offers.iter() {
-                table.add_row(offer.to_table_entry());
+        Commands::Catalogs { command } => match command {
+            CatalogsCommands::List { dealer } => {
+                catalogs::list(userdata, dealer, options).await;
+                Ok(())
+            }
+            CatalogsCommands::Show { id } => catalogs::show(userdata, &id, options).await,
+            CatalogsCommands::Download { id, pdf, output } => {
+                catalogs::download(&id, pdf, output).await
+            }
+        },
+        Commands::Stats { command } => match command.unwrap_or(StatsCommands::Summary) {
+            StatsCommands::Summary => {
+                stats::summary(userdata, options).await;
+                Ok(())
+            }
+            StatsCommands::Dealers => {
+                stats::dealers(userdata, options).await;
+                Ok(())
             }
-            println!("{}", table);
-            println!("Amount of offers: {}", offers.len());
+            StatsCommands::Inflation => stats::inflation(options),
+        },
+        Commands::Nutrition { by, max, search } => {
+            offers::nutrition(userdata, by, max, &search, options).await
         }
-        (false, false) if !args.search.is_empty() => {
-            for offer in offers.iter() {
-                table.add_row(offer.to_table_entry());
+        Commands::Popular { count } => offers::popular(userdata, count, options).await,
+        Commands::Top { count } => offers::top(userdata, count, options).await,
+    }
+}
+
+/// The arguments with `query <name>` replaced by the words of the saved
+/// search, so flags given along with it still apply. Exits after listing the
+/// queries without a name.
+fn expand_query(name: Option<&str>) -> Cli {
+    let queries = &config::get_config().queries;
+    let Some(name) = name else {
+        if queries.is_empty() {
+            println!("No saved queries, add them under [queries] in the config");
+        }
+        for (name, query) in queries.iter() {
+            println!("{name}: {query}");
+        }
+        exit(0);
+    };
+    let Some(query) = queries.get(name) else {
+        let known: Vec<&str> = queries.keys().map(String::as_str).collect();
+        eprintln!(
+            "No saved query named {name}{}",
+            if known.is_empty() {
+                ", add it under [queries] in the config".to_string()
+            } else {
+                format!(", the queries are {}", known.join(", "))
             }
-            println!("{}", table);
-            println!("Amount of offers: {}", offers.len());
+        );
+        exit(1);
+    };
+    let words = match config::query_args(query) {
+        Ok(words) => words,
+        Err(err) => fail(err),
+    };
+    let mut argv: Vec<String> = std::env::args().collect();
+    let Some(at) = argv
+        .iter()
+        .skip(1)
+        .position(|arg| arg == "query")
+        .map(|at| at + 1)
+    else {
+        unreachable!("the query subcommand was given");
+    };
+    argv.remove(at);
+    if let Some(offset) = argv[at..].iter().position(|arg| arg == name) {
+        argv.remove(at + offset);
+    }
+    argv.splice(at..at, words);
+    match Cli::try_parse_from(&argv) {
+        Ok(args) if matches!(args.command, Some(Commands::Query { .. })) => {
+            eprintln!("The query {name} runs another query");
+            exit(1);
         }
-        (false, false) => {
-            println!("Amount of offers: {}", offers.len());
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("The query {name} is not valid: {query}");
+            err.exit()
         }
     }
 }
 
+/// Prints the error and exits, with an exit code telling API failures apart.
+fn fail(err: anyhow::Error) -> ! {
+    eprintln!("{err:#}");
+    exit(etilbudsavis_cli::error::exit_code(&err));
+}
+
 #[derive(Parser, Debug)]
-#[command(author, version, about = "A CLI interface for the eTilbudsavis API.", long_about = None)]
+#[command(
+    author,
+    version,
+    about = "A CLI interface for the eTilbudsavis API.",
+    long_about = None,
+    after_help = "Exit codes when failing because of the API: 3 network error, 4 error status, \
+    5 invalid JSON, 6 missing fields, 7 offline"
+)]
 struct Cli {
-    search: Vec<Cow<'static, str>>,
-    #[arg(short, long, default_value_t = false)]
-    /// Always print offers
-    print: bool,
-    /// Output offers as JSON (cannot be combined with other options)
-    #[arg(short, long)]
-    json: bool,
-
-    /// Search by dealer
-    #[arg(short)]
-    dealer: bool,
+    #[command(flatten)]
+    options: Options,
     #[command(subcommand)]
-    favorites: Option<FavoriteCommands>,
+    command: Option<Commands>,
 }
 
 #[derive(Subcommand, Debug)]
 #[command(author, version, about, long_about = None)]
-enum FavoriteCommands {
-    #[command(about = "Add a dealer to favorites")]
-    Add { dealers: Vec<Dealer> },
-    #[command(about = "Remove a dealer from favorites")]
-    Remove { dealers: Vec<Dealer> },
+enum Commands {
+    #[command(about = "Add a dealer to favorites (same as `favorites add`)")]
+    Add {
+        #[arg(required = true)]
+        dealers: Vec<Dealer>,
+    },
+    #[command(about = "Remove a dealer from favorites (same as `favorites remove`)")]
+    Remove {
+        #[arg(required = true)]
+        dealers: Vec<Dealer>,
+    },
     #[command(about = "List available dealers")]
+    Dealers {
+        /// Fetch every dealer from the API first, so they can be used by name
+        #[arg(long)]
+        update: bool,
+    },
+    #[command(about = "Archive every offer of the favorite dealers as this week's snapshot")]
+    Snapshot {
+        /// How many weekly snapshots to keep [default: snapshot.keep or 52]
+        #[arg(long)]
+        keep: Option<usize>,
+    },
+    #[command(about = "Find the favorite dealers where a shopping list is cheapest")]
+    Basket {
+        /// Items on the shopping list, e.g. "hakket oksekød" mælk [default: the
+        /// items of `list`]
+        items: Vec<String>,
+        /// Shop at no more than this many dealers, 1 for a single store
+        #[arg(long)]
+        max_stores: Option<std::num::NonZeroUsize>,
+    },
+    #[command(about = "Show, add or remove items of the shopping list basket plans for")]
+    List {
+        #[command(subcommand)]
+        command: Option<ListCommands>,
+    },
+    #[command(about = "List new, removed and changed offers since the fetch before the last")]
+    Diff,
+    #[command(about = "Serve the offers of favorite dealers as JSON over HTTP")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+        /// How often to refresh the offers of dealers whose cache is outdated
+        #[arg(long, default_value = "1h")]
+        interval: Span,
+        /// Also register a D-Bus service with a Search method and a NewMatches
+        /// signal for new matches of the watchlist
+        #[arg(long)]
+        dbus: bool,
+    },
+    #[command(about = "Compare the best offer of a product at every dealer")]
+    Compare {
+        /// Product name, or words of it in any order
+        #[arg(required = true)]
+        product: Vec<String>,
+    },
+    #[command(about = "List the nearby stores of favorite dealers")]
+    Stores {
+        /// List the stores of every known dealer
+        #[arg(long)]
+        all: bool,
+    },
+    #[command(
+        about = "Show the past prices of a product and its historical low",
+        args_conflicts_with_subcommands = true
+    )]
+    History {
+        #[command(subcommand)]
+        command: Option<HistoryCommands>,
+        /// Product name, or part of it
+        product: Vec<String>,
+    },
+    #[command(about = "Watch products for offers at or below a target unit price")]
+    Watch {
+        #[command(subcommand)]
+        command: WatchCommands,
+    },
+    #[command(
+        about = "Fetch the offers again and send a desktop notification about new deals \
+        on watched products"
+    )]
+    Notify,
+    #[command(about = "Run a saved search from the queries in the config")]
+    Query {
+        /// Name of the query, or none to list them
+        name: Option<String>,
+    },
+    #[command(about = "Show or edit the config file")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    #[command(about = "Show, locate or clear the cache")]
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+    #[command(about = "Browse the offers of favorite dealers interactively")]
+    Tui,
+    #[command(about = "Search the offers of favorite dealers by product name")]
+    Search {
+        #[arg(required = true)]
+        terms: Vec<String>,
+    },
+    #[command(about = "List, add or remove favorite dealers")]
+    Favorites {
+        #[command(subcommand)]
+        command: Option<FavoritesCommands>,
+    },
+    #[command(about = "Write a report of this week's offers matching a shopping list")]
+    Report {
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Pdf)]
+        format: ReportFormat,
+        /// File to write the report to [default: report.<format>]
+        #[arg(short, long = "file", id = "output_file")]
+        output: Option<PathBuf>,
+        /// Download offer images to the cache and show them in the report
+        #[arg(long)]
+        images: bool,
+        /// Shopping list items to match offers against
+        search: Vec<String>,
+    },
+    #[command(about = "List offers that end soon")]
+    Expiring {
+        /// Show offers ending within this many days
+        #[arg(long, default_value_t = 2)]
+        days: i64,
+        /// Only show offers matching one of these items
+        search: Vec<String>,
+    },
+    #[command(
+        about = "Show the best deals across favorites, compared to earlier offers of \
+        each product or its price before the offer",
+        visible_alias = "surprise"
+    )]
+    Top {
+        /// Number of offers to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: usize,
+    },
+    #[command(about = "Rank offers by the price of their protein or energy")]
+    Nutrition {
+        /// Metric to rank by
+        #[arg(long, value_enum, default_value_t = NutritionMetric::Protein)]
+        by: NutritionMetric,
+        /// Only show offers where the metric costs at most this many kr
+        #[arg(long)]
+        max: Option<f64>,
+        /// Items to look up, nutrition facts are fetched per product
+        #[arg(required = true)]
+        search: Vec<String>,
+    },
+    #[command(
+        about = "Browse the catalogs of the favorite dealers",
+        alias = "catalog"
+    )]
+    Catalogs {
+        #[command(subcommand)]
+        command: CatalogsCommands,
+    },
+    #[command(about = "Show statistics about the offers")]
+    Stats {
+        #[command(subcommand)]
+        command: Option<StatsCommands>,
+    },
+    #[command(about = "Show the most viewed offers of your favorite dealers")]
+    Popular {
+        /// Number of offers to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        count: usize,
+    },
+    #[command(about = "Print links to the cheapest offers, or upload a report to share")]
+    Share {
+        /// Upload an HTML report of the items with share.upload_command instead
+        #[arg(long)]
+        report: bool,
+        /// Items to share offers for
+        #[arg(required = true)]
+        search: Vec<String>,
+    },
+    #[command(about = "Show everything known about an offer")]
+    Show {
+        /// Offer id, as shown by `--json` or with alerts
+        id: String,
+    },
+    #[command(about = "Open an offer on eTilbudsavis in the browser")]
+    Open {
+        /// Offer id, as shown by `--json` or with alerts
+        id: String,
+        /// Open the catalog page the offer is on instead
+        #[arg(long)]
+        catalog: bool,
+    },
+    #[command(about = "List, acknowledge or snooze alerts about offers")]
+    Alerts {
+        #[command(subcommand)]
+        command: Option<AlertsCommands>,
+    },
+    #[command(about = "Import data from other sources")]
+    Import {
+        #[command(subcommand)]
+        command: ImportCommands,
+    },
+    #[command(about = "Export offers to other services")]
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum FavoritesCommands {
+    #[command(about = "List currently set favorites (the default)")]
+    List,
+    #[command(about = "Add dealers to favorites")]
+    Add {
+        /// Dealer names, slugs or ids
+        #[arg(required = true)]
+        dealers: Vec<Dealer>,
+    },
+    #[command(about = "Remove dealers from favorites")]
+    Remove {
+        /// Dealer names, slugs or ids
+        #[arg(required = true)]
+        dealers: Vec<Dealer>,
+    },
+    #[command(
+        about = "Sync favorites both ways with the eTilbudsavis account from the config \
+        (build with `--features account`)"
+    )]
+    Sync,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    #[command(about = "Print where the config file is read from")]
+    Path,
+    #[command(about = "Open the config file in $VISUAL or $EDITOR, creating it if needed")]
+    Edit,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommands {
+    #[command(about = "Show how old and how big the cached offers of every dealer are")]
+    Status,
+    #[command(about = "Remove the cached offers, images, branding and nutrition")]
+    Clear,
+    #[command(about = "Print where the cache is")]
+    Path,
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCommands {
+    #[command(about = "Remove the prices of offers that ended long ago")]
+    Prune {
+        /// Keep the prices of offers that ended within this long, e.g. 26w
+        #[arg(long, default_value = "52w")]
+        older_than: Span,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ListCommands {
+    #[command(about = "Show the items of the shopping list (the default)")]
+    Show,
+    #[command(about = "Add items to the shopping list")]
+    Add {
+        #[arg(required = true)]
+        items: Vec<String>,
+    },
+    #[command(about = "Remove items from the shopping list")]
+    Remove {
+        #[arg(required = true)]
+        items: Vec<String>,
+    },
+    #[command(about = "Remove every item from the shopping list")]
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+enum WatchCommands {
+    #[command(about = "Watch a product, replacing an earlier watch of it")]
+    Add {
+        /// Product name, or part of it
+        term: String,
+        /// Highest cost per unit in kr to alert on
+        #[arg(long)]
+        max: f64,
+        /// Only match offers in this unit, e.g. kg or l
+        #[arg(long)]
+        unit: Option<String>,
+    },
+    #[command(about = "Stop watching a product")]
+    Remove { term: String },
+    #[command(about = "List watched products")]
+    List,
+    #[command(
+        about = "Print current offers of watched products at or below their target, \
+        exiting with status 1 when there are any"
+    )]
+    Check,
+}
+
+#[derive(Subcommand, Debug)]
+enum CatalogsCommands {
+    #[command(about = "List the catalogs with when they are valid and how many offers they have")]
+    List {
+        /// Only list the catalogs of this dealer
+        dealer: Option<Dealer>,
+    },
+    #[command(about = "List the offers of one catalog")]
+    Show {
+        /// Catalog id, as shown by `catalogs list`
+        id: String,
+    },
+    #[command(about = "Download the pages of a catalog to read it offline")]
+    Download {
+        /// Catalog id, as shown by `catalogs list`
+        id: String,
+        /// Save the pages as one PDF instead of an image per page
+        #[arg(long)]
+        pdf: bool,
+        /// Directory, or file with --pdf, to save to [default: catalog-<id>]
+        #[arg(short, long = "file", id = "output_file")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum StatsCommands {
+    #[command(
+        about = "Show offer counts, unit prices, catalogs and cache freshness (the default)"
+    )]
+    Summary,
+    #[command(about = "Summarize the active offers of every favorite dealer")]
     Dealers,
-    #[command(about = "List currently set favorites")]
-    Favorites,
+    #[command(about = "Show a price index of the basket from the config per dealer and month")]
+    Inflation,
 }
 
-async fn handle_search(
-    userdata: &mut UserData,
-    search_items: &Vec<Cow<'_, str>>,
-    favorites_changed: bool,
-    search_by_dealer: bool,
-) -> Vec<Offer> {
-    if !search_items.is_empty() {
-        let mut offers = Vec::new();
-        for search in search_items {
-            let mut temp = retrieve_offers(userdata, favorites_changed).await;
-            if search_by_dealer {
-                if let Ok(dealer) = Dealer::from_str(search) {
-                    temp.retain(|offer| offer.dealer == dealer);
-                } else {
-                    println!("Search term did not match any known dealers: {search}");
-                    Dealer::list_known_dealers();
-                }
-            } else {
-                temp.retain(|offer| offer.name.to_lowercase().contains(search.trim()))
-            }
-            offers.extend(temp);
-        }
-        offers.sort_unstable_by(|a, b| (&a.name, &a.dealer).cmp(&(&b.name, &b.dealer)));
-        offers.dedup();
-        offers
-    } else {
-        retrieve_offers(userdata, favorites_changed).await
-    }
+#[derive(Subcommand, Debug)]
+enum AlertsCommands {
+    #[command(about = "List alerts that have been sent (the default)")]
+    List,
+    #[command(about = "Stop alerting about offers")]
+    Ack {
+        /// Offer ids, as shown with the alert
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    #[command(about = "Stop alerting about offers for a while")]
+    Snooze {
+        /// How long to snooze, e.g. 12h, 3d or 2w
+        duration: Span,
+        /// Offer ids, as shown with the alert
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportCommands {
+    #[command(about = "Add reference prices from a CSV file to the price history")]
+    Prices {
+        /// CSV with product, price and unit columns, and optionally
+        /// cost_per_unit, dealer and date
+        file: PathBuf,
+        /// Name to tag the prices with [default: the file name]
+        #[arg(long)]
+        source: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ExportCommands {
+    #[command(about = "Write the price history as anonymous CSV for the community price dataset")]
+    Community {
+        /// File to write to
+        #[arg(
+            short,
+            long = "file",
+            id = "output_file",
+            default_value = "community-prices.csv"
+        )]
+        output: PathBuf,
+    },
+    #[command(about = "Write an RSS feed of the offers matching the watchlist")]
+    Rss {
+        /// File to write to
+        #[arg(
+            short,
+            long = "file",
+            id = "output_file",
+            default_value = "watchlist.xml"
+        )]
+        output: PathBuf,
+    },
+    #[command(about = "Push offers to a Google Sheet using the service account from the config")]
+    Sheets {
+        /// Spreadsheet id [default: sheets.spreadsheet_id from the config]
+        #[arg(long)]
+        spreadsheet: Option<String>,
+        /// Sheet to write to [default: sheets.sheet from the config or \"Offers\"]
+        #[arg(long)]
+        sheet: Option<String>,
+        /// Keep the existing rows, updating those of the offers by id, instead of
+        /// replacing the sheet contents
+        #[arg(long)]
+        append: bool,
+        /// Only export offers matching one of these items
+        search: Vec<String>,
+    },
 }
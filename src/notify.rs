@@ -0,0 +1,223 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    locale,
+    requests::{dealer::Catalog, history::History, images, offer::Offer},
+    unit,
+    watch::{self, Watch},
+};
+
+/// Most deals listed in a desktop notification.
+const MAX_LISTED: usize = 5;
+
+static NEW_CATALOGS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Shell command sending a notification with the title as `$1`, the
+    /// message as `$2` and the path of the offer image as `$3` (empty without
+    /// one), e.g. `notify-send -i "$3" "$1" "$2"`. Without it notifications
+    /// are printed.
+    pub command: Option<String>,
+    /// Items to notify about when no search terms are given
+    pub terms: Vec<String>,
+    /// Send notifications to the desktop instead of printing them when no
+    /// command is set
+    pub desktop: bool,
+    /// Download the images of the offers to the cache and show them in the
+    /// notifications
+    pub images: bool,
+}
+
+/// Sends a notification with the configured command, or prints it.
+pub fn send(config: &NotifyConfig, title: &str, message: &str, image: Option<&Path>) -> Result<()> {
+    let Some(command) = &config.command else {
+        if config.desktop {
+            return send_desktop(title, message, image);
+        }
+        println!("{title}: {message}");
+        return Ok(());
+    };
+    let image = image.map(Path::as_os_str).unwrap_or_default();
+    let status = Command::new("sh")
+        .args(["-c", command, "sh", title, message])
+        .arg(image)
+        .status()
+        .context("Could not start notification command")?;
+    if !status.success() {
+        bail!("Notification command exited with {status}");
+    }
+    Ok(())
+}
+
+/// Sends a notification about every catalog of a favorite dealer that is new
+/// when fetched from now on, besides logging it.
+pub fn notify_new_catalogs() {
+    NEW_CATALOGS.store(true, Ordering::Relaxed);
+}
+
+/// Tells about a catalog that was not fetched before, with a notification
+/// when asked to by `notify_new_catalogs`.
+pub(crate) fn new_catalog(catalog: &Catalog) {
+    tracing::info!(
+        "New {} avis is out (valid {})",
+        catalog.dealer,
+        catalog.period()
+    );
+    if !NEW_CATALOGS.load(Ordering::Relaxed) {
+        return;
+    }
+    let message = format!(
+        "{} {} {}",
+        catalog.dealer,
+        locale::text("valid", "gælder"),
+        catalog.period()
+    );
+    let config = &crate::config::get_config().notify;
+    if let Err(err) = send(
+        config,
+        locale::text("New catalog", "Ny avis"),
+        &message,
+        None,
+    ) {
+        tracing::warn!("{err:#}");
+    }
+}
+
+/// Notifies about offers matching the terms or at or below the threshold of a
+/// watch that have not been notified about before, and records them. Returns
+/// how many notifications were sent.
+pub async fn notify_new(
+    config: &NotifyConfig,
+    offers: &[Offer],
+    terms: &[String],
+    watchlist: &[Watch],
+) -> Result<usize> {
+    let mut history = History::open()?;
+    let watched = watch::check(watchlist, offers);
+    let mut new: Vec<&Offer> = Vec::new();
+    for offer in offers.iter().filter(|offer| {
+        terms.iter().any(|term| offer.matches(term))
+            || watched.iter().any(|(_, watched)| watched.id == offer.id)
+    }) {
+        if history.should_alert(&offer.id)? {
+            new.push(offer);
+        }
+    }
+    let images = offer_images(config, &new).await;
+    for offer in new.iter() {
+        let mut message = format!(
+            "{} at {}: {} ({}), {} - {}",
+            offer.name,
+            offer.dealer,
+            locale::money(offer.price),
+            unit::cost(offer.cost_per_unit, offer.unit),
+            offer.run_from.format("%d/%m"),
+            offer.run_till.format("%d/%m")
+        );
+        let watch = watched
+            .iter()
+            .find(|(_, watched)| watched.id == offer.id)
+            .map(|(watch, _)| watch);
+        let title = match watch {
+            Some(watch) => {
+                message.push_str(&format!(
+                    ", {} {}",
+                    locale::text("at most", "højst"),
+                    unit::cost(watch.max, offer.unit)
+                ));
+                locale::text("Watched product on offer", "Overvåget vare på tilbud")
+            }
+            None => locale::text("New offer", "Nyt tilbud"),
+        };
+        send(
+            config,
+            title,
+            &message,
+            images.get(&offer.id).map(PathBuf::as_path),
+        )?;
+    }
+    history.mark_alerted(&new)?;
+    Ok(new.len())
+}
+
+/// The downloaded images of the offers by offer id, when asked for in the
+/// config.
+async fn offer_images(config: &NotifyConfig, offers: &[&Offer]) -> HashMap<String, PathBuf> {
+    if !config.images {
+        return HashMap::new();
+    }
+    images::download_images(offers).await
+}
+
+fn send_desktop(title: &str, message: &str, image: Option<&Path>) -> Result<()> {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("etilbudsavis-cli")
+        .summary(title)
+        .body(message);
+    if let Some(image) = image {
+        notification.image_path(&image.to_string_lossy());
+    }
+    notification
+        .show()
+        .context("Could not send desktop notification")?;
+    Ok(())
+}
+
+/// Offers of watched products at or below their target that were not among
+/// the previous offers.
+pub fn new_watch_deals<'a>(
+    watchlist: &'a [Watch],
+    previous: &[Offer],
+    offers: &'a [Offer],
+) -> Vec<(&'a Watch, &'a Offer)> {
+    let seen: HashSet<&str> = previous.iter().map(|offer| offer.id.as_str()).collect();
+    watch::check(watchlist, offers)
+        .into_iter()
+        .filter(|(_, offer)| !seen.contains(offer.id.as_str()))
+        .collect()
+}
+
+/// Sends one desktop notification summarizing the deals, or uses the
+/// configured command when there is one. The image shown is the one of the
+/// first deal.
+pub async fn notify_deals(config: &NotifyConfig, deals: &[(&Watch, &Offer)]) -> Result<()> {
+    let title = match deals.len() {
+        1 => "1 new deal".to_string(),
+        count => format!("{count} new deals"),
+    };
+    let mut lines: Vec<String> = deals
+        .iter()
+        .take(MAX_LISTED)
+        .map(|(_, offer)| {
+            format!(
+                "{} at {}: {} ({})",
+                offer.name,
+                offer.dealer,
+                locale::money(offer.price),
+                unit::cost(offer.cost_per_unit, offer.unit)
+            )
+        })
+        .collect();
+    if deals.len() > MAX_LISTED {
+        lines.push(format!("and {} more", deals.len() - MAX_LISTED));
+    }
+    let message = lines.join("\n");
+    let first: Vec<&Offer> = deals.iter().take(1).map(|(_, offer)| *offer).collect();
+    let images = offer_images(config, &first).await;
+    let image = images.values().next().map(PathBuf::as_path);
+    if config.command.is_some() {
+        send(config, &title, &message, image)
+    } else {
+        send_desktop(&title, &message, image)
+    }
+}
@@ -0,0 +1,166 @@
+//! Python bindings, built with maturin and the `python` feature. They are
+//! imported as `etilbudsavis`:
+//!
+//! ```python
+//! import etilbudsavis
+//!
+//! for offer in etilbudsavis.offers("kaffe"):
+//!     print(offer.name, offer.dealer, offer.cost_per_unit, offer.unit)
+//! ```
+
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
+
+use crate::{
+    requests::history::{History, PricePoint},
+    retrieve_offers, userdata, Offer,
+};
+
+/// An offer from a dealer's catalog.
+#[pyclass(name = "Offer", frozen)]
+struct PyOffer(Offer);
+
+#[pymethods]
+impl PyOffer {
+    #[getter]
+    fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    #[getter]
+    fn dealer(&self) -> &'static str {
+        self.0.dealer.name()
+    }
+
+    #[getter]
+    fn price(&self) -> f64 {
+        self.0.price
+    }
+
+    #[getter]
+    fn pre_price(&self) -> Option<f64> {
+        self.0.pre_price
+    }
+
+    /// Cost per kg, l or piece, see `unit`
+    #[getter]
+    fn cost_per_unit(&self) -> f64 {
+        self.0.cost_per_unit
+    }
+
+    #[getter]
+    fn unit(&self) -> &'static str {
+        self.0.unit.symbol()
+    }
+
+    #[getter]
+    fn size(&self) -> (f64, f64) {
+        (self.0.min_size, self.0.max_size)
+    }
+
+    #[getter]
+    fn amount(&self) -> (u32, u32) {
+        (self.0.min_amount, self.0.max_amount)
+    }
+
+    /// First day of the offer as `YYYY-MM-DD`
+    #[getter]
+    fn run_from(&self) -> String {
+        self.0.run_from.to_string()
+    }
+
+    /// Last day of the offer as `YYYY-MM-DD`
+    #[getter]
+    fn run_till(&self) -> String {
+        self.0.run_till.to_string()
+    }
+
+    #[getter]
+    fn link(&self) -> &str {
+        &self.0.link
+    }
+
+    /// Whether the term is part of the name, the way the CLI searches.
+    fn matches(&self, term: &str) -> bool {
+        self.0.matches(term)
+    }
+
+    /// The offer as JSON, like `--output json`.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.0).map_err(|err| PyRuntimeError::new_err(err.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Offer {} at {}>", self.0.name, self.0.dealer)
+    }
+}
+
+/// The price of a product at a dealer during an offer period.
+#[pyclass(name = "PricePoint", frozen, get_all)]
+struct PyPricePoint {
+    dealer: String,
+    name: String,
+    price: f64,
+    cost_per_unit: f64,
+    unit: String,
+    run_from: String,
+    run_till: String,
+}
+
+impl From<PricePoint> for PyPricePoint {
+    fn from(point: PricePoint) -> Self {
+        PyPricePoint {
+            dealer: point.dealer,
+            name: point.name,
+            price: point.price,
+            cost_per_unit: point.cost_per_unit,
+            unit: point.unit,
+            run_from: point.run_from,
+            run_till: point.run_till,
+        }
+    }
+}
+
+fn runtime_error(err: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{err:#}"))
+}
+
+/// The offers of the favorite dealers, from the cache while it is fresh.
+/// Only offers matching `query` are returned, when it is given.
+#[pyfunction]
+#[pyo3(signature = (query = None, refresh = false))]
+fn offers(py: Python<'_>, query: Option<&str>, refresh: bool) -> PyResult<Vec<PyOffer>> {
+    let mut userdata = userdata::get_userdata().map_err(runtime_error)?;
+    let mut offers = py
+        .detach(|| {
+            let runtime = tokio::runtime::Runtime::new()?;
+            anyhow::Ok(runtime.block_on(retrieve_offers(&mut userdata, refresh)))
+        })
+        .map_err(runtime_error)?;
+    if let Some(query) = query {
+        offers.retain(|offer| offer.matches(query));
+    }
+    Ok(offers.into_iter().map(PyOffer).collect())
+}
+
+/// The past prices of products matching the term in the local price history.
+#[pyfunction]
+fn price_history(term: &str) -> PyResult<Vec<PyPricePoint>> {
+    let points = History::open()
+        .and_then(|history| history.prices_of(term))
+        .map_err(runtime_error)?;
+    Ok(points.into_iter().map(PyPricePoint::from).collect())
+}
+
+#[pymodule]
+fn etilbudsavis(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyOffer>()?;
+    module.add_class::<PyPricePoint>()?;
+    module.add_function(wrap_pyfunction!(offers, module)?)?;
+    module.add_function(wrap_pyfunction!(price_history, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use super::Report;
+use crate::{locale, requests::offer::Offer, unit};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; }
+td.number { text-align: right; white-space: nowrap; }
+td.dealer { border-left: 0.4em solid var(--accent, #ccc); white-space: nowrap; }
+td.dealer img { height: 1.2em; vertical-align: middle; margin-right: 0.4em; }
+td.product img { height: 4em; vertical-align: middle; margin-right: 0.6em; }
+.anomaly { color: #b00; font-weight: bold; }
+";
+
+/// Renders the report as a standalone HTML page, with every dealer's rows
+/// accented in its brand color and its logo next to its name.
+pub fn render(report: &Report) -> String {
+    let mut html = String::new();
+    let title = escape(&report.title());
+    html.push_str(&format!(
+        "<!DOCTYPE html>\n<html lang=\"da\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    ));
+
+    if !report.anomalies.is_empty() {
+        html.push_str("<h2>Exceptional deals</h2>\n<ul>\n");
+        for anomaly in report.anomalies.iter() {
+            let offer = anomaly.offer;
+            html.push_str(&format!(
+                "<li class=\"anomaly\">{} at {}: {}, usually {}</li>\n",
+                escape(&offer.name),
+                offer.dealer,
+                escape(&unit::cost(offer.cost_per_unit, offer.unit)),
+                escape(&unit::cost(anomaly.usual_unit_price, offer.unit))
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("<h2>Shopping list</h2>\n<ul>\n");
+    for (term, offer) in report.shopping_list() {
+        let line = match offer {
+            Some(offer) => format!(
+                "<b>{}</b>: {} at {}, {} ({})",
+                escape(term),
+                escape(&offer.name),
+                offer.dealer,
+                locale::money(offer.price),
+                escape(&unit::cost(offer.cost_per_unit, offer.unit))
+            ),
+            None => format!("<b>{}</b>: no offers this week", escape(term)),
+        };
+        html.push_str(&format!("<li>{line}</li>\n"));
+    }
+    html.push_str("</ul>\n");
+
+    for item in report.items.iter() {
+        html.push_str(&format!(
+            "<h2>{} ({} offers)</h2>\n<table>\n<tr><th>Dealer</th><th>Product</th>\
+             <th>Count</th><th>Price</th><th>Cost/unit</th><th>Period</th></tr>\n",
+            escape(&item.term),
+            item.offers.len()
+        ));
+        for offer in item.offers.iter() {
+            html.push_str(&offer_row(report, offer));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn offer_row(report: &Report, offer: &Offer) -> String {
+    let branding = report.branding.get(&offer.dealer);
+    let accent = branding
+        .and_then(|branding| branding.css_color())
+        .map(|color| format!(" style=\"--accent: {color}\""))
+        .unwrap_or_default();
+    let logo = branding
+        .and_then(|branding| branding.logo.as_deref())
+        .map(|logo| format!("<img src=\"{}\" alt=\"\">", escape(logo)))
+        .unwrap_or_default();
+    let image = report
+        .images
+        .get(&offer.id)
+        .map(|path| format!("<img src=\"{}\" alt=\"\">", escape(&path.to_string_lossy())))
+        .unwrap_or_default();
+    let count = if offer.min_amount == offer.max_amount {
+        offer.min_amount.to_string()
+    } else {
+        format!("{}-{}", offer.min_amount, offer.max_amount)
+    };
+    format!(
+        "<tr><td class=\"dealer\"{accent}>{logo}{}</td><td class=\"product\">{image}{}</td><td class=\"number\">{count}</td>\
+         <td class=\"number\">{}</td><td class=\"number\">{}</td>\
+         <td>{} - {}</td></tr>\n",
+        offer.dealer,
+        escape(&offer.name),
+        locale::money(offer.price),
+        escape(&unit::cost(offer.cost_per_unit, offer.unit)),
+        offer.run_from.format("%d/%m"),
+        offer.run_till.format("%d/%m")
+    )
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
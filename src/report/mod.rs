@@ -0,0 +1,149 @@
+pub mod html;
+pub mod pdf;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    locale,
+    requests::{branding::Branding, dealer::Dealer, offer::Offer},
+    score::{self, Anomaly},
+    week::Week,
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ReportFormat {
+    Pdf,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Pdf => "pdf",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+/// The offers of a single week, grouped by the shopping list item they matched.
+pub struct Report<'a> {
+    pub week: Week,
+    pub items: Vec<ReportItem<'a>>,
+    /// Offers far below their usual unit price.
+    pub anomalies: Vec<Anomaly<'a>>,
+    pub branding: HashMap<Dealer, Branding>,
+    /// Downloaded offer images by offer id.
+    pub images: HashMap<String, PathBuf>,
+}
+
+pub struct ReportItem<'a> {
+    pub term: String,
+    /// Matching offers, cheapest cost per unit first.
+    pub offers: Vec<&'a Offer>,
+}
+
+impl<'a> Report<'a> {
+    /// Builds a report of the offers valid in the given week. Without any
+    /// search terms every offer ends up under a single "All offers" item.
+    pub fn new(offers: &'a [Offer], terms: &[String], week: Week) -> Self {
+        let in_week: Vec<&Offer> = offers.iter().filter(|offer| week.overlaps(offer)).collect();
+        let anomalies = score::find_anomalies(offers)
+            .into_iter()
+            .filter(|anomaly| in_week.contains(&anomaly.offer))
+            .collect();
+
+        let mut items: Vec<ReportItem> = if terms.is_empty() {
+            vec![ReportItem {
+                term: locale::text("All offers", "Alle tilbud").to_string(),
+                offers: in_week,
+            }]
+        } else {
+            terms
+                .iter()
+                .map(|term| ReportItem {
+                    term: term.to_string(),
+                    offers: in_week
+                        .iter()
+                        .copied()
+                        .filter(|offer| offer.matches(term))
+                        .collect(),
+                })
+                .collect()
+        };
+        for item in items.iter_mut() {
+            item.offers
+                .sort_by(|a, b| a.cost_per_unit.total_cmp(&b.cost_per_unit));
+        }
+
+        Report {
+            week,
+            items,
+            anomalies,
+            branding: HashMap::new(),
+            images: HashMap::new(),
+        }
+    }
+
+    pub fn with_branding(mut self, branding: HashMap<Dealer, Branding>) -> Self {
+        self.branding = branding;
+        self
+    }
+
+    pub fn with_images(mut self, images: HashMap<String, PathBuf>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Every offer in the report, once.
+    pub fn offers(&self) -> Vec<&'a Offer> {
+        let mut offers: Vec<&Offer> = self
+            .items
+            .iter()
+            .flat_map(|item| item.offers.iter().copied())
+            .collect();
+        offers.sort_by(|a, b| a.id.cmp(&b.id));
+        offers.dedup_by(|a, b| a.id == b.id);
+        offers
+    }
+
+    /// Every dealer with an offer in the report.
+    pub fn dealers(&self) -> Vec<Dealer> {
+        let mut dealers: Vec<Dealer> = self
+            .items
+            .iter()
+            .flat_map(|item| item.offers.iter().map(|offer| offer.dealer))
+            .collect();
+        dealers.sort();
+        dealers.dedup();
+        dealers
+    }
+
+    pub fn title(&self) -> String {
+        format!(
+            "{} {}",
+            locale::text("Offers", "Tilbud"),
+            self.week.to_string().to_lowercase()
+        )
+    }
+
+    /// The cheapest offer of every shopping list item.
+    pub fn shopping_list(&self) -> impl Iterator<Item = (&str, Option<&Offer>)> {
+        self.items
+            .iter()
+            .map(|item| (item.term.as_str(), item.offers.first().copied()))
+    }
+
+    pub fn write(&self, format: ReportFormat, path: &Path) -> Result<()> {
+        let bytes = match format {
+            ReportFormat::Pdf => pdf::render(self),
+            ReportFormat::Html => html::render(self).into_bytes(),
+        };
+        std::fs::write(path, bytes)
+            .with_context(|| format!("Could not write report to {}", path.display()))
+    }
+}
@@ -0,0 +1,460 @@
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str};
+use std::{collections::HashMap, path::Path};
+
+use super::Report;
+use crate::{locale, requests::offer::Offer, unit};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 40.0;
+const LINE_HEIGHT: f32 = 14.0;
+/// Largest width and height of an offer image.
+const THUMBNAIL: f32 = 24.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// Column layout of the offer rows as (left edge, right aligned).
+const COLUMNS: [(f32, bool); 6] = [
+    (MARGIN, false),
+    (MARGIN + 70.0, false),
+    (MARGIN + 330.0, true),
+    (MARGIN + 390.0, true),
+    (MARGIN + 450.0, true),
+    (PAGE_WIDTH - MARGIN, true),
+];
+const HEADER: [&str; 6] = ["Dealer", "Product", "Count", "Price", "Cost/unit", "Period"];
+
+/// Renders the report as an A4 PDF using the standard Helvetica fonts, so no
+/// font data has to be embedded. The downloaded offer images are embedded as
+/// thumbnails next to the product names.
+pub fn render(report: &Report) -> Vec<u8> {
+    let mut writer = PageWriter::new();
+
+    writer.text(BOLD, 18.0, MARGIN, &report.title());
+    writer.advance(LINE_HEIGHT * 2.0);
+
+    if !report.anomalies.is_empty() {
+        writer.heading("Exceptional deals");
+        for anomaly in report.anomalies.iter() {
+            let offer = anomaly.offer;
+            let line = format!(
+                "! {} at {}: {}, usually {}",
+                offer.name,
+                offer.dealer,
+                unit::cost(offer.cost_per_unit, offer.unit),
+                unit::cost(anomaly.usual_unit_price, offer.unit)
+            );
+            writer.text(
+                BOLD,
+                10.0,
+                MARGIN,
+                &truncate(&line, PAGE_WIDTH - 2.0 * MARGIN, 10.0),
+            );
+            writer.advance(LINE_HEIGHT);
+        }
+        writer.advance(LINE_HEIGHT);
+    }
+
+    writer.heading("Shopping list");
+    for (term, offer) in report.shopping_list() {
+        let line = match offer {
+            Some(offer) => format!(
+                "{term}: {} at {}, {} ({})",
+                offer.name,
+                offer.dealer,
+                locale::money(offer.price),
+                unit::cost(offer.cost_per_unit, offer.unit)
+            ),
+            None => format!("{term}: no offers this week"),
+        };
+        writer.text(
+            REGULAR,
+            10.0,
+            MARGIN,
+            &truncate(&line, PAGE_WIDTH - 2.0 * MARGIN, 10.0),
+        );
+        writer.advance(LINE_HEIGHT);
+    }
+    writer.advance(LINE_HEIGHT);
+
+    for item in report.items.iter() {
+        writer.heading(&format!("{} ({} offers)", item.term, item.offers.len()));
+        writer.row(BOLD, HEADER.map(String::from));
+        for offer in item.offers.iter() {
+            let accent = report
+                .branding
+                .get(&offer.dealer)
+                .and_then(|branding| branding.rgb());
+            let image = report
+                .images
+                .get(&offer.id)
+                .and_then(|path| writer.image(path));
+            match image {
+                Some(image) => writer.image_row(image, accent, offer_row(offer, THUMBNAIL + 4.0)),
+                None => {
+                    if let Some(color) = accent {
+                        writer.accent(color);
+                    }
+                    writer.row(REGULAR, offer_row(offer, 0.0));
+                }
+            }
+        }
+        writer.advance(LINE_HEIGHT);
+    }
+
+    writer.finish()
+}
+
+/// The cells of the offer, with `indent` less room for the product name.
+fn offer_row(offer: &Offer, indent: f32) -> [String; 6] {
+    let count = if offer.min_amount == offer.max_amount {
+        offer.min_amount.to_string()
+    } else {
+        format!("{}-{}", offer.min_amount, offer.max_amount)
+    };
+    [
+        offer.dealer.to_string(),
+        truncate(
+            &offer.name,
+            COLUMNS[2].0 - COLUMNS[1].0 - 40.0 - indent,
+            9.0,
+        ),
+        count,
+        locale::money(offer.price),
+        unit::cost(offer.cost_per_unit, offer.unit),
+        format!(
+            "{} - {}",
+            offer.run_from.format("%d/%m"),
+            offer.run_till.format("%d/%m")
+        ),
+    ]
+}
+
+struct PageWriter {
+    pages: Vec<Content>,
+    /// The images drawn on each page, by index into `images`.
+    page_images: Vec<Vec<usize>>,
+    images: Vec<Jpeg>,
+    image_indices: HashMap<String, usize>,
+    y: f32,
+}
+
+/// A JPEG image, embedded as is.
+struct Jpeg {
+    data: Vec<u8>,
+    width: u16,
+    height: u16,
+    components: u8,
+}
+
+impl PageWriter {
+    fn new() -> Self {
+        PageWriter {
+            pages: vec![Content::new()],
+            page_images: vec![Vec::new()],
+            images: Vec::new(),
+            image_indices: HashMap::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Reads the image to embed, once however often it is drawn. Images that
+    /// are not JPEGs in grayscale or RGB are left out.
+    fn image(&mut self, path: &Path) -> Option<usize> {
+        let key = path.to_string_lossy().to_string();
+        if let Some(&index) = self.image_indices.get(&key) {
+            return Some(index);
+        }
+        let data = std::fs::read(path).ok()?;
+        let Some((width, height, components)) = jpeg_size(&data) else {
+            tracing::debug!("{} is not a JPEG that can be embedded", path.display());
+            return None;
+        };
+        self.images.push(Jpeg {
+            data,
+            width,
+            height,
+            components,
+        });
+        let index = self.images.len() - 1;
+        self.image_indices.insert(key, index);
+        Some(index)
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.page_images.push(Vec::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn advance(&mut self, height: f32) {
+        self.y -= height;
+        if self.y < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn text(&mut self, font: Name, size: f32, x: f32, text: &str) {
+        let content = self.pages.last_mut().expect("there is always a page");
+        content
+            .begin_text()
+            .set_font(font, size)
+            .next_line(x, self.y)
+            .show(Str(&encode(text)))
+            .end_text();
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.text(BOLD, 13.0, MARGIN, text);
+        self.advance(LINE_HEIGHT * 1.5);
+    }
+
+    /// Draws a small bar in the given color left of the next row.
+    fn accent(&mut self, (r, g, b): (u8, u8, u8)) {
+        let content = self.pages.last_mut().expect("there is always a page");
+        content
+            .save_state()
+            .set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+            .rect(MARGIN - 8.0, self.y - 2.0, 4.0, LINE_HEIGHT - 3.0)
+            .fill_nonzero()
+            .restore_state();
+    }
+
+    fn row(&mut self, font: Name, cells: [String; 6]) {
+        self.cells(font, &cells);
+        self.advance(LINE_HEIGHT);
+    }
+
+    fn cells(&mut self, font: Name, cells: &[String; 6]) {
+        for (cell, (x, right_aligned)) in cells.iter().zip(COLUMNS) {
+            let x = if right_aligned {
+                x - text_width(cell, 9.0)
+            } else {
+                x
+            };
+            self.text(font, 9.0, x, cell);
+        }
+    }
+
+    /// A row with the image in front of the product name and the accent in
+    /// the dealer's color, made taller to fit the image.
+    fn image_row(&mut self, image: usize, accent: Option<(u8, u8, u8)>, mut cells: [String; 6]) {
+        // Room above and below the text to fit the image
+        let padding = (THUMBNAIL + 4.0 - LINE_HEIGHT) / 2.0;
+        if self.y - padding - THUMBNAIL < MARGIN {
+            self.new_page();
+        }
+        self.y -= padding;
+        if let Some(color) = accent {
+            self.accent(color);
+        }
+        let Jpeg {
+            width,
+            height: image_height,
+            ..
+        } = self.images[image];
+        let scale = THUMBNAIL / width.max(image_height) as f32;
+        let (width, image_height) = (width as f32 * scale, image_height as f32 * scale);
+        // Centered on the text of the row
+        let x = COLUMNS[1].0;
+        let y = self.y + 3.0 - image_height / 2.0;
+        let name = image_name(image);
+        let content = self.pages.last_mut().expect("there is always a page");
+        content
+            .save_state()
+            .transform([width, 0.0, 0.0, image_height, x, y])
+            .x_object(Name(name.as_bytes()))
+            .restore_state();
+        let page_images = self.page_images.last_mut().expect("there is always a page");
+        if !page_images.contains(&image) {
+            page_images.push(image);
+        }
+        let product = std::mem::take(&mut cells[1]);
+        self.text(REGULAR, 9.0, x + THUMBNAIL + 4.0, &product);
+        self.cells(REGULAR, &cells);
+        self.advance(LINE_HEIGHT + padding);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut pdf = Pdf::new();
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let first_page_id = 5;
+
+        let page_ids: Vec<Ref> = (0..self.pages.len() as i32)
+            .map(|i| Ref::new(first_page_id + 2 * i))
+            .collect();
+
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+        pdf.type1_font(regular_id)
+            .base_font(Name(b"Helvetica"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold_id)
+            .base_font(Name(b"Helvetica-Bold"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+        let first_image_id = first_page_id + 2 * page_ids.len() as i32;
+        let image_ids: Vec<Ref> = (0..self.images.len() as i32)
+            .map(|i| Ref::new(first_image_id + i))
+            .collect();
+
+        for ((content, images), page_id) in
+            self.pages.into_iter().zip(self.page_images).zip(page_ids)
+        {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            resources
+                .fonts()
+                .pair(REGULAR, regular_id)
+                .pair(BOLD, bold_id);
+            if !images.is_empty() {
+                let mut x_objects = resources.x_objects();
+                for image in images {
+                    x_objects.pair(Name(image_name(image).as_bytes()), image_ids[image]);
+                }
+            }
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+
+        for (image, id) in self.images.iter().zip(image_ids) {
+            let mut xobject = pdf.image_xobject(id, &image.data);
+            xobject.filter(Filter::DctDecode);
+            xobject.width(image.width as i32);
+            xobject.height(image.height as i32);
+            xobject.bits_per_component(8);
+            if image.components == 1 {
+                xobject.color_space().device_gray();
+            } else {
+                xobject.color_space().device_rgb();
+            }
+        }
+
+        pdf.finish()
+    }
+}
+
+fn image_name(image: usize) -> String {
+    format!("Im{image}")
+}
+
+/// The width, height and number of color components of a baseline or
+/// progressive JPEG in grayscale or RGB, read from its frame header.
+fn jpeg_size(data: &[u8]) -> Option<(u16, u16, u8)> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut i = 2;
+    while i + 9 < data.len() {
+        if data[i] != 0xFF {
+            return None;
+        }
+        let marker = data[i + 1];
+        if marker == 0xFF {
+            // Padding before a marker
+            i += 1;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        match marker {
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = u16::from_be_bytes([data[i + 5], data[i + 6]]);
+                let width = u16::from_be_bytes([data[i + 7], data[i + 8]]);
+                let components = data[i + 9];
+                return (data[i + 4] == 8
+                    && matches!(components, 1 | 3)
+                    && width > 0
+                    && height > 0)
+                    .then_some((width, height, components));
+            }
+            // The image data starts without a frame header
+            0xDA => return None,
+            _ => i += 2 + length,
+        }
+    }
+    None
+}
+
+/// Encodes text as WinAnsi, which covers the Danish letters. Characters outside
+/// of it are replaced with `?`.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '–' => 0x96,
+            '—' => 0x97,
+            '€' => 0x80,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            c if (c as u32) < 0x80 || (0xA0..=0xFF).contains(&(c as u32)) => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Rough Helvetica text width, good enough for aligning and truncating.
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|c| match c {
+            '0'..='9' => 0.556,
+            ' ' | '.' | ',' | '/' | 'i' | 'l' | 'j' | 'f' | 't' => 0.278,
+            'm' | 'w' | 'M' | 'W' | 'æ' | 'Æ' => 0.833,
+            c if c.is_uppercase() => 0.667,
+            _ => 0.5,
+        })
+        .sum::<f32>()
+        * size
+}
+
+fn truncate(text: &str, max_width: f32, size: f32) -> String {
+    if text_width(text, size) <= max_width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    for c in text.chars() {
+        if text_width(&truncated, size) + text_width(&format!("{c}..."), size) > max_width {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push_str("...");
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_size_of_jpegs() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        // An APP0 segment before the frame header
+        jpeg.extend([0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46]);
+        jpeg.extend([0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x28, 0x00, 0x40, 0x03]);
+        jpeg.extend([0; 9]);
+        assert_eq!(jpeg_size(&jpeg), Some((64, 40, 3)));
+    }
+
+    #[test]
+    fn leaves_out_other_images() {
+        assert_eq!(jpeg_size(b"\x89PNG\r\n\x1a\n0000000000"), None);
+        // CMYK
+        let cmyk = [
+            0xFF, 0xD8, 0xFF, 0xC2, 0x00, 0x14, 0x08, 0x00, 0x28, 0x00, 0x40, 0x04, 0, 0,
+        ];
+        assert_eq!(jpeg_size(&cmyk), None);
+        // Truncated before the frame header
+        assert_eq!(jpeg_size(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10]), None);
+    }
+}
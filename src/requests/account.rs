@@ -0,0 +1,277 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, Method, RequestBuilder};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use super::{
+    dealer::{get_json, Dealer, API},
+    userdata::UserData,
+    SendRetrying,
+};
+
+/// The Tjek API app and the eTilbudsavis account to sync favorites with.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct AccountConfig {
+    /// Key and secret of an app registered with Tjek
+    pub api_key: Option<String>,
+    pub api_secret: Option<String>,
+    /// Login of the eTilbudsavis account
+    pub email: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SessionResponse {
+    token: String,
+    user: Option<User>,
+}
+
+#[derive(Deserialize)]
+struct User {
+    id: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct FavoriteDealer {
+    id: String,
+}
+
+/// A session signed with the app secret and logged in to the account.
+struct Session<'a> {
+    client: Client,
+    token: String,
+    secret: &'a str,
+    user_id: String,
+}
+
+/// What a sync changed, locally and in the account.
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncSummary {
+    pub added: Vec<Dealer>,
+    pub removed: Vec<Dealer>,
+    pub pushed: usize,
+    pub deleted: usize,
+}
+
+/// Syncs the favorites both ways with the eTilbudsavis account. Dealers added
+/// or removed on either side since the last sync are added or removed on the
+/// other, so the phone app and the CLI end up with the same favorites.
+pub async fn sync_favorites(
+    config: &AccountConfig,
+    userdata: &mut UserData,
+) -> Result<SyncSummary> {
+    let session = Session::log_in(config).await?;
+    let remote = session.favorites().await?;
+    let merged = merge(&userdata.favorites, &remote, &userdata.synced_favorites);
+
+    let mut summary = SyncSummary::default();
+    for &dealer in merged.difference(&remote) {
+        session
+            .send(Method::PUT, &session.favorite_path(dealer))
+            .send_retrying()
+            .await
+            .and_then(check_status)
+            .with_context(|| format!("Could not add {dealer} to the account favorites"))?;
+        summary.pushed += 1;
+    }
+    for &dealer in remote.difference(&merged) {
+        session
+            .send(Method::DELETE, &session.favorite_path(dealer))
+            .send_retrying()
+            .await
+            .and_then(check_status)
+            .with_context(|| format!("Could not remove {dealer} from the account favorites"))?;
+        summary.deleted += 1;
+    }
+    summary.added = merged.difference(&userdata.favorites).copied().collect();
+    summary.removed = userdata.favorites.difference(&merged).copied().collect();
+    summary.added.sort();
+    summary.removed.sort();
+    userdata.favorites = merged.clone();
+    userdata.synced_favorites = merged;
+    Ok(summary)
+}
+
+/// The favorites after a sync. A dealer is kept when it is favorite on both
+/// sides, or was added on one side since the last sync. Dealers removed on
+/// either side since then are left out.
+fn merge(
+    local: &HashSet<Dealer>,
+    remote: &HashSet<Dealer>,
+    synced: &HashSet<Dealer>,
+) -> HashSet<Dealer> {
+    local
+        .union(remote)
+        .filter(|dealer| {
+            let both = local.contains(dealer) && remote.contains(dealer);
+            both || !synced.contains(dealer)
+        })
+        .copied()
+        .collect()
+}
+
+impl<'a> Session<'a> {
+    /// Creates a session for the app and logs in to the account with it.
+    async fn log_in(config: &'a AccountConfig) -> Result<Session<'a>> {
+        let missing = |key: &str| format!("No account.{key} set in the config");
+        let api_key = config
+            .api_key
+            .as_deref()
+            .with_context(|| missing("api_key"))?;
+        let secret = config
+            .api_secret
+            .as_deref()
+            .with_context(|| missing("api_secret"))?;
+        let email = config.email.as_deref().with_context(|| missing("email"))?;
+        let password = config
+            .password
+            .as_deref()
+            .with_context(|| missing("password"))?;
+
+        let client = super::client()?;
+        let response = client
+            .post(format!("{API}/sessions"))
+            .json(&json!({ "api_key": api_key }))
+            .header("Accept", "application/json")
+            .send_retrying()
+            .await?;
+        let created: SessionResponse = serde_json::from_value(get_json(response).await?)
+            .context("Could not create a Tjek API session")?;
+        let mut session = Session {
+            client,
+            token: created.token,
+            secret,
+            user_id: String::new(),
+        };
+        let response = session
+            .send(Method::PUT, "sessions")
+            .json(&json!({ "email": email, "password": password }))
+            .send_retrying()
+            .await?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED
+            || response.status() == reqwest::StatusCode::FORBIDDEN
+        {
+            anyhow::bail!(
+                "Could not log in to the eTilbudsavis account, \
+                check account.email and account.password"
+            );
+        }
+        let logged_in: SessionResponse = serde_json::from_value(get_json(response).await?)
+            .context("Could not log in to the eTilbudsavis account")?;
+        let user = logged_in
+            .user
+            .context("Logging in to the eTilbudsavis account gave no user")?;
+        session.token = logged_in.token;
+        session.user_id = match user.id {
+            serde_json::Value::String(id) => id,
+            id => id.to_string(),
+        };
+        Ok(session)
+    }
+
+    /// A request to the path of the API, signed with the session token.
+    fn send(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{API}/{path}"))
+            .header("Accept", "application/json")
+            .header("X-Token", &self.token)
+            .header("X-Signature", signature(self.secret, &self.token))
+    }
+
+    fn favorites_path(&self) -> String {
+        format!("users/{}/favorites/dealers", self.user_id)
+    }
+
+    fn favorite_path(&self, dealer: Dealer) -> String {
+        format!("{}/{}", self.favorites_path(), dealer.id())
+    }
+
+    /// The favorite dealers of the account. Dealers that are not known are
+    /// left out, and kept in the account.
+    async fn favorites(&self) -> Result<HashSet<Dealer>> {
+        let response = self
+            .send(Method::GET, &self.favorites_path())
+            .send_retrying()
+            .await?;
+        let favorites: Vec<FavoriteDealer> = serde_json::from_value(get_json(response).await?)
+            .context("Could not read the favorites of the account")?;
+        Ok(favorites
+            .into_iter()
+            .filter_map(|favorite| {
+                let dealer = Dealer::from_id(&favorite.id);
+                if dealer.is_none() {
+                    tracing::warn!(
+                        "Favorite dealer {} of the account is not known, run `dealers --update`",
+                        favorite.id
+                    );
+                }
+                dealer
+            })
+            .collect())
+    }
+}
+
+/// The signature of a session token, the SHA-256 of the app secret followed
+/// by the token in hex.
+fn signature(secret: &str, token: &str) -> String {
+    Sha256::digest(format!("{secret}{token}"))
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    if !response.status().is_success() {
+        return Err(crate::error::Error::Status(response.status()).into());
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dealers(names: &[&str]) -> HashSet<Dealer> {
+        names
+            .iter()
+            .map(|name| Dealer::from_str(name).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn first_sync_keeps_the_favorites_of_both_sides() {
+        let merged = merge(&dealers(&["Netto"]), &dealers(&["Rema1000"]), &dealers(&[]));
+        assert_eq!(merged, dealers(&["Netto", "Rema1000"]));
+    }
+
+    #[test]
+    fn keeps_dealers_added_on_either_side() {
+        let synced = dealers(&["Netto"]);
+        let local = dealers(&["Netto", "Aldi"]);
+        let remote = dealers(&["Netto", "Rema1000"]);
+        assert_eq!(
+            merge(&local, &remote, &synced),
+            dealers(&["Netto", "Aldi", "Rema1000"])
+        );
+    }
+
+    #[test]
+    fn leaves_out_dealers_removed_on_either_side() {
+        let synced = dealers(&["Netto", "Aldi", "Rema1000"]);
+        let local = dealers(&["Netto", "Rema1000"]);
+        let remote = dealers(&["Netto", "Aldi"]);
+        assert_eq!(merge(&local, &remote, &synced), dealers(&["Netto"]));
+    }
+
+    #[test]
+    fn signs_the_token_with_the_secret() {
+        assert_eq!(
+            signature("secret", "token"),
+            "a78dd2126c3ef3a0fafe52ae69a366340edd5046474eab3d03402f95fef8289e"
+        );
+    }
+}
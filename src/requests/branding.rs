@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use futures::future;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::dealer::Dealer;
+
+/// Logo and brand color of a dealer, as published by the API.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Branding {
+    pub logo: Option<String>,
+    /// Hex color without the leading `#`
+    pub color: Option<String>,
+}
+
+impl Branding {
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        let color = self.color.as_deref()?.trim_start_matches('#');
+        if color.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(color.get(i..i + 2)?, 16).ok();
+        Some((channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    pub fn css_color(&self) -> Option<String> {
+        self.rgb()
+            .map(|(r, g, b)| format!("#{r:02x}{g:02x}{b:02x}"))
+    }
+}
+
+/// Branding of the given dealers. Brandings are cached, so only dealers that
+/// have not been seen before are fetched. Dealers that could not be fetched
+/// are left out.
+pub async fn get_branding(dealers: &[Dealer]) -> HashMap<Dealer, Branding> {
+    let mut branding = cached_branding().unwrap_or_default();
+    let missing: Vec<Dealer> = dealers
+        .iter()
+        .filter(|dealer| !branding.contains_key(dealer))
+        .copied()
+        .collect();
+    if missing.is_empty() {
+        return branding;
+    }
+
+    let Ok(client) = super::client() else {
+        return branding;
+    };
+    let fetched = future::join_all(
+        missing
+            .iter()
+            .map(|dealer| retrieve_branding(dealer, &client)),
+    )
+    .await;
+    for (dealer, result) in missing.into_iter().zip(fetched) {
+        match result {
+            Ok(fetched) => {
+                branding.insert(dealer, fetched);
+            }
+            Err(err) => tracing::warn!("Could not fetch branding of {dealer}: {err:#}"),
+        }
+    }
+    if let Err(err) = cache_branding(&branding) {
+        tracing::error!("{err:#}");
+    }
+    branding
+}
+
+async fn retrieve_branding(dealer: &Dealer, client: &Client) -> Result<Branding> {
+    client
+        .get(format!(
+            "https://squid-api.tjek.com/v2/dealers/{}",
+            dealer.id()
+        ))
+        .header("Accept", "application/json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Branding>()
+        .await
+        .context("Dealer returned invalid JSON")
+}
+
+fn cached_branding() -> Result<HashMap<Dealer, Branding>> {
+    let path = dirs::cache_dir()
+        .context("Could not find cache dir")?
+        .join("etilbudsavis-cli/branding.json");
+    let data = std::fs::read_to_string(path).context("Branding cache not found")?;
+    serde_json::from_str(&data).context("Branding cache has invalid JSON")
+}
+
+fn cache_branding(branding: &HashMap<Dealer, Branding>) -> Result<()> {
+    let path = dirs::cache_dir()
+        .context("Could not find cache dir")?
+        .join("etilbudsavis-cli");
+    std::fs::create_dir_all(&path)?;
+    std::fs::write(
+        path.join("branding.json"),
+        serde_json::to_string(branding).context("Failed to serialize branding to JSON")?,
+    )
+    .context("could not write branding cache")
+}
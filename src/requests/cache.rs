@@ -0,0 +1,368 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{duration::Span, share};
+
+use super::{
+    dealer::{Catalog, Dealer},
+    offer::Offer,
+    registry,
+};
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// How long cached offers are used, e.g. `6h` or `2d` [default: until the
+    /// next day]
+    pub ttl: Option<Span>,
+}
+
+/// Version of the cache files. Raise it whenever `DealerCache` or `Offer`
+/// change shape, and bring older caches up to date in `migrate`.
+pub const CACHE_VERSION: u32 = 2;
+
+/// The offers of one dealer as they were last fetched.
+#[derive(Serialize, Deserialize)]
+pub struct DealerCache {
+    /// Caches from before versioning are version 0
+    #[serde(default)]
+    pub version: u32,
+    pub fetched_at: DateTime<Utc>,
+    /// When the first of the catalogs the offers are from ends
+    pub run_till: Option<NaiveDate>,
+    pub offers: Vec<Offer>,
+}
+
+impl DealerCache {
+    /// Stamps the offers with the end of the first catalog to end, or of the
+    /// first offer when there are no catalogs.
+    pub fn new(catalogs: &[Catalog], offers: Vec<Offer>) -> Self {
+        let run_till = catalogs
+            .iter()
+            .filter_map(Catalog::run_till_date)
+            .min()
+            .or_else(|| offers.iter().map(|offer| offer.run_till).min());
+        DealerCache {
+            version: CACHE_VERSION,
+            fetched_at: Utc::now(),
+            run_till,
+            offers,
+        }
+    }
+
+    /// Whether the offers can be used without fetching them again: they are
+    /// younger than `ttl`, or were fetched today without a ttl, and none of
+    /// the catalogs have ended.
+    pub fn is_fresh(&self, ttl: Option<Duration>) -> bool {
+        let now = Utc::now();
+        let young = match ttl {
+            Some(ttl) => now - self.fetched_at < ttl,
+            None => self.fetched_at.date_naive() == now.date_naive(),
+        };
+        let ended = self.run_till.is_some_and(|till| till < now.date_naive());
+        young && !ended
+    }
+
+    /// How long ago the offers were fetched, e.g. "5 hours" or "3 days".
+    pub fn age(&self) -> String {
+        match (Utc::now() - self.fetched_at).num_hours() {
+            hours if hours < 48 => format!("{hours} hours"),
+            hours => format!("{} days", hours / 24),
+        }
+    }
+}
+
+/// Where etilbudsavis-cli caches offers, along with the favorites and the
+/// price history.
+pub fn root_dir() -> Result<PathBuf> {
+    Ok(dirs::cache_dir()
+        .context("Could not find cache dir")?
+        .join("etilbudsavis-cli"))
+}
+
+fn cache_dir() -> Result<PathBuf> {
+    Ok(root_dir()?.join("offers"))
+}
+
+fn cache_path(dealer: Dealer) -> Result<PathBuf> {
+    Ok(cache_dir()?.join(format!("{}.json", registry::slug(dealer.name()))))
+}
+
+/// Where the offers of the fetch before the last one are kept, for `diff`.
+fn previous_path(dealer: Dealer) -> Result<PathBuf> {
+    Ok(cache_dir()?
+        .join("previous")
+        .join(format!("{}.json", registry::slug(dealer.name()))))
+}
+
+pub fn read(dealer: Dealer) -> Result<DealerCache> {
+    migrate_offer_cache();
+    let data = std::fs::read_to_string(cache_path(dealer)?)
+        .with_context(|| format!("No cached offers of {dealer}"))?;
+    parse(&data).map_err(|err| {
+        // The offers are fetched again and the cache written anew
+        let action = if super::is_offline() {
+            "ignoring it"
+        } else {
+            "fetching the offers again"
+        };
+        tracing::warn!("The offer cache of {dealer} {err:#}, {action}");
+        err
+    })
+}
+
+/// The offers of the dealer as they were fetched before the last time.
+pub fn read_previous(dealer: Dealer) -> Result<DealerCache> {
+    let data = std::fs::read_to_string(previous_path(dealer)?)
+        .with_context(|| format!("No earlier fetch of {dealer}"))?;
+    parse(&data).map_err(|err| anyhow!("The previous offers of {dealer} {err:#}"))
+}
+
+/// Reads a cache file of any version up to the current one, migrating it.
+/// Errors read as the rest of a sentence about the file, like "has invalid
+/// JSON".
+fn parse(data: &str) -> Result<DealerCache> {
+    #[derive(Deserialize)]
+    struct Versioned {
+        #[serde(default)]
+        version: u32,
+    }
+
+    let version = serde_json::from_str::<Versioned>(data)
+        .context("has invalid JSON")?
+        .version;
+    if version > CACHE_VERSION {
+        bail!("was written by a newer version of etilbudsavis-cli (cache version {version})");
+    }
+    let mut cache: DealerCache = serde_json::from_str(data).with_context(|| {
+        if version < CACHE_VERSION {
+            format!("is from an older version (cache version {version})")
+        } else {
+            "can not be read".to_string()
+        }
+    })?;
+    migrate(&mut cache);
+    Ok(cache)
+}
+
+/// Brings a cache of an earlier version up to date.
+fn migrate(cache: &mut DealerCache) {
+    if cache.version < 1 {
+        for offer in cache.offers.iter_mut() {
+            offer.link = share::link_to(&offer.id);
+        }
+    }
+    // Version 2 only allows the units kg, l and stk. Caches with other units
+    // can not be read, and are fetched again.
+    cache.version = CACHE_VERSION;
+}
+
+/// Caches the offers, keeping a copy of the ones they replace as the
+/// previous fetch. The cached offers are replaced in one go, so readers
+/// never see them missing or half written. Call it while holding `lock`.
+pub fn write(dealer: Dealer, cache: &DealerCache) -> Result<()> {
+    std::fs::create_dir_all(cache_dir()?.join("previous"))?;
+    let json = serde_json::to_string(cache).context("Failed to serialize offers to JSON")?;
+    let path = cache_path(dealer)?;
+    if let Ok(current) = std::fs::read(&path) {
+        write_atomic(&previous_path(dealer)?, &current)
+            .with_context(|| format!("Could not keep the previous offers of {dealer}"))?;
+    }
+    write_atomic(&path, json.as_bytes())
+        .with_context(|| format!("Could not write offer cache of {dealer}"))
+}
+
+/// Waits for and takes the lock of the offer cache, which is released when
+/// the file is dropped. Runs refreshing the cache at the same time, like one
+/// from cron and one by hand, take turns holding it, so only the first one
+/// fetches. The waiting is done on a blocking thread, so it does not stall
+/// the async runtime.
+pub async fn lock() -> Result<File> {
+    tokio::task::spawn_blocking(lock_blocking)
+        .await
+        .context("Could not lock the offer cache")?
+}
+
+/// Like `lock`, blocking the thread while waiting.
+fn lock_blocking() -> Result<File> {
+    let dir = cache_dir()?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    let path = dir.join(".lock");
+    let file = File::create(&path).with_context(|| format!("Could not open {}", path.display()))?;
+    file.lock().context("Could not lock the offer cache")?;
+    Ok(file)
+}
+
+/// Writes the file through a temporary file next to it that is renamed over
+/// it, so readers see either the old or the new contents and never a half
+/// written file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+    let written = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp, path)
+    })();
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written.with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// The cached offers of every dealer, however old they are.
+pub fn read_all() -> Result<Vec<Offer>> {
+    migrate_offer_cache();
+    let entries = std::fs::read_dir(cache_dir()?).context("Offer cache not found")?;
+    let mut offers = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            let data = std::fs::read_to_string(&path)?;
+            let cache = parse(&data).map_err(|err| anyhow!("{} {err:#}", path.display()))?;
+            offers.extend(cache.offers);
+        }
+    }
+    Ok(offers)
+}
+
+/// The cached offers of a dealer, for `cache status`.
+#[derive(Serialize)]
+pub struct CacheEntry {
+    pub dealer: String,
+    pub offers: usize,
+    pub fetched_at: DateTime<Utc>,
+    /// How long ago the offers were fetched, e.g. "5 hours"
+    pub age: String,
+    /// Whether the offers are used without fetching them again
+    pub fresh: bool,
+    pub bytes: u64,
+}
+
+/// The cached offers of every dealer, by dealer. Files that can not be read
+/// are left out with a warning.
+pub fn entries(ttl: Option<Duration>) -> Result<Vec<CacheEntry>> {
+    migrate_offer_cache();
+    let Ok(dir) = std::fs::read_dir(cache_dir()?) else {
+        return Ok(Vec::new());
+    };
+    let mut entries = Vec::new();
+    for entry in dir {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let data = std::fs::read_to_string(&path)?;
+        let cache = match parse(&data) {
+            Ok(cache) => cache,
+            Err(err) => {
+                tracing::warn!("{} {err:#}", path.display());
+                continue;
+            }
+        };
+        let dealer = match cache.offers.first() {
+            Some(offer) => offer.dealer.to_string(),
+            None => path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        };
+        entries.push(CacheEntry {
+            dealer,
+            offers: cache.offers.len(),
+            fetched_at: cache.fetched_at,
+            age: cache.age(),
+            fresh: cache.is_fresh(ttl),
+            bytes: data.len() as u64,
+        });
+    }
+    entries.sort_by(|a, b| a.dealer.cmp(&b.dealer));
+    Ok(entries)
+}
+
+/// What `clear` removes from the cache dir. The favorites, the price
+/// history, the dealers found with `dealers --update` and the snapshots are
+/// kept.
+pub const CLEARED: [&str; 4] = ["offers", "images", "branding.json", "nutrition.json"];
+
+/// Removes the cached offers, images, branding and nutrition, returning how
+/// many bytes that freed.
+pub fn clear() -> Result<u64> {
+    let root = root_dir()?;
+    let _lock = lock_blocking().ok();
+    let mut freed = 0;
+    for name in CLEARED {
+        let path = root.join(name);
+        let removed = if path.is_dir() {
+            let size = size_of(&path);
+            std::fs::remove_dir_all(&path).map(|()| size)
+        } else if path.exists() {
+            let size = size_of(&path);
+            std::fs::remove_file(&path).map(|()| size)
+        } else {
+            continue;
+        };
+        freed += removed.with_context(|| format!("Could not remove {}", path.display()))?;
+    }
+    Ok(freed)
+}
+
+/// Size of the file, or of everything in the directory, in bytes.
+pub fn size_of(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| size_of(&entry.path()))
+        .sum()
+}
+
+/// Splits the single `offer_cache.json` of earlier versions into a cache per
+/// dealer, keeping when it was written.
+fn migrate_offer_cache() {
+    let (Some(dir), Ok(new_dir)) = (dirs::cache_dir(), cache_dir()) else {
+        return;
+    };
+    let old = dir.join("etilbudsavis-cli/offer_cache.json");
+    if new_dir.exists() || !old.exists() {
+        return;
+    }
+    let migrated = (|| -> Result<()> {
+        let fetched_at: DateTime<Utc> = std::fs::metadata(&old)?.modified()?.into();
+        let offers: Vec<Offer> = serde_json::from_str(&std::fs::read_to_string(&old)?)?;
+        let mut by_dealer: HashMap<Dealer, Vec<Offer>> = HashMap::new();
+        for offer in offers {
+            by_dealer.entry(offer.dealer).or_default().push(offer);
+        }
+        std::fs::create_dir_all(&new_dir)?;
+        for (dealer, offers) in by_dealer {
+            let mut cache = DealerCache::new(&[], offers);
+            cache.fetched_at = fetched_at;
+            write(dealer, &cache)?;
+        }
+        std::fs::remove_file(&old)?;
+        Ok(())
+    })();
+    if let Err(err) = migrated {
+        tracing::error!("Could not move the offer cache to a cache per dealer: {err:#}");
+    }
+}
@@ -1,175 +1,566 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, Result};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+use crate::{style, suggest};
+
+use super::{deserialize::deserialize_dealer_name, registry};
+
+#[cfg(feature = "native")]
 use futures::future;
+#[cfg(feature = "native")]
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use std::str::FromStr;
-use strum::{EnumIter, IntoEnumIterator};
+#[cfg(feature = "native")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "native")]
+use std::collections::HashSet;
+
+#[cfg(feature = "native")]
+use crate::error::Error;
 
+#[cfg(feature = "native")]
 use super::{
-    deserialize::{deserialize_dealer_name, deserialize_offer, OfferWrapper},
-    offer::Offer,
+    deserialize::{
+        deserialize_listed_offer, deserialize_offer, ListedOffer, OfferWrapper, Skipped,
+    },
+    incito,
+    offer::{merge_catalog_duplicates, Offer, OfferSender, Source},
+    progress::FetchStatus,
+    SendRetrying,
 };
-#[derive(
-    Hash,
-    Debug,
-    EnumIter,
-    Serialize,
-    Deserialize,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-    Clone,
-    Copy,
-    Default,
-    // ValueEnum,
-)]
-pub enum Dealer {
-    Rema1000,
-    Netto,
-    DagliBrugsen,
-    SuperBrugsen,
-    Aldi,
-    Bilka,
-    Coop365,
-    Irma,
-    Føtex,
-    Lidl,
-    Meny,
-    Kvickly,
-    #[default]
-    Spar,
+
+/// The offers of a dealer as fetched from the API.
+#[cfg(feature = "native")]
+pub struct FetchedOffers {
+    pub catalogs: Vec<Catalog>,
+    pub offers: Vec<Offer>,
+    /// Ids of the catalogs whose offers could not be fetched
+    pub failed_catalogs: Vec<String>,
 }
 
+/// A dealer in the dealer registry. Dealers other than the built-in ones are
+/// known after `dealers --update` has fetched the full dealer list.
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Dealer(usize);
+
 impl Dealer {
-    fn id(&self) -> &'static str {
-        match self {
-            Dealer::Rema1000 => "11deC",
-            Dealer::Netto => "9ba51",
-            Dealer::DagliBrugsen => "d311fg",
-            Dealer::Aldi => "98b7e",
-            Dealer::Bilka => "93f13",
-            Dealer::Coop365 => "DWZE1w",
-            Dealer::Irma => "d432U",
-            Dealer::Føtex => "bdf5A",
-            Dealer::Lidl => "71c90",
-            Dealer::Meny => "267e1m",
-            Dealer::Kvickly => "c1edq",
-            Dealer::Spar => "88ddE",
-            Dealer::SuperBrugsen => "0b1e8",
-        }
+    pub fn id(&self) -> &'static str {
+        registry::entry(self.0).id
+    }
+
+    pub fn name(&self) -> &'static str {
+        registry::entry(self.0).name
+    }
+
+    pub fn from_id(id: &str) -> Option<Dealer> {
+        Dealer::all().into_iter().find(|dealer| dealer.id() == id)
     }
-    pub(crate) fn list_known_dealers() {
-        let mut table = comfy_table::Table::new();
 
-        table
-            .load_preset(comfy_table::presets::UTF8_FULL)
-            .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
-            .set_header(vec!["Dealers"]);
-        for dealer in Dealer::iter() {
-            table.add_row(vec![dealer.to_string()]);
+    /// Every known dealer, the built-in ones first.
+    pub fn all() -> Vec<Dealer> {
+        (0..registry::len()).map(Dealer).collect()
+    }
+}
+
+#[cfg(feature = "native")]
+impl Dealer {
+    pub fn list_known_dealers() {
+        let mut table = style::table();
+        table.set_header(vec!["Dealers", "Slug", "Id"]);
+        for dealer in Dealer::all() {
+            table.add_row(vec![
+                dealer.to_string(),
+                registry::slug(dealer.name()),
+                dealer.id().to_string(),
+            ]);
         }
         println!("{table}");
     }
-    pub(crate) async fn remote_offers_for_dealer(&self) -> Vec<Offer> {
-        let client = Client::new();
-        let catalogs = retrieve_catalogs_from_dealer(self, &client)
-            .await
-            .unwrap_or_default();
+
+    /// The current catalogs of the dealer.
+    pub async fn catalogs(&self) -> Result<Vec<Catalog>> {
+        retrieve_catalogs_from_dealer(self, &super::client()?).await
+    }
+
+    /// The offers in the current catalogs of the dealer, fetched from the API
+    /// without going through the cache.
+    pub async fn offers(&self) -> Result<Vec<Offer>> {
+        Ok(self
+            .fetch_offers(None, &FetchStatus::hidden())
+            .await?
+            .offers)
+    }
+
+    /// The current catalogs of the dealer along with all of their offers. The
+    /// offers of every catalog are also sent to `sender` once it is parsed.
+    /// Fails when no offers could be fetched at all, while failing catalogs
+    /// are listed in `failed_catalogs` otherwise.
+    pub async fn remote_offers_for_dealer(
+        &self,
+        sender: Option<OfferSender>,
+        status: FetchStatus,
+    ) -> Result<FetchedOffers> {
+        let result = self.fetch_offers(sender, &status).await;
+        let offers = result.as_ref().ok().map(|fetched| fetched.offers.len());
+        status.finished(*self, offers);
+        result
+    }
+
+    async fn fetch_offers(
+        &self,
+        sender: Option<OfferSender>,
+        status: &FetchStatus,
+    ) -> Result<FetchedOffers> {
+        let client = super::client()?;
+        let catalogs = match retrieve_catalogs_from_dealer(self, &client).await {
+            Ok(catalogs) => catalogs,
+            Err(err) => {
+                status.note(&format!(
+                    "Could not list the catalogs of {self} ({err:#}), \
+                    falling back to the offers endpoint"
+                ));
+                let query = [("dealer_ids", self.id().to_string())];
+                let offers = retrieve_listed_offers(&client, &query, None, status).await?;
+                if let Some(sender) = sender {
+                    let _ = sender.send(offers.clone());
+                }
+                return Ok(FetchedOffers {
+                    catalogs: Vec::new(),
+                    offers,
+                    failed_catalogs: Vec::new(),
+                });
+            }
+        };
+        status.catalogs_found(*self, catalogs.len());
         let tasks: Vec<_> = catalogs
-            .into_iter()
+            .iter()
             .map(|catalog| {
                 let catalog = catalog.clone();
                 let client = client.clone();
-                tokio::spawn(async move { retrieve_offers_from_catalog(catalog, &client).await })
+                let sender = sender.clone();
+                let status = status.clone();
+                let dealer = *self;
+                tokio::spawn(async move {
+                    let offers =
+                        retrieve_offers_from_catalog(catalog, dealer, &client, &status).await;
+                    status.catalog_done(dealer, offers.as_ref().map_or(0, Vec::len));
+                    if let (Some(sender), Ok(offers)) = (sender, offers.as_ref()) {
+                        // Nobody listening is fine, the offers are returned as well
+                        let _ = sender.send(offers.clone());
+                    }
+                    offers
+                })
             })
             .collect();
 
-        future::join_all(tasks)
-            .await
-            .into_iter()
-            .flatten()
-            .flatten()
-            .flatten()
-            .collect()
+        let mut offers = Vec::new();
+        let mut failed_catalogs = Vec::new();
+        let mut error = None;
+        for (catalog, result) in catalogs.iter().zip(future::join_all(tasks).await) {
+            match result
+                .map_err(anyhow::Error::from)
+                .and_then(|offers| offers)
+            {
+                Ok(catalog_offers) => offers.extend(catalog_offers),
+                Err(err) => {
+                    failed_catalogs.push(catalog.id.clone());
+                    error = Some(err);
+                }
+            }
+        }
+        if let Some(err) = error {
+            if offers.is_empty() {
+                return Err(err);
+            }
+            status.note(&format!(
+                "Could not fetch {} of the catalogs of {self} ({err:#})",
+                failed_catalogs.len()
+            ));
+        }
+        Ok(FetchedOffers {
+            catalogs,
+            offers: merge_catalog_duplicates(offers),
+            failed_catalogs,
+        })
     }
 }
 
 impl std::fmt::Display for Dealer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::fmt::Debug for Dealer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Default for Dealer {
+    fn default() -> Self {
+        Dealer::from_str("Spar").expect("Spar is a built-in dealer")
     }
 }
 
 impl FromStr for Dealer {
     type Err = anyhow::Error;
 
+    /// Accepts the name, slug or Tjek id of the dealer, ignoring case, spaces
+    /// and punctuation.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = match s.to_lowercase().trim() {
-            "bilka" => Dealer::Bilka,
-            "coop365" => Dealer::Coop365,
-            "lidl" => Dealer::Lidl,
-            "rema1000" | "rema 1000" => Dealer::Rema1000,
-            "spar" => Dealer::Spar,
-            "meny" => Dealer::Meny,
-            "føtex" => Dealer::Føtex,
-            "irma" => Dealer::Irma,
-            "aldi" => Dealer::Aldi,
-            "netto" => Dealer::Netto,
-            "kvickly" => Dealer::Kvickly,
-            "daglibrugsen" | "dagli'brugsen" => Dealer::DagliBrugsen,
-            "superbrugsen" => Dealer::SuperBrugsen,
-            _ => {
-                return Err(anyhow!(
-                    "Unknown dealer: {s}.\nSee `dealers` for available dealers."
-                ))
-            }
+        if let Some(index) = registry::find(s) {
+            return Ok(Dealer(index));
+        }
+        let names: Vec<String> = Dealer::all()
+            .iter()
+            .map(|dealer| dealer.name().to_lowercase())
+            .collect();
+        let suggestions: Vec<&str> =
+            suggest::closest(&s.trim().to_lowercase(), names.iter().map(String::as_str))
+                .iter()
+                .filter_map(|name| registry::find(name))
+                .map(|index| Dealer(index).name())
+                .collect();
+        let hint = if suggestions.is_empty() {
+            String::new()
+        } else {
+            format!(" Did you mean {}?", suggestions.join(", "))
         };
-        Ok(value)
+        Err(anyhow!(
+            "Unknown dealer: {s}.{hint}\nSee `dealers` for available dealers, or \
+            `dealers --update` to fetch every dealer from the API."
+        ))
+    }
+}
+
+impl Serialize for Dealer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
     }
 }
 
+impl<'de> Deserialize<'de> for Dealer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Dealer::from_str(&name).map_err(de::Error::custom)
+    }
+}
+
+/// A catalog (leaflet) of a dealer.
 #[derive(Deserialize, Clone)]
-struct Catalog {
-    id: String,
+pub struct Catalog {
+    pub id: String,
     #[serde(deserialize_with = "deserialize_dealer_name")]
-    dealer: String,
+    pub dealer: String,
+    pub run_from: String,
+    pub run_till: String,
+    /// Set for catalogs published in the incito format, as a document
+    /// instead of pages
+    #[serde(default)]
+    pub incito_publication_id: Option<String>,
+}
+
+impl Catalog {
+    /// The last day the catalog is valid.
+    pub fn run_till_date(&self) -> Option<chrono::NaiveDate> {
+        let date = self.run_till.split('T').next()?;
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+    }
+
+    /// The period the catalog is valid in, e.g. "12/5–18/5".
+    pub fn period(&self) -> String {
+        let format = |date: &str| {
+            let date = date.split('T').next().unwrap_or_default();
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(|date| date.format("%-d/%-m").to_string())
+                .unwrap_or_else(|_| date.to_string())
+        };
+        format!(
+            "{}{}{}",
+            format(&self.run_from),
+            style::symbol("–", "-"),
+            format(&self.run_till)
+        )
+    }
 }
 
+#[cfg(feature = "native")]
+pub(super) const API: &str = "https://squid-api.tjek.com/v2";
+
+/// Most offers the offers endpoint returns per page.
+#[cfg(feature = "native")]
+pub(super) const PAGE_SIZE: usize = 100;
+
+/// Pages of a list fetched at most, in case the API keeps returning full
+/// pages.
+#[cfg(feature = "native")]
+pub(super) const MAX_PAGES: usize = 100;
+
+#[cfg(feature = "native")]
 async fn retrieve_catalogs_from_dealer(dealer: &Dealer, client: &Client) -> Result<Vec<Catalog>> {
-    let catalog_response = client
-        .get("https://squid-api.tjek.com/v2/catalogs")
+    let response = client
+        .get(format!("{API}/catalogs"))
         .query(&[("dealer_ids", dealer.id())])
         .header("Accept", "application/json")
-        .send()
+        .send_retrying()
         .await?;
-    match catalog_response.status() {
-        reqwest::StatusCode::OK => catalog_response
-            .json::<Vec<Catalog>>()
-            .await
-            .context("Dealer returned invalid JSON"),
-        _ => {
-            println!(
-                "Did not succesfully access API, StatusCode: {}",
-                catalog_response.status()
-            );
-            Ok(Vec::new())
+    let (catalogs, skipped) = parse_each(get_json(response).await?)?;
+    if skipped > 0 && catalogs.is_empty() {
+        return Err(Error::MissingFields {
+            what: "catalogs",
+            skipped,
         }
+        .into());
     }
+    Ok(catalogs)
 }
 
-async fn retrieve_offers_from_catalog(catalog: Catalog, client: &Client) -> Result<Vec<Offer>> {
-    let offers = client
-        .get(format!(
-            "https://squid-api.tjek.com/v2/catalogs/{}/hotspots",
-            catalog.id.as_str()
-        ))
-        .send()
-        .await?
-        .json::<Vec<OfferWrapper>>()
-        .await?
+/// The offers of the catalog from its hotspots, or from the offers endpoint
+/// when the hotspots can not be fetched or read or there are none. The
+/// offers of incito catalogs are read from the incito document first, as
+/// they have no hotspots.
+#[cfg(feature = "native")]
+async fn retrieve_offers_from_catalog(
+    catalog: Catalog,
+    dealer: Dealer,
+    client: &Client,
+    status: &FetchStatus,
+) -> Result<Vec<Offer>> {
+    if let Some(publication_id) = catalog.incito_publication_id.as_deref() {
+        match retrieve_incito_offers(&catalog, publication_id, client, status).await {
+            Ok(offers) if !offers.is_empty() => return Ok(offers),
+            Ok(_) => {}
+            Err(err) => status.note(&format!(
+                "Could not read the incito catalog of {} ({err:#})",
+                catalog.dealer
+            )),
+        }
+    }
+    match retrieve_hotspots(&catalog, dealer, client, status).await {
+        Ok(offers) if !offers.is_empty() => return Ok(offers),
+        Ok(_) => {}
+        Err(err) => status.note(&format!(
+            "Could not read the hotspots of a {} catalog ({err:#}), \
+            falling back to the offers endpoint",
+            catalog.dealer
+        )),
+    }
+    let offers =
+        retrieve_listed_offers(client, &[("catalog_ids", catalog.id.clone())], None, status)
+            .await?;
+    if !offers.is_empty() {
+        return Ok(offers);
+    }
+    // Some catalogs have neither hotspots nor offers listed under them. The
+    // offers of the dealer's other catalogs that this brings along are
+    // merged with their own copies later.
+    retrieve_listed_offers(
+        client,
+        &[("dealer_ids", dealer.id().to_string())],
+        None,
+        status,
+    )
+    .await
+}
+
+#[cfg(feature = "native")]
+async fn retrieve_hotspots(
+    catalog: &Catalog,
+    dealer: Dealer,
+    client: &Client,
+    status: &FetchStatus,
+) -> Result<Vec<Offer>> {
+    let response = client
+        .get(format!("{API}/catalogs/{}/hotspots", catalog.id))
+        .header("Accept", "application/json")
+        .send_retrying()
+        .await?;
+    let mut skipped = Skipped::default();
+    let hotspots: Vec<OfferWrapper> = skipped.parse_each(elements(get_json(response).await?)?);
+    let offers: Vec<Offer> = hotspots
+        .into_iter()
+        .filter_map(|ow| skipped.keep(deserialize_offer(ow, dealer, catalog)))
+        .collect();
+    skipped.report(
+        &format!("the {} catalog {}", catalog.dealer, catalog.id),
+        status,
+    );
+    if offers.is_empty() && skipped.total() > 0 {
+        return Err(Error::MissingFields {
+            what: "hotspots",
+            skipped: skipped.total(),
+        }
+        .into());
+    }
+    Ok(offers)
+}
+
+#[cfg(feature = "native")]
+#[derive(Deserialize)]
+struct CatalogPage {
+    thumb: Option<String>,
+    view: Option<String>,
+    zoom: Option<String>,
+}
+
+/// URLs of the page images of the catalog, in page order and in the largest
+/// size there is of each.
+#[cfg(feature = "native")]
+pub async fn catalog_pages(catalog_id: &str) -> Result<Vec<String>> {
+    let response = super::client()?
+        .get(format!("{API}/catalogs/{catalog_id}/pages"))
+        .header("Accept", "application/json")
+        .send_retrying()
+        .await?;
+    let (pages, skipped): (Vec<CatalogPage>, usize) = parse_each(get_json(response).await?)?;
+    let urls: Vec<String> = pages
         .into_iter()
-        .map(|ow| deserialize_offer(ow, &catalog.dealer))
+        .filter_map(|page| page.zoom.or(page.view).or(page.thumb))
         .collect();
+    if urls.is_empty() && skipped > 0 {
+        return Err(Error::MissingFields {
+            what: "catalog pages",
+            skipped,
+        }
+        .into());
+    }
+    Ok(urls)
+}
+
+/// The offers shown in the incito document of the catalog, fetched by id
+/// from the offers endpoint.
+#[cfg(feature = "native")]
+async fn retrieve_incito_offers(
+    catalog: &Catalog,
+    publication_id: &str,
+    client: &Client,
+    status: &FetchStatus,
+) -> Result<Vec<Offer>> {
+    let ids = incito::offer_ids(client, publication_id).await?;
+    let mut offers = Vec::with_capacity(ids.len());
+    for ids in ids.chunks(PAGE_SIZE) {
+        let query = [("offer_ids", ids.join(","))];
+        offers.extend(retrieve_listed_offers(client, &query, None, status).await?);
+    }
+    for offer in offers.iter_mut().filter(|offer| offer.sources.is_empty()) {
+        offer.sources.push(Source {
+            catalog_id: catalog.id.clone(),
+            page: None,
+        });
+    }
     Ok(offers)
 }
+
+/// Offers from the offers endpoint, fetching pages until there are no more
+/// or `limit` offers have been fetched.
+#[cfg(feature = "native")]
+async fn retrieve_listed_offers(
+    client: &Client,
+    query: &[(&str, String)],
+    limit: Option<usize>,
+    status: &FetchStatus,
+) -> Result<Vec<Offer>> {
+    let mut offers: Vec<Offer> = Vec::new();
+    let mut ids = HashSet::new();
+    let mut skipped = Skipped::default();
+    let described = || {
+        let query: Vec<String> = query
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        format!("the offers with {}", query.join("&"))
+    };
+    // Counts the rows of the API, also the ones that could not be parsed
+    let mut offset = 0;
+    let mut pages = 0;
+    loop {
+        let page_size = limit.map_or(PAGE_SIZE, |limit| (limit - offers.len()).min(PAGE_SIZE));
+        let response = client
+            .get(format!("{API}/offers"))
+            .query(query)
+            .query(&[
+                ("limit", page_size.to_string()),
+                ("offset", offset.to_string()),
+            ])
+            .header("Accept", "application/json")
+            .send_retrying()
+            .await?;
+        let elements = elements(get_json(response).await?)?;
+        let count = elements.len();
+        offset += count;
+        let page: Vec<ListedOffer> = skipped.parse_each(elements);
+        let parsed: Vec<Offer> = page
+            .into_iter()
+            .filter_map(|listed| skipped.keep(deserialize_listed_offer(listed)))
+            .collect();
+        let before = offers.len();
+        let any_parsed = !parsed.is_empty();
+        offers.extend(
+            parsed
+                .into_iter()
+                .filter(|offer| ids.insert(offer.id.clone())),
+        );
+        pages += 1;
+        let done = count < page_size || limit.is_some_and(|limit| offers.len() >= limit);
+        // An API ignoring the offset returns the same page again and again
+        let repeated = any_parsed && offers.len() == before;
+        if !done && (repeated || pages >= MAX_PAGES) {
+            tracing::warn!("Stopped paging through {} after {pages} pages", described());
+        }
+        if done || repeated || pages >= MAX_PAGES {
+            skipped.report(&described(), status);
+            return Ok(offers);
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+pub(super) async fn get_json(response: reqwest::Response) -> Result<serde_json::Value> {
+    if !response.status().is_success() {
+        return Err(Error::Status(response.status()).into());
+    }
+    let body = response.text().await.map_err(Error::Network)?;
+    Ok(serde_json::from_str(&body).map_err(Error::Json)?)
+}
+
+/// Reads every element of a JSON array on its own, so a change in the shape
+/// of some elements does not lose the rest. Returns the elements that could
+/// be read and how many could not.
+#[cfg(feature = "native")]
+pub(super) fn parse_each<T: DeserializeOwned>(value: serde_json::Value) -> Result<(Vec<T>, usize)> {
+    let elements = elements(value)?;
+    let mut parsed = Vec::with_capacity(elements.len());
+    let mut skipped = 0;
+    for element in elements {
+        match serde_json::from_value(element) {
+            Ok(element) => parsed.push(element),
+            Err(_) => skipped += 1,
+        }
+    }
+    Ok((parsed, skipped))
+}
+
+#[cfg(feature = "native")]
+fn elements(value: serde_json::Value) -> Result<Vec<serde_json::Value>> {
+    match value {
+        serde_json::Value::Array(elements) => Ok(elements),
+        _ => Err(Error::NotAList.into()),
+    }
+}
+
+/// The most popular offers of the dealers right now, most popular first.
+#[cfg(feature = "native")]
+pub async fn retrieve_popular_offers(dealers: &[Dealer], limit: usize) -> Result<Vec<Offer>> {
+    let dealer_ids: Vec<&str> = dealers.iter().map(Dealer::id).collect();
+    retrieve_listed_offers(
+        &super::client()?,
+        &[
+            ("dealer_ids", dealer_ids.join(",")),
+            ("order_by", "-popularity".to_string()),
+        ],
+        Some(limit),
+        &FetchStatus::hidden(),
+    )
+    .await
+}
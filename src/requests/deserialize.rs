@@ -1,7 +1,85 @@
-use std::str::FromStr;
+use chrono::NaiveDate;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-use crate::Offer;
-use serde::Deserialize;
+#[cfg(feature = "native")]
+use super::progress::FetchStatus;
+use super::{
+    dealer::{Catalog, Dealer},
+    offer::Source,
+};
+use crate::{share, unit, Offer};
+use serde::{de::DeserializeOwned, Deserialize};
+
+static LENIENT: AtomicBool = AtomicBool::new(false);
+static SHOW_SKIPPED: AtomicBool = AtomicBool::new(false);
+
+/// Fills in a name or dates missing from an offer instead of leaving the
+/// offer out. The name defaults to the description and the dates to those
+/// of the catalog.
+pub fn set_lenient(lenient: bool) {
+    LENIENT.store(lenient, Ordering::Relaxed);
+}
+
+/// Notes how many offers were left out while parsing, and why, for every
+/// catalog.
+pub fn set_show_skipped(show: bool) {
+    SHOW_SKIPPED.store(show, Ordering::Relaxed);
+}
+
+/// Offers that were left out while parsing, counted by why.
+#[derive(Default, Debug)]
+pub struct Skipped(BTreeMap<String, usize>);
+
+impl Skipped {
+    pub fn add(&mut self, reason: impl ToString) {
+        *self.0.entry(reason.to_string()).or_default() += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.values().sum()
+    }
+
+    /// The element, or None after counting why it was left out.
+    pub fn keep<T, E: ToString>(&mut self, element: Result<T, E>) -> Option<T> {
+        element.map_err(|reason| self.add(reason)).ok()
+    }
+
+    /// Reads every element of a JSON array on its own, counting the ones
+    /// that could not be read.
+    pub fn parse_each<T: DeserializeOwned>(&mut self, elements: Vec<serde_json::Value>) -> Vec<T> {
+        elements
+            .into_iter()
+            .filter_map(|element| self.keep(serde_json::from_value(element)))
+            .collect()
+    }
+
+    /// Notes the skipped offers of `what` with `--show-skipped`, and logs
+    /// them at debug level otherwise.
+    #[cfg(feature = "native")]
+    pub fn report(&self, what: &str, status: &FetchStatus) {
+        if self.0.is_empty() {
+            return;
+        }
+        let reasons: Vec<String> = self
+            .0
+            .iter()
+            .map(|(reason, count)| format!("{count} {reason}"))
+            .collect();
+        let message = format!(
+            "Skipped {} offers of {what}: {}",
+            self.total(),
+            reasons.join(", ")
+        );
+        if SHOW_SKIPPED.load(Ordering::Relaxed) {
+            status.note(&message);
+        } else {
+            tracing::debug!("{message}");
+        }
+    }
+}
 
 pub fn deserialize_dealer_name<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
@@ -19,28 +97,43 @@ where
 #[derive(Deserialize)]
 pub struct OfferWrapper {
     offer: Outer,
+    /// Outline of the offer by catalog page number
+    #[serde(default)]
+    locations: HashMap<String, serde_json::Value>,
 }
 #[derive(Deserialize)]
 struct Outer {
     id: String,
     #[serde(rename = "heading")]
-    name: String,
+    name: Option<String>,
     pricing: Pricing,
-    run_from: String,
-    run_till: String,
+    run_from: Option<String>,
+    run_till: Option<String>,
+    /// Missing or partial for e.g. electronics, which are not sold by size
+    #[serde(default)]
     quantity: Quantity,
+    images: Option<Images>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Images {
+    thumb: Option<String>,
+    view: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct Pricing {
     price: f64,
+    pre_price: Option<f64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
+#[serde(default)]
 struct Quantity {
-    unit: Unit,
-    pieces: Pieces,
-    size: Size,
+    unit: Option<Unit>,
+    pieces: Option<Pieces>,
+    size: Option<Size>,
 }
 
 #[derive(Deserialize)]
@@ -57,7 +150,8 @@ struct Pieces {
 
 #[derive(Deserialize)]
 struct Unit {
-    si: SI,
+    symbol: Option<String>,
+    si: Option<SI>,
 }
 
 #[derive(Deserialize)]
@@ -66,31 +160,268 @@ struct SI {
     factor: f64,
 }
 
-pub(crate) fn deserialize_offer(offer_wrapper: OfferWrapper, dealer_name: &str) -> Offer {
-    let offer = &offer_wrapper.offer;
-    let factor = &offer.quantity.unit.si.factor;
-    let pieces = &offer.quantity.pieces;
-    let size = &offer.quantity.size;
-    Offer {
+/// An offer from the offers endpoint, which unlike hotspots are not wrapped.
+#[derive(Deserialize)]
+pub struct ListedOffer {
+    #[serde(flatten)]
+    offer: Outer,
+    dealer_id: String,
+    catalog_id: Option<String>,
+    catalog_page: Option<u32>,
+}
+
+/// The offer, or why it was left out.
+pub fn deserialize_listed_offer(listed: ListedOffer) -> Result<Offer, String> {
+    let dealer =
+        Dealer::from_id(&listed.dealer_id).ok_or_else(|| "of unknown dealers".to_string())?;
+    let mut offer = to_offer(&listed.offer, dealer, None)?;
+    if let Some(catalog_id) = listed.catalog_id {
+        offer.sources.push(Source {
+            catalog_id,
+            page: listed.catalog_page,
+        });
+    }
+    Ok(offer)
+}
+
+/// The offer of the dealer's catalog, or why it was left out.
+pub fn deserialize_offer(
+    offer_wrapper: OfferWrapper,
+    dealer: Dealer,
+    catalog: &Catalog,
+) -> Result<Offer, String> {
+    let mut offer = to_offer(&offer_wrapper.offer, dealer, Some(catalog))?;
+    offer.sources.push(Source {
+        catalog_id: catalog.id.clone(),
+        page: offer_wrapper
+            .locations
+            .keys()
+            .filter_map(|page| page.parse().ok())
+            .min(),
+    });
+    Ok(offer)
+}
+
+/// The date of an API timestamp like `2024-05-12T00:00:00+0200`.
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.split('T').next()?, "%Y-%m-%d").ok()
+}
+
+/// A date of the offer, or when lenient the one of the catalog or today
+/// when it is missing or can not be read.
+fn offer_date(
+    field: &str,
+    date: Option<&str>,
+    catalog_date: Option<&str>,
+) -> Result<NaiveDate, String> {
+    let parsed = date.and_then(parse_date);
+    if let Some(date) = parsed {
+        return Ok(date);
+    }
+    if LENIENT.load(Ordering::Relaxed) {
+        return Ok(catalog_date
+            .and_then(parse_date)
+            .unwrap_or_else(|| chrono::Utc::now().date_naive()));
+    }
+    Err(match date {
+        Some(_) => format!("with an unreadable {field}"),
+        None => format!("missing field `{field}`"),
+    })
+}
+
+/// The bottle deposit (pant) a description like "Inkl. pant 3,00 kr" says
+/// is part of the price. Descriptions like "+ pant" or "ekskl. pant" mean a
+/// price without it, and so give None.
+fn deposit_in(description: &str) -> Option<f64> {
+    let description = description.to_lowercase();
+    let at = description.find("pant")?;
+    let before: String = {
+        let mut before: Vec<char> = description[..at].chars().rev().take(16).collect();
+        before.reverse();
+        before.into_iter().collect()
+    };
+    if ["ekskl", "excl", "eksl", "plus", "+"]
+        .iter()
+        .any(|word| before.contains(word))
+    {
+        return None;
+    }
+    let amount = |text: &str| {
+        text.split(|c: char| !(c.is_ascii_digit() || c == ','))
+            .filter_map(|number| {
+                number
+                    .trim_matches(',')
+                    .replace(',', ".")
+                    .parse::<f64>()
+                    .ok()
+            })
+            .find(|amount| *amount > 0.0 && *amount <= 50.0)
+    };
+    let after: String = description[at + "pant".len()..].chars().take(12).collect();
+    // Only amounts in kr before it, not e.g. "6 stk. inkl. pant"
+    amount(&after).or_else(|| amount(&before).filter(|_| before.contains("kr")))
+}
+
+/// How many pieces a multipack like "3 for 20 kr", "6 stk." or "4-pak"
+/// contains, going by its name.
+fn multipack_pieces(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let count = |word: &str| {
+        word.parse::<u32>()
+            .ok()
+            .filter(|count| (2..=100).contains(count))
+    };
+    words.iter().enumerate().find_map(|(index, word)| {
+        for suffix in ["-pak", "-pack", "pak", "-stk", "x"] {
+            if let Some(count) = word.strip_suffix(suffix).and_then(count) {
+                return Some(count);
+            }
+        }
+        let next = words.get(index + 1)?.trim_end_matches(['.', ',']);
+        ["for", "stk", "x", "pak", "pk"]
+            .contains(&next)
+            .then(|| count(word))
+            .flatten()
+    })
+}
+
+fn to_offer(offer: &Outer, dealer: Dealer, catalog: Option<&Catalog>) -> Result<Offer, String> {
+    let name = match (&offer.name, &offer.description) {
+        (Some(name), _) => name.clone(),
+        (None, Some(description)) if LENIENT.load(Ordering::Relaxed) => description
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+        _ => return Err("missing field `heading`".to_string()),
+    };
+    let run_from = offer_date(
+        "run_from",
+        offer.run_from.as_deref(),
+        catalog.map(|catalog| catalog.run_from.as_str()),
+    )?;
+    let run_till = offer_date(
+        "run_till",
+        offer.run_till.as_deref(),
+        catalog.map(|catalog| catalog.run_till.as_str()),
+    )?;
+    let mut pieces = offer
+        .quantity
+        .pieces
+        .as_ref()
+        .map_or((1, 1), |pieces| (pieces.from, pieces.to.max(1)));
+    let unit = offer.quantity.unit.as_ref();
+    let si = unit.and_then(|unit| unit.si.as_ref());
+    // The unit the dealer gave the size in, normalized, or else the SI unit
+    // the API converted it to. Offers in units that can not be normalized are
+    // priced per piece.
+    let measure: Option<(unit::Unit, f64)> = unit
+        .and_then(|unit| unit.symbol.as_deref())
+        .and_then(unit::Unit::parse)
+        .or_else(|| {
+            let si = si?;
+            unit::Unit::parse(&si.symbol).map(|(unit, factor)| (unit, factor * si.factor))
+        });
+    let size = offer.quantity.size.as_ref().filter(|size| size.to > 0.0);
+    // A size in pieces, like eggs in "10 stk", is a count rather than a size
+    let counted = measure
+        .as_ref()
+        .is_some_and(|(unit, _)| *unit == unit::Unit::Stk);
+    if counted {
+        if let Some(size) = size {
+            pieces = (
+                pieces.0 * size.from.round().max(1.0) as u32,
+                pieces.1 * size.to.round().max(1.0) as u32,
+            );
+        }
+    }
+    let sized = measure.zip(size).filter(|_| !counted);
+    if sized.is_none() && pieces == (1, 1) {
+        if let Some(count) = multipack_pieces(&name) {
+            pieces = (count, count);
+        }
+    }
+    // Offers without a size, like most electronics and toys, are priced per piece
+    let (min_size, max_size, si_unit, factor) = match sized {
+        Some(((unit, factor), size)) => (size.from * factor, size.to * factor, unit, Some(factor)),
+        None => (1.0, 1.0, unit::Unit::Stk, None),
+    };
+    let cost_per_unit = offer.pricing.price / max_size / pieces.1 as f64;
+    Ok(Offer {
         id: offer.id.to_owned(),
-        name: offer.name.to_owned(),
+        name,
         price: offer.pricing.price,
-        min_amount: pieces.from,
-        max_amount: pieces.to,
-        min_size: size.from * factor,
-        max_size: size.to * factor,
-        unit: offer.quantity.unit.si.symbol.to_owned(),
-        cost_per_unit: offer.pricing.price / (size.to * factor) / pieces.to as f64,
-        dealer: super::dealer::Dealer::from_str(dealer_name).unwrap_or_default(),
-        run_from: chrono::NaiveDate::parse_from_str(
-            offer.run_from.split('T').next().unwrap(),
-            "%Y-%m-%d",
-        )
-        .expect("failed to format NaiveDate from API date"),
-        run_till: chrono::NaiveDate::parse_from_str(
-            offer.run_till.split('T').next().unwrap(),
-            "%Y-%m-%d",
-        )
-        .expect("failed to format NaiveDate from API date"),
+        pre_price: offer.pricing.pre_price,
+        min_amount: pieces.0,
+        max_amount: pieces.1,
+        min_size,
+        max_size,
+        unit: si_unit,
+        size_unit: unit
+            .and_then(|unit| unit.symbol.clone())
+            .filter(|_| factor.is_some()),
+        si_factor: factor,
+        cost_per_unit,
+        dealer,
+        run_from,
+        run_till,
+        deposit: offer.description.as_deref().and_then(deposit_in),
+        sources: Vec::new(),
+        image_url: offer
+            .images
+            .as_ref()
+            .and_then(|images| images.thumb.clone().or_else(|| images.view.clone())),
+        link: share::link_to(&offer.id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposit_included_in_the_price() {
+        assert_eq!(deposit_in("Inkl. pant 3,00 kr"), Some(3.0));
+        assert_eq!(deposit_in("pant 1,50"), Some(1.5));
+        assert_eq!(deposit_in("1,5 l. Inkl. 1,95 kr pant"), Some(1.95));
+    }
+
+    #[test]
+    fn no_deposit_when_it_is_not_included() {
+        assert_eq!(deposit_in("Pr. flaske + pant"), None);
+        assert_eq!(deposit_in("Ekskl. pant"), None);
+        assert_eq!(deposit_in("Plus pant 3,00 kr"), None);
+        assert_eq!(deposit_in("Frit valg"), None);
+    }
+
+    #[test]
+    fn counts_before_the_deposit_are_not_amounts() {
+        assert_eq!(deposit_in("6 stk. inkl. pant"), None);
+        assert_eq!(deposit_in("24 x 33 cl inkl. pant"), None);
+    }
+
+    #[test]
+    fn multipacks() {
+        assert_eq!(multipack_pieces("Croissanter 3 for 20 kr"), Some(3));
+        assert_eq!(multipack_pieces("Cola 6 stk."), Some(6));
+        assert_eq!(multipack_pieces("Pilsner 4-pak"), Some(4));
+        assert_eq!(multipack_pieces("Yoghurt 2x"), Some(2));
+    }
+
+    #[test]
+    fn single_pieces_and_sizes_are_not_multipacks() {
+        assert_eq!(multipack_pieces("Agurk 1 stk"), None);
+        assert_eq!(multipack_pieces("Hvedemel 200 g"), None);
+        assert_eq!(multipack_pieces("Kaffe"), None);
+    }
+
+    #[test]
+    fn years_and_sizes_in_names_are_not_counts() {
+        assert_eq!(multipack_pieces("Juleøl 2024"), None);
+        assert_eq!(multipack_pieces("Rødvin 2019 75 cl"), None);
+        assert_eq!(multipack_pieces("Mel 1000 g"), None);
+        assert_eq!(multipack_pieces("Sodavand 33 cl"), None);
     }
 }
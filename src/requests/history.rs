@@ -0,0 +1,452 @@
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::{collections::HashMap, str::FromStr};
+
+use crate::unit;
+
+use super::{
+    dealer::{Catalog, Dealer},
+    offer::Offer,
+};
+
+/// Schema changes, applied in order. `PRAGMA user_version` holds how many of
+/// them have been applied to a database.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE prices (
+        offer_id TEXT NOT NULL,
+        dealer TEXT NOT NULL,
+        name TEXT NOT NULL,
+        price REAL NOT NULL,
+        cost_per_unit REAL NOT NULL,
+        unit TEXT NOT NULL,
+        run_from TEXT NOT NULL,
+        run_till TEXT NOT NULL,
+        fetched_on TEXT NOT NULL,
+        PRIMARY KEY (offer_id, fetched_on)
+    );
+    CREATE INDEX prices_name ON prices (name COLLATE NOCASE, unit);",
+    "CREATE TABLE catalogs (
+        catalog_id TEXT PRIMARY KEY,
+        dealer TEXT NOT NULL,
+        run_from TEXT NOT NULL,
+        run_till TEXT NOT NULL,
+        first_seen TEXT NOT NULL
+    );",
+    "CREATE TABLE alerts (
+        offer_id TEXT PRIMARY KEY,
+        dealer TEXT NOT NULL,
+        name TEXT NOT NULL,
+        notified_at TEXT NOT NULL,
+        acknowledged INTEGER NOT NULL DEFAULT 0,
+        snoozed_until TEXT
+    );",
+    "ALTER TABLE prices ADD COLUMN offer TEXT;",
+    "ALTER TABLE prices ADD COLUMN source TEXT NOT NULL DEFAULT 'catalog';",
+];
+
+/// Source of prices fetched from the dealers' catalogs.
+const CATALOG_SOURCE: &str = "catalog";
+
+/// Format of timestamps in the database, which sorts chronologically as text.
+const TIMESTAMP: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// The price of a product at a dealer during an offer period.
+#[derive(Serialize)]
+pub struct PricePoint {
+    pub dealer: String,
+    pub name: String,
+    pub price: f64,
+    pub cost_per_unit: f64,
+    pub unit: String,
+    pub run_from: String,
+    pub run_till: String,
+}
+
+/// An alert about an offer that has been sent.
+#[derive(Serialize)]
+pub struct Alert {
+    pub offer_id: String,
+    pub dealer: String,
+    pub name: String,
+    pub notified_at: NaiveDateTime,
+    pub acknowledged: bool,
+    pub snoozed_until: Option<NaiveDateTime>,
+}
+
+/// Local price history, recording every offer each day it is fetched.
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    pub fn open() -> Result<Self> {
+        let path = dirs::cache_dir()
+            .context("Could not find cache dir")?
+            .join("etilbudsavis-cli");
+        std::fs::create_dir_all(&path)?;
+        let conn = Connection::open(path.join("history.sqlite"))
+            .context("Could not open price history")?;
+        let mut history = History { conn };
+        history.migrate()?;
+        Ok(history)
+    }
+
+    fn migrate(&mut self) -> Result<()> {
+        let version: i64 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration)
+                .context("Could not migrate price history")?;
+            tx.pragma_update(None, "user_version", i as i64 + 1)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    pub fn record(&mut self, offers: &[Offer]) -> Result<()> {
+        let today = Utc::now().date_naive().to_string();
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR IGNORE INTO prices
+                (offer_id, dealer, name, price, cost_per_unit, unit, run_from, run_till, fetched_on, offer)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            for offer in offers {
+                insert.execute(params![
+                    offer.id,
+                    offer.dealer.to_string(),
+                    offer.name,
+                    offer.price,
+                    offer.cost_per_unit,
+                    offer.unit.symbol(),
+                    offer.run_from.to_string(),
+                    offer.run_till.to_string(),
+                    today,
+                    serde_json::to_string(offer).ok(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Removes the prices of offers that ended before the date, leaving
+    /// alerts and catalogs. Returns how many prices were removed.
+    pub fn prune(&mut self, before: NaiveDate) -> Result<usize> {
+        let removed = self.conn.execute(
+            "DELETE FROM prices WHERE run_till < ?1",
+            params![before.to_string()],
+        )?;
+        Ok(removed)
+    }
+
+    /// Records prices from another source than the catalogs, e.g. a price
+    /// comparison site. Returns how many were new.
+    pub fn import(&mut self, source: &str, points: &[PricePoint]) -> Result<usize> {
+        if source == CATALOG_SOURCE {
+            bail!("The source {CATALOG_SOURCE} is reserved for prices from catalogs");
+        }
+        let tx = self.conn.transaction()?;
+        let mut imported = 0;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR IGNORE INTO prices
+                (offer_id, dealer, name, price, cost_per_unit, unit, run_from, run_till, fetched_on, source)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?7, ?9)",
+            )?;
+            for point in points {
+                let id = format!(
+                    "{source}:{}:{}:{}",
+                    point.dealer, point.name, point.run_from
+                );
+                imported += insert.execute(params![
+                    id,
+                    point.dealer,
+                    point.name,
+                    point.price,
+                    point.cost_per_unit,
+                    point.unit,
+                    point.run_from,
+                    point.run_till,
+                    source,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(imported)
+    }
+
+    /// Unit prices of earlier offers for the same product, one per offer, and
+    /// not including the offer itself. Imported prices are included.
+    pub fn past_unit_prices(&self, offer: &Offer) -> Result<Vec<f64>> {
+        let mut query = self.conn.prepare_cached(
+            "SELECT MIN(cost_per_unit) FROM prices
+            WHERE name = ?1 COLLATE NOCASE AND unit = ?2 AND offer_id != ?3
+            GROUP BY offer_id",
+        )?;
+        let prices = query
+            .query_map(params![offer.name, offer.unit.symbol(), offer.id], |row| {
+                row.get(0)
+            })?
+            .collect::<rusqlite::Result<Vec<f64>>>()?;
+        Ok(prices)
+    }
+
+    /// Records the catalogs and returns the ones not seen before. The very
+    /// first time nothing is returned, as every catalog would be new.
+    pub fn record_catalogs<'a>(&mut self, catalogs: &'a [Catalog]) -> Result<Vec<&'a Catalog>> {
+        let today = Utc::now().date_naive().to_string();
+        let tx = self.conn.transaction()?;
+        let known: i64 = tx.query_row("SELECT COUNT(*) FROM catalogs", [], |row| row.get(0))?;
+        let mut new = Vec::new();
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR IGNORE INTO catalogs
+                (catalog_id, dealer, run_from, run_till, first_seen)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for catalog in catalogs {
+                let inserted = insert.execute(params![
+                    catalog.id,
+                    catalog.dealer,
+                    catalog.run_from,
+                    catalog.run_till,
+                    today,
+                ])?;
+                if inserted > 0 && known > 0 {
+                    new.push(catalog);
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(new)
+    }
+
+    /// Whether alerts about the offer have been acknowledged or snoozed.
+    pub fn is_silenced(&self, offer_id: &str) -> Result<bool> {
+        let now = Utc::now().naive_utc().format(TIMESTAMP).to_string();
+        let silenced: Option<bool> = self
+            .conn
+            .query_row(
+                "SELECT acknowledged OR IFNULL(snoozed_until > ?2, 0)
+                FROM alerts WHERE offer_id = ?1",
+                params![offer_id, now],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(silenced.unwrap_or(false))
+    }
+
+    /// Whether an alert about the offer is due, which is when none has been
+    /// sent before or its snooze has run out, and it is not acknowledged.
+    pub fn should_alert(&self, offer_id: &str) -> Result<bool> {
+        let now = Utc::now().naive_utc().format(TIMESTAMP).to_string();
+        let due: Option<bool> = self
+            .conn
+            .query_row(
+                "SELECT NOT acknowledged AND IFNULL(snoozed_until <= ?2, 0)
+                FROM alerts WHERE offer_id = ?1",
+                params![offer_id, now],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(due.unwrap_or(true))
+    }
+
+    /// Records that alerts about the offers have been sent.
+    pub fn mark_alerted(&mut self, offers: &[&Offer]) -> Result<()> {
+        let now = Utc::now().naive_utc().format(TIMESTAMP).to_string();
+        let tx = self.conn.transaction()?;
+        {
+            let mut upsert = tx.prepare(
+                "INSERT INTO alerts (offer_id, dealer, name, notified_at) VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (offer_id) DO UPDATE SET notified_at = ?4, snoozed_until = NULL",
+            )?;
+            for offer in offers {
+                upsert.execute(params![offer.id, offer.dealer.to_string(), offer.name, now])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Silences alerts about the offer for good. Returns whether the offer
+    /// has been alerted about.
+    pub fn acknowledge_alert(&mut self, offer_id: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE alerts SET acknowledged = 1 WHERE offer_id = ?1",
+            params![offer_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Silences alerts about the offer until the given time. Returns whether
+    /// the offer has been alerted about.
+    pub fn snooze_alert(&mut self, offer_id: &str, until: NaiveDateTime) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE alerts SET snoozed_until = ?2 WHERE offer_id = ?1",
+            params![offer_id, until.format(TIMESTAMP).to_string()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Every alert that has been sent, most recent first.
+    pub fn alerts(&self) -> Result<Vec<Alert>> {
+        let parse = |timestamp: String| NaiveDateTime::parse_from_str(&timestamp, TIMESTAMP).ok();
+        let mut query = self.conn.prepare(
+            "SELECT offer_id, dealer, name, notified_at, acknowledged, snoozed_until
+            FROM alerts ORDER BY notified_at DESC",
+        )?;
+        let rows = query.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?;
+        let mut alerts = Vec::new();
+        for row in rows {
+            let (offer_id, dealer, name, notified_at, acknowledged, snoozed_until) = row?;
+            alerts.push(Alert {
+                offer_id,
+                dealer,
+                name,
+                notified_at: parse(notified_at).unwrap_or_default(),
+                acknowledged,
+                snoozed_until: snoozed_until.and_then(parse),
+            });
+        }
+        Ok(alerts)
+    }
+
+    /// Offers that ran on the date, as they were last fetched. Offers recorded
+    /// before whole offers were stored lack their size and count.
+    pub fn offers_on(&self, date: NaiveDate) -> Result<Vec<Offer>> {
+        let mut query = self.conn.prepare(
+            "SELECT offer_id, dealer, name, price, cost_per_unit, unit, run_from, run_till,
+                offer, MAX(fetched_on)
+            FROM prices WHERE run_from <= ?1 AND run_till >= ?1 AND source = ?2
+            GROUP BY offer_id",
+        )?;
+        let rows = query.query_map(params![date.to_string(), CATALOG_SOURCE], |row| {
+            if let Some(offer) = row
+                .get::<_, Option<String>>(8)?
+                .and_then(|offer| serde_json::from_str(&offer).ok())
+            {
+                return Ok(offer);
+            }
+            let date = |i| {
+                row.get::<_, String>(i)
+                    .map(|date| date.parse().unwrap_or_default())
+            };
+            Ok(Offer {
+                id: row.get(0)?,
+                dealer: Dealer::from_str(&row.get::<_, String>(1)?).unwrap_or_default(),
+                name: row.get(2)?,
+                price: row.get(3)?,
+                cost_per_unit: row.get(4)?,
+                unit: unit::Unit::parse(&row.get::<_, String>(5)?)
+                    .map(|(unit, _)| unit)
+                    .unwrap_or_default(),
+                run_from: date(6)?,
+                run_till: date(7)?,
+                ..Offer::default()
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<Offer>>>()?)
+    }
+
+    /// Every distinct price recorded from catalogs, oldest first.
+    pub fn price_points(&self) -> Result<Vec<PricePoint>> {
+        let mut query = self.conn.prepare(
+            "SELECT DISTINCT dealer, name, price, cost_per_unit, unit, run_from, run_till
+            FROM prices WHERE source = ?1 ORDER BY run_from, dealer, name",
+        )?;
+        let points = query
+            .query_map([CATALOG_SOURCE], |row| {
+                Ok(PricePoint {
+                    dealer: row.get(0)?,
+                    name: row.get(1)?,
+                    price: row.get(2)?,
+                    cost_per_unit: row.get(3)?,
+                    unit: row.get(4)?,
+                    run_from: row.get(5)?,
+                    run_till: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<PricePoint>>>()?;
+        Ok(points)
+    }
+
+    /// Every recorded and imported price of products whose name contains the
+    /// term, compared like searches are, oldest first.
+    pub fn prices_of(&self, term: &str) -> Result<Vec<PricePoint>> {
+        let term = crate::search::fold(term.trim());
+        let mut query = self.conn.prepare(
+            "SELECT DISTINCT dealer, name, price, cost_per_unit, unit, run_from, run_till
+            FROM prices ORDER BY run_from, dealer, name",
+        )?;
+        let points = query
+            .query_map([], |row| {
+                Ok(PricePoint {
+                    dealer: row.get(0)?,
+                    name: row.get(1)?,
+                    price: row.get(2)?,
+                    cost_per_unit: row.get(3)?,
+                    unit: row.get(4)?,
+                    run_from: row.get(5)?,
+                    run_till: row.get(6)?,
+                })
+            })?
+            .filter(|point| {
+                point.as_ref().map_or(true, |point| {
+                    crate::search::fold(&point.name).contains(&term)
+                })
+            })
+            .collect::<rusqlite::Result<Vec<PricePoint>>>()?;
+        Ok(points)
+    }
+
+    /// The day every recorded offer was first fetched, by offer id.
+    pub fn first_seen(&self) -> Result<HashMap<String, NaiveDate>> {
+        let mut query = self.conn.prepare(
+            "SELECT offer_id, MIN(fetched_on) FROM prices WHERE source = ?1 GROUP BY offer_id",
+        )?;
+        let rows = query.query_map([CATALOG_SOURCE], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut first_seen = HashMap::new();
+        for row in rows {
+            let (id, date) = row?;
+            if let Ok(date) = date.parse() {
+                first_seen.insert(id, date);
+            }
+        }
+        Ok(first_seen)
+    }
+}
+
+/// Records freshly fetched offers in the price history.
+pub fn record_offers(offers: &[Offer]) {
+    if let Err(err) = History::open().and_then(|mut history| history.record(offers)) {
+        tracing::error!("Failed to update price history: {err:#}");
+    }
+}
+
+/// Records freshly fetched catalogs and returns the ones not seen before.
+pub fn record_catalogs(catalogs: &[Catalog]) -> Vec<&Catalog> {
+    History::open()
+        .and_then(|mut history| history.record_catalogs(catalogs))
+        .unwrap_or_else(|err| {
+            tracing::error!("Failed to update catalog history: {err:#}");
+            Vec::new()
+        })
+}
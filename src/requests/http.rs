@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, RequestBuilder, Response, StatusCode,
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::{BuildHasher, RandomState},
+    sync::OnceLock,
+    time::Duration,
+};
+use tokio::sync::Semaphore;
+
+use super::is_offline;
+use crate::error::Error;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// User-Agent to send instead of `etilbudsavis-cli/<version>`
+    pub user_agent: Option<String>,
+    /// Extra headers sent with every request, to every host
+    pub headers: HashMap<String, String>,
+    pub retry: RetryConfig,
+    /// Most API requests in flight at once [default: 8]
+    pub concurrency: Option<usize>,
+}
+
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Limits how many API requests are sent at once, across all dealers.
+fn request_slots() -> &'static Semaphore {
+    static SLOTS: OnceLock<Semaphore> = OnceLock::new();
+    SLOTS.get_or_init(|| {
+        let concurrency = crate::config::get_config()
+            .http
+            .concurrency
+            .unwrap_or(DEFAULT_CONCURRENCY);
+        Semaphore::new(concurrency.max(1))
+    })
+}
+
+/// How requests to the API are retried after network errors, rate limiting
+/// and server errors.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Tries in total, 1 to never retry
+    pub attempts: u32,
+    /// Wait before the first retry in milliseconds, doubled for every retry
+    pub backoff_ms: u64,
+    /// Longest wait between two tries in milliseconds
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            attempts: 3,
+            backoff_ms: 500,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The wait after the given failed try, with up to half of it added at
+    /// random so parallel requests do not retry at the same time.
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .backoff_ms
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.max_backoff_ms);
+        let jitter = RandomState::new().hash_one(attempt) % (backoff / 2 + 1);
+        Duration::from_millis(backoff + jitter)
+    }
+}
+
+fn retry_config() -> &'static RetryConfig {
+    &crate::config::get_config().http.retry
+}
+
+/// Sending requests with retries as set up in the config.
+pub trait SendRetrying {
+    /// Sends the request, trying again after network errors, 429 and 5xx
+    /// responses. Fails with the last error once every try has failed. Waits
+    /// for a free request slot before every try.
+    fn send_retrying(self) -> impl Future<Output = Result<Response>> + Send;
+}
+
+impl SendRetrying for RequestBuilder {
+    async fn send_retrying(self) -> Result<Response> {
+        let config = retry_config();
+        let attempts = config.attempts.max(1);
+        let target = self
+            .try_clone()
+            .and_then(|request| request.build().ok())
+            .map(|request| format!("{} {}", request.method(), request.url()))
+            .unwrap_or_default();
+        let mut attempt = 1;
+        loop {
+            tracing::debug!("{target} (try {attempt} of {attempts})");
+            let request = self
+                .try_clone()
+                .context("Request can not be sent more than once")?;
+            let slot = request_slots().acquire().await?;
+            let result = request.send().await;
+            drop(slot);
+            let error = match result {
+                Ok(response) if should_retry(response.status()) => {
+                    Error::Status(response.status()).into()
+                }
+                Ok(response) => {
+                    tracing::debug!("{target}: {}", response.status());
+                    return Ok(response);
+                }
+                Err(err) if err.is_builder() => return Err(Error::Network(err).into()),
+                Err(err) => anyhow::Error::from(Error::Network(err)),
+            };
+            if attempt >= attempts {
+                return Err(if attempts > 1 {
+                    error.context(format!("gave up after {attempts} tries"))
+                } else {
+                    error
+                });
+            }
+            tokio::time::sleep(config.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn should_retry(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The HTTP client every request is made with, set up from the config.
+/// Fails when offline.
+pub fn client() -> Result<Client> {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    if is_offline() {
+        return Err(Error::Offline.into());
+    }
+    Ok(CLIENT
+        .get_or_init(|| build_client(&crate::config::get_config().http))
+        .clone())
+}
+
+fn build_client(config: &HttpConfig) -> Client {
+    let mut headers = HeaderMap::new();
+    for (name, value) in config.headers.iter() {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!("Ignoring invalid header in config: {name}"),
+        }
+    }
+    let user_agent = config
+        .user_agent
+        .clone()
+        .unwrap_or_else(|| format!("etilbudsavis-cli/{}", env!("CARGO_PKG_VERSION")));
+    Client::builder()
+        .user_agent(user_agent)
+        .default_headers(headers)
+        .build()
+        .unwrap_or_else(|err| {
+            tracing::warn!("Could not set up HTTP client from config: {err}");
+            Client::new()
+        })
+}
@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use futures::future;
+use reqwest::Client;
+use std::{collections::HashMap, path::PathBuf};
+
+use super::{dealer, offer::Offer, SendRetrying};
+
+/// Downloads the thumbnails of the offers to the cache, skipping images that
+/// are already there. Downloads share the request slots of the other requests,
+/// and offline only the images already there are used. Returns the local path
+/// of every available image by offer id.
+pub async fn download_images(offers: &[&Offer]) -> HashMap<String, PathBuf> {
+    let dir = match image_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            tracing::error!("{err:#}");
+            return HashMap::new();
+        }
+    };
+    let client = super::client()
+        .inspect_err(|err| tracing::warn!("{err:#}, using the images already downloaded"))
+        .ok();
+    let downloads = offers.iter().filter_map(|offer| {
+        let url = offer.image_url.as_deref()?;
+        let path = dir.join(format!("{}.jpg", offer.id));
+        let missing = !path.exists();
+        if missing && client.is_none() {
+            return None;
+        }
+        let client = &client;
+        Some(async move {
+            if let Some(client) = client.as_ref().filter(|_| missing) {
+                download(client, url, &path).await?;
+            }
+            Ok::<_, anyhow::Error>((offer.id.clone(), path))
+        })
+    });
+
+    let mut images = HashMap::new();
+    for result in future::join_all(downloads).await {
+        match result {
+            Ok((id, path)) => {
+                images.insert(id, path);
+            }
+            Err(err) => tracing::warn!("Could not download image: {err:#}"),
+        }
+    }
+    images
+}
+
+/// Downloads the page images of the catalog, in page order.
+pub async fn download_catalog(catalog_id: &str) -> Result<Vec<Vec<u8>>> {
+    let urls = dealer::catalog_pages(catalog_id).await?;
+    let client = super::client()?;
+    let downloads = urls.iter().enumerate().map(|(i, url)| {
+        let client = &client;
+        async move {
+            fetch(client, url)
+                .await
+                .with_context(|| format!("Could not download page {}", i + 1))
+        }
+    });
+    future::try_join_all(downloads).await
+}
+
+async fn download(client: &Client, url: &str, path: &PathBuf) -> Result<()> {
+    let bytes = fetch(client, url).await?;
+    std::fs::write(path, bytes).with_context(|| format!("Could not write {}", path.display()))
+}
+
+async fn fetch(client: &Client, url: &str) -> Result<Vec<u8>> {
+    let bytes = client
+        .get(url)
+        .send_retrying()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+fn image_dir() -> Result<PathBuf> {
+    let path = dirs::cache_dir()
+        .context("Could not find cache dir")?
+        .join("etilbudsavis-cli/images");
+    std::fs::create_dir_all(&path)?;
+    Ok(path)
+}
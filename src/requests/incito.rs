@@ -0,0 +1,57 @@
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use super::{dealer::get_json, SendRetrying};
+
+const V4_API: &str = "https://squid-api.tjek.com/v4";
+
+/// Meta key of the views that show an offer.
+const OFFER_META: &str = "tjek.offer.v1";
+
+/// Ids of the offers in an incito publication, in the order they appear.
+/// Incito catalogs are documents of nested views rather than pages, so their
+/// offers have no hotspots and can only be found in the views.
+pub(super) async fn offer_ids(client: &Client, publication_id: &str) -> Result<Vec<String>> {
+    let response = client
+        .post(format!("{V4_API}/rpc/generate_incito_from_publication"))
+        .header("Accept", "application/json")
+        .json(&json!({
+            "id": publication_id,
+            "device_category": "desktop",
+            "pointer": "fine",
+            "orientation": "horizontal",
+            "pixel_ratio": 1,
+            "max_width": 1920,
+            "versions_supported": ["1.0.0"],
+            "locale_code": "da_DK",
+            "time": chrono::Utc::now().to_rfc3339(),
+        }))
+        .send_retrying()
+        .await?;
+    let document = get_json(response).await?;
+    let mut ids = Vec::new();
+    collect_offer_ids(document.get("root_view").unwrap_or(&document), &mut ids);
+    Ok(ids)
+}
+
+fn collect_offer_ids(view: &Value, ids: &mut Vec<String>) {
+    let offer = view
+        .get("meta")
+        .and_then(|meta| meta.get(OFFER_META))
+        .and_then(|offer| offer.get("id"))
+        .and_then(Value::as_str);
+    if let Some(id) = offer {
+        if !ids.iter().any(|known| known == id) {
+            ids.push(id.to_string());
+        }
+    }
+    for child in view
+        .get("child_views")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        collect_offer_ids(child, ids);
+    }
+}
@@ -1,4 +1,43 @@
-pub(crate) mod dealer;
-pub(crate) mod deserialize;
-pub(crate) mod offer;
-pub(crate) mod userdata;
+#[cfg(feature = "account")]
+pub mod account;
+#[cfg(feature = "native")]
+pub mod branding;
+#[cfg(feature = "native")]
+pub mod cache;
+pub mod dealer;
+pub mod deserialize;
+#[cfg(feature = "native")]
+pub mod history;
+#[cfg(feature = "native")]
+mod http;
+#[cfg(feature = "native")]
+pub mod images;
+#[cfg(feature = "native")]
+mod incito;
+#[cfg(feature = "native")]
+pub mod nutrition;
+pub mod offer;
+#[cfg(feature = "native")]
+pub mod progress;
+pub mod registry;
+#[cfg(feature = "native")]
+pub mod stores;
+#[cfg(feature = "native")]
+pub mod userdata;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "native")]
+pub use http::{client, HttpConfig, RetryConfig, SendRetrying};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Forbids network access, making `client` fail and offers come from the
+/// cache only.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
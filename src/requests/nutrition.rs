@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use futures::{stream, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::unit::Unit;
+
+use super::offer::Offer;
+
+/// Lookups running against Open Food Facts at the same time.
+const CONCURRENT_LOOKUPS: usize = 4;
+
+/// Nutrition facts per 100 g of a product, from Open Food Facts.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Nutrition {
+    pub product_name: Option<String>,
+    pub protein: Option<f64>,
+    pub kcal: Option<f64>,
+    /// Open Food Facts allergen tags, e.g. `en:gluten`
+    #[serde(default)]
+    pub allergens: Vec<String>,
+    /// Open Food Facts label tags, e.g. `en:vegetarian`
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl Nutrition {
+    /// Price of 100 g of protein, for offers priced by weight or volume.
+    pub fn kr_per_100g_protein(&self, offer: &Offer) -> Option<f64> {
+        let per_kg = cost_per_kg(offer)?;
+        self.protein
+            .filter(|&protein| protein > 0.0)
+            .map(|protein| per_kg * 10.0 / protein)
+    }
+
+    /// Price of 1000 kcal, for offers priced by weight or volume.
+    pub fn kr_per_1000_kcal(&self, offer: &Offer) -> Option<f64> {
+        let per_kg = cost_per_kg(offer)?;
+        self.kcal
+            .filter(|&kcal| kcal > 0.0)
+            .map(|kcal| per_kg * 100.0 / kcal)
+    }
+}
+
+/// Liquids are treated as weighing 1 kg per liter.
+fn cost_per_kg(offer: &Offer) -> Option<f64> {
+    matches!(offer.unit, Unit::Kg | Unit::L).then_some(offer.cost_per_unit)
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    products: Vec<Product>,
+}
+
+#[derive(Deserialize)]
+struct Product {
+    product_name: Option<String>,
+    #[serde(default)]
+    nutriments: Nutriments,
+    #[serde(default)]
+    allergens_tags: Vec<String>,
+    #[serde(default)]
+    labels_tags: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct Nutriments {
+    proteins_100g: Option<f64>,
+    #[serde(rename = "energy-kcal_100g")]
+    kcal_100g: Option<f64>,
+}
+
+/// Looks up nutrition facts for the offers by product name. Results,
+/// including products that were not found, are cached by name.
+pub async fn lookup_nutrition(offers: &[Offer]) -> HashMap<String, Nutrition> {
+    let mut cache = cached_nutrition().unwrap_or_default();
+    let mut missing: Vec<&str> = offers
+        .iter()
+        .map(|offer| offer.name.as_str())
+        .filter(|name| !cache.contains_key(*name))
+        .collect();
+    missing.sort_unstable();
+    missing.dedup();
+
+    let client = if missing.is_empty() {
+        None
+    } else {
+        super::client()
+            .map_err(|err| tracing::warn!("Skipping nutrition lookups: {err:#}"))
+            .ok()
+    };
+    if let Some(client) = client {
+        let results: Vec<_> = stream::iter(missing)
+            .map(|name| {
+                let client = &client;
+                async move { (name, search_product(client, name).await) }
+            })
+            .buffer_unordered(CONCURRENT_LOOKUPS)
+            .collect()
+            .await;
+        for (name, result) in results {
+            match result {
+                Ok(nutrition) => {
+                    cache.insert(name.to_string(), nutrition);
+                }
+                Err(err) => tracing::warn!("Nutrition lookup of {name} failed: {err:#}"),
+            }
+        }
+        if let Err(err) = cache_nutrition(&cache) {
+            tracing::error!("{err:#}");
+        }
+    }
+
+    cache
+        .into_iter()
+        .filter_map(|(name, nutrition)| Some((name, nutrition?)))
+        .collect()
+}
+
+/// Product data that has already been looked up, without fetching anything.
+pub fn cached_products() -> HashMap<String, Nutrition> {
+    cached_nutrition()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(name, nutrition)| Some((name, nutrition?)))
+        .collect()
+}
+
+async fn search_product(client: &Client, name: &str) -> Result<Option<Nutrition>> {
+    let response = client
+        .get("https://world.openfoodfacts.org/cgi/search.pl")
+        .query(&[
+            ("search_terms", name),
+            ("search_simple", "1"),
+            ("json", "1"),
+            ("page_size", "1"),
+            ("countries_tags_en", "denmark"),
+            (
+                "fields",
+                "product_name,nutriments,allergens_tags,labels_tags",
+            ),
+        ])
+        .header(
+            "User-Agent",
+            concat!("etilbudsavis-cli/", env!("CARGO_PKG_VERSION")),
+        )
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SearchResponse>()
+        .await
+        .context("Open Food Facts returned invalid JSON")?;
+    Ok(response
+        .products
+        .into_iter()
+        .next()
+        .map(|product| Nutrition {
+            product_name: product.product_name,
+            protein: product.nutriments.proteins_100g,
+            kcal: product.nutriments.kcal_100g,
+            allergens: product.allergens_tags,
+            labels: product.labels_tags,
+        }))
+}
+
+fn cached_nutrition() -> Result<HashMap<String, Option<Nutrition>>> {
+    let path = dirs::cache_dir()
+        .context("Could not find cache dir")?
+        .join("etilbudsavis-cli/nutrition.json");
+    let data = std::fs::read_to_string(path).context("Nutrition cache not found")?;
+    serde_json::from_str(&data).context("Nutrition cache has invalid JSON")
+}
+
+fn cache_nutrition(nutrition: &HashMap<String, Option<Nutrition>>) -> Result<()> {
+    let path = dirs::cache_dir()
+        .context("Could not find cache dir")?
+        .join("etilbudsavis-cli");
+    std::fs::create_dir_all(&path)?;
+    std::fs::write(
+        path.join("nutrition.json"),
+        serde_json::to_string(nutrition).context("Failed to serialize nutrition to JSON")?,
+    )
+    .context("could not write nutrition cache")
+}
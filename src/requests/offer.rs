@@ -1,25 +1,92 @@
-use anyhow::{Context, Result};
 use chrono::{NaiveDate, Utc};
-use comfy_table::{Cell, CellAlignment};
-use futures::future;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    holidays,
+    locale::{self, Lang},
+    unit::{self, Unit},
+};
+
+#[cfg(feature = "native")]
+use anyhow::Result;
+#[cfg(feature = "native")]
+use comfy_table::{Cell, CellAlignment, Color};
+#[cfg(feature = "native")]
+use futures::future;
+#[cfg(feature = "native")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "native")]
+use tokio::sync::mpsc::UnboundedSender;
+
+#[cfg(feature = "native")]
+use crate::style;
+
+use super::dealer::Dealer;
+#[cfg(feature = "native")]
+use super::{
+    cache::{self, DealerCache},
+    dealer::FetchedOffers,
+    history,
+    progress::FetchStatus,
+    userdata::UserData,
+};
+
+/// An offer from a dealer's catalog, with sizes converted to SI units.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialOrd)]
+pub struct Offer {
+    pub id: String,
+    pub name: String,
+    pub dealer: Dealer,
+    pub price: f64,
+    /// Price before the offer, when the dealer lists it
+    #[serde(default)]
+    pub pre_price: Option<f64>,
+    pub cost_per_unit: f64,
+    /// Bottle deposit (pant) included in the price, when the description
+    /// gives it
+    #[serde(default)]
+    pub deposit: Option<f64>,
+    pub unit: Unit,
+    pub min_size: f64,
+    pub max_size: f64,
+    pub min_amount: u32,
+    pub max_amount: u32,
+    /// Unit the dealer gave the size in, before converting to `unit`
+    #[serde(default)]
+    pub size_unit: Option<String>,
+    /// Factor converting from `size_unit` to `unit`
+    #[serde(default)]
+    pub si_factor: Option<f64>,
+    pub run_from: NaiveDate,
+    pub run_till: NaiveDate,
+    /// Thumbnail of the offer
+    #[serde(default)]
+    pub image_url: Option<String>,
+    /// Every catalog the offer appears in
+    #[serde(default)]
+    pub sources: Vec<Source>,
+    /// Public page of the offer on eTilbudsavis. Empty for offers cached
+    /// before links were kept, use `share::offer_link` to always get one.
+    #[serde(default)]
+    pub link: String,
+}
 
-use super::{dealer::Dealer, userdata::UserData};
+/// Where in the dealer's catalogs an offer appears.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, PartialOrd)]
+pub struct Source {
+    pub catalog_id: String,
+    pub page: Option<u32>,
+}
 
-#[derive(Debug, Deserialize, Serialize, PartialOrd)]
-pub(crate) struct Offer {
-    pub(crate) id: String,
-    pub(crate) name: String,
-    pub(crate) dealer: Dealer,
-    pub(crate) price: f64,
-    pub(crate) cost_per_unit: f64,
-    pub(crate) unit: String,
-    pub(crate) min_size: f64,
-    pub(crate) max_size: f64,
-    pub(crate) min_amount: u32,
-    pub(crate) max_amount: u32,
-    pub(crate) run_from: NaiveDate,
-    pub(crate) run_till: NaiveDate,
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.page {
+            Some(page) => write!(f, "catalog {} page {page}", self.catalog_id),
+            None => write!(f, "catalog {}", self.catalog_id),
+        }
+    }
 }
 
 impl PartialEq for Offer {
@@ -41,14 +108,21 @@ impl Default for Offer {
             name: String::default(),
             dealer: Dealer::default(),
             price: f64::default(),
+            pre_price: None,
             cost_per_unit: f64::default(),
-            unit: String::default(),
+            deposit: None,
+            unit: Unit::default(),
             min_size: f64::default(),
             max_size: f64::default(),
             min_amount: u32::default(),
             max_amount: u32::default(),
+            size_unit: None,
+            si_factor: None,
             run_from: Utc::now().date_naive(),
             run_till: Utc::now().date_naive(),
+            image_url: None,
+            sources: Vec::new(),
+            link: String::default(),
         }
     }
 }
@@ -56,14 +130,13 @@ impl Default for Offer {
 impl std::fmt::Display for Offer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let offer_str = format!(
-            "{} - {}: {} - {}: {} kr. - {:.2} kr/{}",
+            "{} - {}: {} - {}: {} kr. - {}",
             self.run_from.format("%d/%m"),
             self.run_till.format("%d/%m"),
             self.dealer,
             self.name,
             self.price,
-            self.cost_per_unit,
-            self.unit
+            unit::cost(self.cost_per_unit, self.unit)
         );
         write!(f, "{}", offer_str)?;
         Ok(())
@@ -71,15 +144,166 @@ impl std::fmt::Display for Offer {
 }
 
 impl Offer {
-    pub(crate) fn to_table_entry(&self) -> Vec<Cell> {
-        let unit = &self.unit;
-        let period = format!(
-            "{}\n  ↓  \n{}",
-            self.run_from.format("%d/%m"),
-            self.run_till.format("%d/%m")
+    /// Whether the search term is part of the name, ignoring case and
+    /// accents, with æ, ø and å also matching ae, oe and aa.
+    pub fn matches(&self, search: &str) -> bool {
+        crate::search::contains(&self.name, search)
+    }
+
+    /// Whether any of the terms is in the name.
+    pub fn matches_any(&self, terms: &[String]) -> bool {
+        terms.iter().any(|term| self.matches(term))
+    }
+
+    /// Relative discount compared to the price before the offer.
+    pub fn discount(&self) -> Option<f64> {
+        self.pre_price
+            .filter(|&pre_price| pre_price > self.price)
+            .map(|pre_price| (pre_price - self.price) / pre_price)
+    }
+
+    pub fn is_active(&self) -> bool {
+        let today = Utc::now().date_naive();
+        self.run_from <= today && today <= self.run_till
+    }
+
+    /// Takes the deposit out of the cost per unit, so drinks priced with it
+    /// compare fairly against those priced without. The price keeps it, as
+    /// it is what is paid in the store. Returns whether there was a deposit
+    /// to take out.
+    pub fn exclude_deposit(&mut self) -> bool {
+        match self.deposit {
+            Some(deposit) if deposit < self.price => {
+                self.cost_per_unit *= (self.price - deposit) / self.price;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// How the cost per unit was calculated from the price, size and count.
+    pub fn explain(&self) -> String {
+        let mut explanation = format!(
+            "{} / {} {unit} / {} = {}/{unit}",
+            locale::money(self.price),
+            locale::decimal(self.max_size, 3),
+            self.max_amount,
+            locale::money(self.cost_per_unit),
+            unit = self.unit
         );
-        let cost_per_unit = format!("{:.2} kr/{}", self.cost_per_unit, unit);
-        let price = format!("{:.2} kr", self.price);
+        if let (Some(size_unit), Some(factor)) = (&self.size_unit, self.si_factor) {
+            if size_unit != self.unit.symbol() {
+                explanation.push_str(&format!(
+                    "; {} {size_unit} {} {} {} ({} {})",
+                    locale::decimal(self.max_size / factor, 3),
+                    locale::text("is", "er"),
+                    locale::decimal(self.max_size, 3),
+                    self.unit,
+                    locale::text("factor", "faktor"),
+                    locale::decimal(factor, 3)
+                ));
+            }
+        }
+        if self.max_size - self.min_size > 0.001 {
+            explanation.push_str(&format!(
+                "; {} {} {} {} {}, {}",
+                locale::text("size varies from", "størrelsen går fra"),
+                locale::decimal(self.min_size, 3),
+                locale::text("to", "til"),
+                locale::decimal(self.max_size, 3),
+                self.unit,
+                locale::text("the largest is used", "den største bruges")
+            ));
+        }
+        if self.min_amount != self.max_amount {
+            explanation.push_str(&format!(
+                "; {} {} {} {}, {}",
+                locale::text("count varies from", "antallet går fra"),
+                self.min_amount,
+                locale::text("to", "til"),
+                self.max_amount,
+                locale::text("the largest is used", "det største bruges")
+            ));
+        }
+        explanation
+    }
+
+    /// Whether the offer is sold by weight or volume rather than per piece.
+    pub fn is_sized(&self) -> bool {
+        self.unit.is_sized()
+    }
+
+    /// Whether the offer ends within `days` days from today, or has ended.
+    /// Holidays at the end of the period do not count, as stores are closed.
+    pub fn ends_within(&self, days: i64) -> bool {
+        (self.last_open_day() - Utc::now().date_naive()).num_days() <= days
+    }
+
+    /// The last day the offer can be bought.
+    pub fn last_open_day(&self) -> NaiveDate {
+        holidays::last_open_day(self.run_from, self.run_till)
+    }
+
+    /// The period relative to today, like "ends tomorrow" or "starts
+    /// Saturday". Offers end on the last day their stores are open.
+    pub fn relative_period(&self, today: NaiveDate) -> String {
+        if self.run_from > today {
+            let starts = locale::text("starts", "starter");
+            return match (self.run_from - today).num_days() {
+                1 => format!("{starts} {}", locale::text("tomorrow", "i morgen")),
+                2..=6 => format!("{starts} {}", locale::weekday(self.run_from)),
+                _ => format!("{starts} {}", self.run_from.format("%d/%m")),
+            };
+        }
+        let last = self.last_open_day();
+        let ends = locale::text("ends", "slutter");
+        match (last - today).num_days() {
+            ..0 => format!(
+                "{} {}",
+                locale::text("ended", "sluttede"),
+                last.format("%d/%m")
+            ),
+            0 => format!("{ends} {}", locale::text("today", "i dag")),
+            1 => format!("{ends} {}", locale::text("tomorrow", "i morgen")),
+            days @ 2..=6 => match locale::lang() {
+                Lang::En => format!("ends in {days} days"),
+                Lang::Da => format!("slutter om {days} dage"),
+            },
+            _ => format!("{ends} {}", last.format("%d/%m")),
+        }
+    }
+
+    /// Table row for the offer. The period is colored as a warning when the
+    /// offer ends within `warn_days` days. With relative dates offers ending
+    /// today are flagged in red.
+    #[cfg(feature = "native")]
+    pub fn to_table_entry(&self, warn_days: Option<i64>) -> Vec<Cell> {
+        let unit = &self.unit;
+        let separator = if style::is_ascii() {
+            locale::text(" to ", " til ")
+        } else {
+            "\n  ↓  \n"
+        };
+        let relative = style::relative_dates();
+        let mut period = if relative {
+            self.relative_period(Utc::now().date_naive())
+        } else {
+            format!(
+                "{}{separator}{}",
+                self.run_from.format("%d/%m"),
+                self.run_till.format("%d/%m")
+            )
+        };
+        for date in holidays::closing_days(self.run_from, self.run_till) {
+            period.push_str(style::symbol("\n", ", "));
+            period.push_str(&format!(
+                "{} {}",
+                locale::text("closed", "lukket"),
+                date.format("%d/%m")
+            ));
+        }
+        let cost_per_unit = unit::cost(self.cost_per_unit, unit);
+        let price = locale::money(self.price);
         let count = if self.min_amount == self.max_amount {
             format!("{}", self.min_amount)
         } else {
@@ -91,24 +315,39 @@ impl Offer {
         let max_size_equals_min_size = self.max_size - self.min_size < 0.001;
 
         let min_size = if min_size_is_decimal {
-            format!("{:.3}", self.min_size)
+            locale::decimal(self.min_size, 3)
         } else {
             format!("{}", self.min_size)
         };
         let max_size = if max_size_is_decimal {
-            format!("{:.3}", self.max_size)
+            locale::decimal(self.max_size, 3)
         } else {
             format!("{}", self.max_size)
         };
 
-        let weight = if max_size_equals_min_size {
+        let weight = if !self.is_sized() {
+            "-".to_string()
+        } else if max_size_equals_min_size {
             format!("{} {}", min_size, unit)
         } else {
             format!("{}-{} {}", min_size, max_size, unit)
         };
 
+        let period = match warn_days {
+            _ if relative && self.ends_within(0) && style::is_ascii() => {
+                Cell::new(format!("{period}!"))
+            }
+            _ if relative && self.ends_within(0) => Cell::new(period).fg(Color::Red),
+            Some(days) if self.ends_within(days) && style::is_ascii() => Cell::new(format!(
+                "{period}, {}",
+                locale::text("ending soon", "slutter snart")
+            )),
+            Some(days) if self.ends_within(days) => Cell::new(period).fg(Color::Yellow),
+            _ => Cell::new(period),
+        };
+
         vec![
-            Cell::new(period),
+            period,
             Cell::new(self.dealer.to_string()),
             Cell::new(self.name.to_string()),
             Cell::new(count),
@@ -119,69 +358,340 @@ impl Offer {
     }
 }
 
-pub(crate) async fn retrieve_offers(
+/// A column of the offer table, in the order of `Offer::to_table_entry`.
+/// The columns can also be called by the names of the `--format`
+/// placeholders, like `name` and `unit_price`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Column {
+    Period,
+    Dealer,
+    #[value(alias = "name")]
+    #[serde(alias = "name")]
+    Product,
+    #[value(alias = "amount")]
+    #[serde(alias = "amount")]
+    Count,
+    Price,
+    #[value(alias = "unit-price", alias = "unit_price")]
+    #[serde(alias = "unit-price", alias = "unit_price")]
+    CostPerUnit,
+    #[value(alias = "size")]
+    #[serde(alias = "size")]
+    Weight,
+}
+
+impl Column {
+    pub const ALL: [Column; 7] = [
+        Column::Period,
+        Column::Dealer,
+        Column::Product,
+        Column::Count,
+        Column::Price,
+        Column::CostPerUnit,
+        Column::Weight,
+    ];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Period => locale::text("Period", "Periode"),
+            Column::Dealer => locale::text("Dealer", "Butik"),
+            Column::Product => locale::text("Product", "Produkt"),
+            Column::Count => locale::text("Count", "Antal"),
+            Column::Price => locale::text("Price", "Pris"),
+            Column::CostPerUnit => locale::text("Cost/unit", "Pris/enhed"),
+            Column::Weight => locale::text("Weight", "Vægt"),
+        }
+    }
+
+    /// Position of the column in a row from `Offer::to_table_entry`.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// What the copies of an offer in several catalogs have in common, as
+/// dealers sometimes give every copy its own id.
+type ContentKey = (Dealer, String, u64, u64, u32, NaiveDate, NaiveDate);
+
+/// Recognizes offers of a dealer that were already seen in another of its
+/// catalogs, by id or by contents.
+#[derive(Default)]
+pub struct CatalogDuplicates {
+    ids: HashMap<String, usize>,
+    contents: HashMap<ContentKey, usize>,
+}
+
+impl CatalogDuplicates {
+    /// The index the first copy of the offer was seen under, or None after
+    /// remembering the offer under `index`.
+    pub fn check(&mut self, offer: &Offer, index: usize) -> Option<usize> {
+        let key = (
+            offer.dealer,
+            offer.name.clone(),
+            offer.price.to_bits(),
+            offer.max_size.to_bits(),
+            offer.max_amount,
+            offer.run_from,
+            offer.run_till,
+        );
+        if let Some(&first) = self.ids.get(&offer.id).or_else(|| self.contents.get(&key)) {
+            return Some(first);
+        }
+        self.ids.insert(offer.id.clone(), index);
+        self.contents.insert(key, index);
+        None
+    }
+}
+
+/// Merges offers of one dealer that appear in several of its catalogs into
+/// one, keeping every catalog as a source.
+pub fn merge_catalog_duplicates(offers: Vec<Offer>) -> Vec<Offer> {
+    let mut merged: Vec<Offer> = Vec::with_capacity(offers.len());
+    let mut duplicates = CatalogDuplicates::default();
+    for offer in offers {
+        match duplicates.check(&offer, merged.len()) {
+            Some(first) => merged[first].sources.extend(offer.sources),
+            None => merged.push(offer),
+        }
+    }
+    merged
+}
+
+/// Receives the offers of a catalog as soon as they have been parsed.
+#[cfg(feature = "native")]
+pub type OfferSender = UnboundedSender<Vec<Offer>>;
+
+#[cfg(feature = "native")]
+static FORCE_REFRESH: AtomicBool = AtomicBool::new(false);
+
+/// Makes `retrieve_offers` fetch the offers even when the cache is fresh.
+#[cfg(feature = "native")]
+pub fn set_force_refresh(refresh: bool) {
+    FORCE_REFRESH.store(refresh, Ordering::Relaxed);
+}
+
+/// The offers of the favorite dealers. The offers of a dealer are read from
+/// its cache when they are younger than the cache ttl, or were fetched today
+/// without one, and none of its catalogs have ended. The offers of the other
+/// dealers, or of every dealer with `refresh`, are fetched from the API and
+/// cached. Offline the cache is always used.
+#[cfg(feature = "native")]
+pub async fn retrieve_offers(userdata: &mut UserData, refresh: bool) -> Vec<Offer> {
+    retrieve_offers_streaming(userdata, refresh, None).await
+}
+
+/// Like `retrieve_offers`, but also sends the offers to `sender` while they
+/// are fetched. Cached offers are sent all at once.
+#[cfg(feature = "native")]
+pub async fn retrieve_offers_streaming(
     userdata: &mut UserData,
-    favorites_changed: bool,
+    refresh: bool,
+    sender: Option<OfferSender>,
 ) -> Vec<Offer> {
-    match retrieve_cached_offers() {
-        Ok(cached_offers) => {
-            let cache_outdated = userdata.should_update_cache();
-            if favorites_changed || cache_outdated {
-                let offers = retrieve_offers_from_remote(userdata).await;
-                if let Err(err) = cache_retrieved_offers(userdata, &offers) {
-                    eprintln!("{err}");
-                }
-                offers
-            } else {
-                cached_offers
+    let ttl = crate::config::get_config().cache.ttl.map(|ttl| ttl.0);
+    let refresh = refresh || FORCE_REFRESH.load(Ordering::Relaxed);
+    let offline = super::is_offline();
+    // Held until the outdated offers are fetched and cached, so a run at the
+    // same time waits and then reads the fresh cache
+    let _lock = if offline {
+        None
+    } else {
+        cache::lock()
+            .await
+            .inspect_err(|err| tracing::warn!("{err:#}, refreshing without it"))
+            .ok()
+    };
+    let mut offers = Vec::new();
+    let mut outdated = Vec::new();
+    let mut stale = HashMap::new();
+    for &dealer in userdata.favorites.iter() {
+        match cache::read(dealer) {
+            Ok(cached) if offline || (!refresh && cached.is_fresh(ttl)) => {
+                tracing::debug!("{dealer}: using the cached offers ({} old)", cached.age());
+                offers.extend(cached.offers)
+            }
+            Ok(cached) => {
+                tracing::debug!(
+                    "{dealer}: refreshing the cached offers ({} old)",
+                    cached.age()
+                );
+                stale.insert(dealer, cached);
+                outdated.push(dealer);
+            }
+            Err(_) if offline => tracing::warn!("No cached offers of {dealer} to use offline"),
+            Err(err) => {
+                tracing::debug!("{dealer}: no cached offers ({err:#})");
+                outdated.push(dealer)
             }
         }
-        Err(_) => {
-            let offers = retrieve_offers_from_remote(userdata).await;
-            if let Err(err) = cache_retrieved_offers(userdata, &offers) {
-                eprintln!("{err}");
+    }
+    if let Some(sender) = &sender {
+        let _ = sender.send(offers.clone());
+    }
+    if !outdated.is_empty() {
+        offers.extend(refresh_offers(&outdated, stale, sender).await);
+    }
+    offers
+}
+
+/// Fetches the offers of the dealers and caches them, falling back to the
+/// stale cached offers of dealers, or catalogs of dealers, that could not be
+/// fetched.
+#[cfg(feature = "native")]
+async fn refresh_offers(
+    dealers: &[Dealer],
+    mut stale: HashMap<Dealer, DealerCache>,
+    sender: Option<OfferSender>,
+) -> Vec<Offer> {
+    let remote = retrieve_offers_from_remote(dealers, sender.clone()).await;
+    let mut offers = Vec::new();
+    let mut catalogs = Vec::new();
+    for (dealer, fetched) in remote.fetched {
+        if fetched.failed_catalogs.is_empty() {
+            let cache = DealerCache::new(&fetched.catalogs, fetched.offers);
+            if let Err(err) = cache::write(dealer, &cache) {
+                tracing::error!("{err:#}");
             }
-            offers
+            offers.extend(cache.offers);
+        } else {
+            // Not cached, so the next run tries the failed catalogs again
+            let cached = stale_catalog_offers(dealer, &fetched.failed_catalogs, &mut stale);
+            if let Some(sender) = &sender {
+                let _ = sender.send(cached.clone());
+            }
+            offers.extend(merge_catalog_duplicates(
+                fetched.offers.into_iter().chain(cached).collect(),
+            ));
+        }
+        catalogs.extend(fetched.catalogs);
+    }
+    history::record_offers(&offers);
+    if !remote.failures.is_empty() {
+        // The cache of these dealers is left alone, so the next run tries to
+        // refresh them again
+        let stale = stale_offers(&remote.failures, &mut stale);
+        if let Some(sender) = sender {
+            let _ = sender.send(stale.clone());
         }
+        offers.extend(stale);
     }
+    for catalog in history::record_catalogs(&catalogs) {
+        crate::notify::new_catalog(catalog);
+    }
+    offers
+}
+
+/// The offers as they were last fetched, of every dealer with cached offers,
+/// without checking how old they are.
+#[cfg(feature = "native")]
+pub fn retrieve_cached_offers() -> Result<Vec<Offer>> {
+    cache::read_all()
 }
 
-fn cache_retrieved_offers(userdata: &mut UserData, offers: &Vec<Offer>) -> Result<()> {
-    let path = dirs::cache_dir()
-        .context("Could not find cache dir")?
-        .join("etilbudsavis-cli");
-    std::fs::create_dir_all(path.clone())?;
-    std::fs::write(
-        path.join("offer_cache.json"),
-        serde_json::to_string(offers).context("Failed to serialize offers to JSON")?,
-    )
-    .context("could not write offer cache")?;
-    userdata.cache_updated();
-    Ok(())
+/// The offers of the dealer as they were fetched before the last time.
+#[cfg(feature = "native")]
+pub fn retrieve_previous_offers(dealer: Dealer) -> Result<Vec<Offer>> {
+    Ok(cache::read_previous(dealer)?.offers)
 }
 
-fn retrieve_cached_offers() -> Result<Vec<Offer>> {
-    let path = dirs::cache_dir()
-        .context("Could not find cache dir")?
-        .join("etilbudsavis-cli/offer_cache.json");
-    let offer_cache_str = std::fs::read_to_string(path).context("Offer cache not found")?;
-    serde_json::from_str(&offer_cache_str).context("Offer cache has invalid JSON")
+/// Cached offers of the dealers that could not be refreshed, printing a
+/// warning about how old they are.
+#[cfg(feature = "native")]
+fn stale_offers(
+    failures: &[(Dealer, anyhow::Error)],
+    stale: &mut HashMap<Dealer, DealerCache>,
+) -> Vec<Offer> {
+    let mut offers = Vec::new();
+    let mut warned = false;
+    for (dealer, err) in failures {
+        let Some(cached) = stale.remove(dealer) else {
+            tracing::warn!("Could not fetch the offers of {dealer}: {err:#}");
+            continue;
+        };
+        if !warned {
+            tracing::warn!(
+                "{}Refreshing failed, showing cached offers for:",
+                style::symbol("⚠ ", "Warning: ")
+            );
+            warned = true;
+        }
+        tracing::warn!("  {dealer} ({} old): {err:#}", cached.age());
+        offers.extend(cached.offers);
+    }
+    offers
 }
 
-async fn retrieve_offers_from_remote(userdata: &mut UserData) -> Vec<Offer> {
-    let tasks: Vec<_> = userdata
-        .favorites
+/// The cached offers of a dealer from the catalogs that could not be
+/// fetched, printing a warning about how old they are.
+#[cfg(feature = "native")]
+fn stale_catalog_offers(
+    dealer: Dealer,
+    failed_catalogs: &[String],
+    stale: &mut HashMap<Dealer, DealerCache>,
+) -> Vec<Offer> {
+    let Some(cached) = stale.remove(&dealer) else {
+        return Vec::new();
+    };
+    let age = cached.age();
+    let offers: Vec<Offer> = cached
+        .offers
+        .into_iter()
+        .filter(|offer| {
+            offer
+                .sources
+                .iter()
+                .any(|source| failed_catalogs.contains(&source.catalog_id))
+        })
+        .collect();
+    if !offers.is_empty() {
+        tracing::warn!(
+            "{}Showing {} cached offers ({age} old) of {dealer} from catalogs that \
+            could not be fetched",
+            style::symbol("⚠ ", "Warning: "),
+            offers.len()
+        );
+    }
+    offers
+}
+
+/// Offers fetched from the API by dealer, and the dealers they could not be
+/// fetched for.
+#[cfg(feature = "native")]
+struct RemoteOffers {
+    fetched: Vec<(Dealer, FetchedOffers)>,
+    failures: Vec<(Dealer, anyhow::Error)>,
+}
+
+#[cfg(feature = "native")]
+async fn retrieve_offers_from_remote(
+    dealers: &[Dealer],
+    sender: Option<OfferSender>,
+) -> RemoteOffers {
+    let status = FetchStatus::new(dealers.iter().copied());
+    let tasks: Vec<_> = dealers
         .iter()
-        .map(|dealer| {
-            let dealer = *dealer;
-            tokio::spawn(async move { dealer.remote_offers_for_dealer().await })
+        .map(|&dealer| {
+            let sender = sender.clone();
+            let status = status.clone();
+            let task =
+                tokio::spawn(async move { dealer.remote_offers_for_dealer(sender, status).await });
+            async move { (dealer, task.await) }
         })
         .collect();
 
-    future::join_all(tasks)
-        .await
-        .into_iter()
-        .flatten()
-        .flatten()
-        .collect()
+    let mut remote = RemoteOffers {
+        fetched: Vec::new(),
+        failures: Vec::new(),
+    };
+    for (dealer, result) in future::join_all(tasks).await {
+        match result
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result)
+        {
+            Ok(fetched) => remote.fetched.push((dealer, fetched)),
+            Err(err) => remote.failures.push((dealer, err)),
+        }
+    }
+    remote
 }
@@ -0,0 +1,199 @@
+use std::{
+    io::{IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use super::dealer::Dealer;
+use crate::style;
+
+/// Set by `--verbose` to print progress as lines instead of a table.
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// Live table on stderr of how fetching the offers of every dealer is going.
+/// Nothing is drawn when stderr is not a terminal or in ASCII mode. With
+/// `--verbose` every change is printed as a line instead, also in pipes.
+#[derive(Clone)]
+pub struct FetchStatus {
+    board: Option<Arc<Mutex<Board>>>,
+}
+
+struct Board {
+    rows: Vec<Row>,
+    /// Lines drawn last time, to move back up over
+    drawn: usize,
+    /// Print a line per change instead of drawing the table
+    lines: bool,
+}
+
+struct Row {
+    dealer: Dealer,
+    catalogs: Option<usize>,
+    catalogs_done: usize,
+    offers: usize,
+    state: State,
+}
+
+enum State {
+    Listing,
+    Fetching,
+    Done,
+    Failed,
+}
+
+impl FetchStatus {
+    /// A status that is never drawn.
+    pub fn hidden() -> Self {
+        FetchStatus { board: None }
+    }
+
+    pub fn new(dealers: impl IntoIterator<Item = Dealer>) -> Self {
+        let lines = VERBOSE.load(Ordering::Relaxed);
+        if crate::logging::is_quiet() {
+            return FetchStatus::hidden();
+        }
+        if !lines && (!std::io::stderr().is_terminal() || style::is_ascii()) {
+            return FetchStatus::hidden();
+        }
+        let mut rows: Vec<Row> = dealers
+            .into_iter()
+            .map(|dealer| Row {
+                dealer,
+                catalogs: None,
+                catalogs_done: 0,
+                offers: 0,
+                state: State::Listing,
+            })
+            .collect();
+        rows.sort_by_key(|row| row.dealer);
+        let status = FetchStatus {
+            board: Some(Arc::new(Mutex::new(Board {
+                rows,
+                drawn: 0,
+                lines,
+            }))),
+        };
+        if !lines {
+            status.update(None, |_| {});
+        }
+        status
+    }
+
+    pub fn catalogs_found(&self, dealer: Dealer, catalogs: usize) {
+        self.update(Some(dealer), |row| {
+            row.catalogs = Some(catalogs);
+            row.state = State::Fetching;
+        });
+    }
+
+    pub fn catalog_done(&self, dealer: Dealer, offers: usize) {
+        self.update(Some(dealer), |row| {
+            row.catalogs_done += 1;
+            row.offers += offers;
+        });
+    }
+
+    pub fn finished(&self, dealer: Dealer, offers: Option<usize>) {
+        self.update(Some(dealer), |row| match offers {
+            Some(offers) => {
+                row.offers = offers;
+                row.state = State::Done;
+            }
+            None => row.state = State::Failed,
+        });
+    }
+
+    /// Logs a warning above the table, or just logs it without a table.
+    pub fn note(&self, message: &str) {
+        let warn = || tracing::warn!("{}{message}", style::symbol("⚠ ", "Warning: "));
+        let Some(board) = &self.board else {
+            warn();
+            return;
+        };
+        let mut board = board.lock().unwrap_or_else(|err| err.into_inner());
+        if board.lines {
+            warn();
+            return;
+        }
+        board.clear();
+        warn();
+        board.draw();
+    }
+
+    fn update(&self, dealer: Option<Dealer>, change: impl FnOnce(&mut Row)) {
+        let Some(board) = &self.board else {
+            return;
+        };
+        let mut board = board.lock().unwrap_or_else(|err| err.into_inner());
+        let board = &mut *board;
+        if let Some(row) = board.rows.iter_mut().find(|row| Some(row.dealer) == dealer) {
+            change(row);
+            if board.lines {
+                eprintln!(
+                    "{}: {} catalogs, {} offers, {}",
+                    row.dealer,
+                    row.catalogs(),
+                    row.offers,
+                    row.state.label()
+                );
+            }
+        }
+        if !board.lines {
+            board.clear();
+            board.draw();
+        }
+    }
+}
+
+impl Row {
+    fn catalogs(&self) -> String {
+        match self.catalogs {
+            Some(catalogs) => format!("{}/{catalogs}", self.catalogs_done),
+            None => "?".to_string(),
+        }
+    }
+}
+
+impl State {
+    fn label(&self) -> &'static str {
+        match self {
+            State::Listing => "listing catalogs",
+            State::Fetching => "fetching",
+            State::Done => "done",
+            State::Failed => "failed",
+        }
+    }
+}
+
+impl Board {
+    /// Moves back up over the previously drawn table and clears it.
+    fn clear(&mut self) {
+        if self.drawn > 0 {
+            eprint!("\x1b[{}A\x1b[J", self.drawn);
+        }
+        self.drawn = 0;
+    }
+
+    fn draw(&mut self) {
+        let mut table = style::table();
+        table.set_header(vec!["Dealer", "Catalogs", "Offers", "State"]);
+        for row in self.rows.iter() {
+            table.add_row(vec![
+                row.dealer.to_string(),
+                row.catalogs(),
+                row.offers.to_string(),
+                row.state.label().to_string(),
+            ]);
+        }
+        let table = table.to_string();
+        self.drawn = table.lines().count();
+        eprintln!("{table}");
+        let _ = std::io::stderr().flush();
+    }
+}
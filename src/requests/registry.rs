@@ -0,0 +1,239 @@
+use std::sync::{OnceLock, RwLock};
+
+#[cfg(feature = "native")]
+use anyhow::{Context, Result};
+#[cfg(feature = "native")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use std::collections::HashSet;
+
+#[cfg(feature = "native")]
+use super::{
+    cache,
+    dealer::{get_json, parse_each, API, MAX_PAGES, PAGE_SIZE},
+    SendRetrying,
+};
+
+/// A dealer that can be referred to by name, slug or Tjek id.
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub name: &'static str,
+    pub id: &'static str,
+}
+
+/// Dealers known without fetching the dealer list. Their names are what the
+/// offer cache and favorites store, so they must not change.
+const BUILT_IN: &[Entry] = &[
+    Entry {
+        name: "Rema1000",
+        id: "11deC",
+    },
+    Entry {
+        name: "Netto",
+        id: "9ba51",
+    },
+    Entry {
+        name: "DagliBrugsen",
+        id: "d311fg",
+    },
+    Entry {
+        name: "SuperBrugsen",
+        id: "0b1e8",
+    },
+    Entry {
+        name: "Aldi",
+        id: "98b7e",
+    },
+    Entry {
+        name: "Bilka",
+        id: "93f13",
+    },
+    Entry {
+        name: "Coop365",
+        id: "DWZE1w",
+    },
+    Entry {
+        name: "Irma",
+        id: "d432U",
+    },
+    Entry {
+        name: "Føtex",
+        id: "bdf5A",
+    },
+    Entry {
+        name: "Lidl",
+        id: "71c90",
+    },
+    Entry {
+        name: "Meny",
+        id: "267e1m",
+    },
+    Entry {
+        name: "Kvickly",
+        id: "c1edq",
+    },
+    Entry {
+        name: "Spar",
+        id: "88ddE",
+    },
+];
+
+/// A dealer as listed by the API.
+#[cfg(feature = "native")]
+#[derive(Serialize, Deserialize)]
+struct RemoteDealer {
+    id: String,
+    name: String,
+}
+
+/// Every known dealer. Entries are only ever added, so an index into it
+/// stays valid.
+fn registry() -> &'static RwLock<Vec<Entry>> {
+    static REGISTRY: OnceLock<RwLock<Vec<Entry>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        #[cfg_attr(not(feature = "native"), allow(unused_mut))]
+        let mut entries = BUILT_IN.to_vec();
+        #[cfg(feature = "native")]
+        if let Ok(dealers) = cached_dealers() {
+            add(&mut entries, dealers);
+        }
+        RwLock::new(entries)
+    })
+}
+
+/// Adds the dealers that are not known yet. Known dealers listed with
+/// another id get the id from the API, as built-in ids can go out of date.
+/// Returns how many were added.
+#[cfg(feature = "native")]
+fn add(entries: &mut Vec<Entry>, dealers: Vec<RemoteDealer>) -> usize {
+    let before = entries.len();
+    for dealer in dealers {
+        if entries.iter().any(|entry| entry.id == dealer.id) {
+            continue;
+        }
+        // Lives as long as the program, like the built-in dealers
+        let id = Box::leak(dealer.id.into_boxed_str());
+        match entries
+            .iter_mut()
+            .find(|entry| key(entry.name) == key(&dealer.name))
+        {
+            Some(entry) => entry.id = id,
+            None => entries.push(Entry {
+                name: Box::leak(dealer.name.into_boxed_str()),
+                id,
+            }),
+        }
+    }
+    entries.len() - before
+}
+
+/// What names and slugs are compared by, so "Rema 1000", "rema-1000" and
+/// "Rema1000" are the same dealer, and so are "Føtex" and "fotex".
+fn key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'ø' => 'o',
+            'æ' => 'a',
+            'å' => 'a',
+            c => c,
+        })
+        .collect()
+}
+
+/// The name of the dealer in lowercase with words separated by dashes.
+pub fn slug(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+pub fn entry(index: usize) -> Entry {
+    registry().read().unwrap_or_else(|err| err.into_inner())[index]
+}
+
+pub fn len() -> usize {
+    registry()
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .len()
+}
+
+/// The index of the dealer with the name, slug or id.
+pub fn find(name: &str) -> Option<usize> {
+    let name = name.trim();
+    let wanted = key(name);
+    registry()
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .position(|entry| entry.id == name || key(entry.name) == wanted)
+}
+
+/// Fetches the full dealer list from the API and caches it, so the dealers can
+/// be used from now on. Returns how many dealers were not known before.
+#[cfg(feature = "native")]
+pub async fn update() -> Result<usize> {
+    let client = super::client()?;
+    let mut dealers: Vec<RemoteDealer> = Vec::new();
+    let mut ids = HashSet::new();
+    // Counts the rows of the API, also the ones that could not be parsed
+    let mut offset = 0;
+    let mut pages = 0;
+    loop {
+        let response = client
+            .get(format!("{API}/dealers"))
+            .query(&[
+                ("limit", PAGE_SIZE.to_string()),
+                ("offset", offset.to_string()),
+            ])
+            .header("Accept", "application/json")
+            .send_retrying()
+            .await?;
+        let (page, skipped): (Vec<RemoteDealer>, usize) = parse_each(get_json(response).await?)?;
+        let count = page.len() + skipped;
+        offset += count;
+        let before = dealers.len();
+        let any_parsed = !page.is_empty();
+        dealers.extend(
+            page.into_iter()
+                .filter(|dealer| ids.insert(dealer.id.clone())),
+        );
+        pages += 1;
+        if count < PAGE_SIZE {
+            break;
+        }
+        // An API ignoring the offset returns the same page again and again
+        let repeated = any_parsed && dealers.len() == before;
+        if repeated || pages >= MAX_PAGES {
+            tracing::warn!("Stopped paging through the dealers after {pages} pages");
+            break;
+        }
+    }
+    cache_dealers(&dealers)?;
+    let mut entries = registry().write().unwrap_or_else(|err| err.into_inner());
+    Ok(add(&mut entries, dealers))
+}
+
+#[cfg(feature = "native")]
+fn cached_dealers() -> Result<Vec<RemoteDealer>> {
+    let path = cache::root_dir()?.join("dealers.json");
+    let data = std::fs::read_to_string(path).context("Dealer cache not found")?;
+    serde_json::from_str(&data).context("Dealer cache has invalid JSON")
+}
+
+#[cfg(feature = "native")]
+fn cache_dealers(dealers: &[RemoteDealer]) -> Result<()> {
+    let path = cache::root_dir()?;
+    std::fs::create_dir_all(&path)?;
+    cache::write_atomic(
+        &path.join("dealers.json"),
+        serde_json::to_string(dealers)
+            .context("Failed to serialize dealers to JSON")?
+            .as_bytes(),
+    )
+    .context("Could not write dealer cache")
+}
@@ -0,0 +1,184 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, str::FromStr};
+
+use super::{
+    dealer::{get_json, parse_each, Dealer, API, PAGE_SIZE},
+    SendRetrying,
+};
+
+/// Looks up the center of a Danish zip code.
+const ZIP_API: &str = "https://api.dataforsyningen.dk/postnumre";
+
+/// Most stores fetched per dealer, as some chains have hundreds.
+const MAX_STORES: usize = 500;
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct StoresConfig {
+    /// Where to look for stores when `--near` is not given, coordinates like
+    /// `55.68,12.57` or a zip code like `2100`
+    pub near: Option<String>,
+    /// How far away a store can be, in km
+    pub radius: f64,
+}
+
+impl Default for StoresConfig {
+    fn default() -> Self {
+        StoresConfig {
+            near: None,
+            radius: 5.0,
+        }
+    }
+}
+
+/// Where to look for stores, either coordinates or a Danish zip code.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Location {
+    Coordinates { lat: f64, lng: f64 },
+    Zip(String),
+}
+
+impl FromStr for Location {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some((lat, lng)) = s.split_once(',') {
+            let parse = |value: &str| {
+                value
+                    .trim()
+                    .parse::<f64>()
+                    .with_context(|| format!("Invalid coordinates: {s}"))
+            };
+            return Ok(Location::Coordinates {
+                lat: parse(lat)?,
+                lng: parse(lng)?,
+            });
+        }
+        if s.len() == 4 && s.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(Location::Zip(s.to_string()));
+        }
+        Err(anyhow!(
+            "Invalid location: {s}, use coordinates like 55.68,12.57 or a zip code like 2100"
+        ))
+    }
+}
+
+impl Location {
+    /// The coordinates of the location, looking up the center of zip codes.
+    pub async fn coordinates(&self) -> Result<(f64, f64)> {
+        match self {
+            Location::Coordinates { lat, lng } => Ok((*lat, *lng)),
+            Location::Zip(zip) => {
+                let response = super::client()?
+                    .get(format!("{ZIP_API}/{zip}"))
+                    .header("Accept", "application/json")
+                    .send()
+                    .await?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    bail!("Unknown zip code: {zip}");
+                }
+                let zip_code: ZipCode = serde_json::from_value(get_json(response).await?)
+                    .context("Could not read zip code")?;
+                let [lng, lat] = zip_code.center;
+                Ok((lat, lng))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ZipCode {
+    #[serde(rename = "visueltcenter")]
+    center: [f64; 2],
+}
+
+#[derive(Deserialize)]
+struct RemoteStore {
+    id: String,
+    dealer_id: String,
+    street: Option<String>,
+    city: Option<String>,
+    zip_code: Option<String>,
+    latitude: f64,
+    longitude: f64,
+}
+
+/// A branch of a dealer.
+#[derive(Serialize, Clone, Debug)]
+pub struct Store {
+    pub id: String,
+    pub dealer: Dealer,
+    pub street: String,
+    pub city: String,
+    pub zip_code: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Distance from where was searched, in km
+    pub distance: f64,
+}
+
+/// The stores of the dealers within `radius` km of the location, closest first.
+pub async fn nearby_stores(
+    dealers: &[Dealer],
+    location: &Location,
+    radius: f64,
+) -> Result<Vec<Store>> {
+    let (lat, lng) = location.coordinates().await?;
+    let client = super::client()?;
+    let dealer_ids: Vec<&str> = dealers.iter().map(Dealer::id).collect();
+    let mut stores: Vec<Store> = Vec::new();
+    loop {
+        let response = client
+            .get(format!("{API}/stores"))
+            .query(&[
+                ("dealer_ids", dealer_ids.join(",")),
+                ("r_lat", lat.to_string()),
+                ("r_lng", lng.to_string()),
+                ("r_radius", ((radius * 1000.0).round() as u64).to_string()),
+                ("order_by", "distance".to_string()),
+                ("limit", PAGE_SIZE.to_string()),
+                ("offset", stores.len().to_string()),
+            ])
+            .header("Accept", "application/json")
+            .send_retrying()
+            .await?;
+        let (page, skipped): (Vec<RemoteStore>, usize) = parse_each(get_json(response).await?)?;
+        let count = page.len() + skipped;
+        stores.extend(page.into_iter().filter_map(|store| {
+            Some(Store {
+                dealer: Dealer::from_id(&store.dealer_id)?,
+                distance: distance((lat, lng), (store.latitude, store.longitude)),
+                id: store.id,
+                street: store.street.unwrap_or_default(),
+                city: store.city.unwrap_or_default(),
+                zip_code: store.zip_code.unwrap_or_default(),
+                latitude: store.latitude,
+                longitude: store.longitude,
+            })
+        }));
+        if count < PAGE_SIZE || stores.len() >= MAX_STORES * dealers.len().max(1) {
+            break;
+        }
+    }
+    // The API radius is not exact, so check it again
+    stores.retain(|store| store.distance <= radius);
+    stores.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    Ok(stores)
+}
+
+/// The dealers that have a store among the stores.
+pub fn dealers_with_stores(stores: &[Store]) -> HashSet<Dealer> {
+    stores.iter().map(|store| store.dealer).collect()
+}
+
+/// Great-circle distance between two coordinates in km.
+fn distance((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    const EARTH_RADIUS: f64 = 6371.0;
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlat = lat2 - lat1;
+    let dlng = (lng2 - lng1).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
@@ -1,49 +1,81 @@
-use anyhow::Context;
-use chrono::{NaiveDate, TimeZone, Utc};
 use std::collections::HashSet;
 
 use serde::{Deserialize, Serialize};
 
-use super::dealer::Dealer;
+use super::{cache, dealer::Dealer};
+use crate::watch::Watch;
 
-pub(crate) fn get_userdata() -> UserData {
-    let path = dirs::cache_dir()
-        .unwrap()
-        .join("etilbudsavis-cli/userdata.json");
-    match std::fs::read_to_string(path) {
-        Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
-        Err(_) => UserData::default(),
+/// Version of `userdata.json`. Raise it whenever `UserData` changes shape,
+/// and bring older files up to date in `get_userdata`.
+pub const USERDATA_VERSION: u32 = 3;
+
+/// The saved favorites and watchlist, or defaults when nothing is saved.
+/// A file that can not be read is kept as `userdata.json.bak` rather than
+/// being overwritten with the defaults later.
+pub fn get_userdata() -> anyhow::Result<UserData> {
+    let path = cache::root_dir()?.join("userdata.json");
+    let Ok(data) = std::fs::read_to_string(&path) else {
+        tracing::debug!("No userdata in {}, starting with defaults", path.display());
+        return Ok(UserData::default());
+    };
+    match serde_json::from_str::<UserData>(&data) {
+        Ok(mut userdata) => {
+            if userdata.version > USERDATA_VERSION {
+                tracing::warn!(
+                    "{} was written by a newer version of etilbudsavis-cli, \
+                    settings it does not know are lost when it is saved",
+                    path.display()
+                );
+            }
+            userdata.version = USERDATA_VERSION;
+            Ok(userdata)
+        }
+        Err(err) => {
+            let backup = path.with_extension("json.bak");
+            let kept = std::fs::copy(&path, &backup).is_ok();
+            tracing::error!(
+                "{} can not be read ({err}), starting over without favorites{}",
+                path.display(),
+                if kept {
+                    format!(", the old file is kept as {}", backup.display())
+                } else {
+                    String::new()
+                }
+            );
+            Ok(UserData::default())
+        }
     }
 }
 
+/// The favorite dealers and watched products.
 #[derive(Serialize, Deserialize)]
-pub(crate) struct UserData {
-    pub(crate) favorites: HashSet<Dealer>,
-    date_of_last_cache: NaiveDate,
+pub struct UserData {
+    /// Files from before versioning are version 0
+    #[serde(default)]
+    pub version: u32,
+    pub favorites: HashSet<Dealer>,
+    /// Products to check for with `watch check`
+    #[serde(default)]
+    pub watchlist: Vec<Watch>,
+    /// Items `basket` plans for when none are given
+    #[serde(default)]
+    pub shopping_list: Vec<String>,
+    /// The favorites as of the last `favorites sync`
+    #[serde(default)]
+    pub synced_favorites: HashSet<Dealer>,
 }
 
 impl UserData {
-    pub(crate) fn save(&self) -> anyhow::Result<()> {
-        let path = dirs::cache_dir()
-            .context("Could not find cache dir")?
-            .join("etilbudsavis-cli");
-        std::fs::create_dir_all(path.clone())?;
-        std::fs::write(path.join("userdata.json"), serde_json::to_string(&self)?)?;
-        Ok(())
-    }
-
-    pub(crate) fn should_update_cache(&self) -> bool {
-        self.date_of_last_cache < Utc::now().date_naive()
-    }
-
-    pub(crate) fn cache_updated(&mut self) {
-        self.date_of_last_cache = Utc::now().date_naive();
-        if let Err(err) = self.save() {
-            eprintln!("Failed to save userdata: {}", err);
-        };
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = cache::root_dir()?;
+        std::fs::create_dir_all(&path)?;
+        cache::write_atomic(
+            &path.join("userdata.json"),
+            serde_json::to_string(&self)?.as_bytes(),
+        )
     }
 
-    pub(crate) fn add_favorites(&mut self, dealers: &[Dealer]) -> bool {
+    pub fn add_favorites(&mut self, dealers: &[Dealer]) -> bool {
         let mut changed = false;
         for &dealer in dealers {
             changed |= self.favorites.insert(dealer)
@@ -51,7 +83,7 @@ impl UserData {
         changed
     }
 
-    pub(crate) fn remove_favorites(&mut self, dealers: &[Dealer]) -> bool {
+    pub fn remove_favorites(&mut self, dealers: &[Dealer]) -> bool {
         let mut changed = false;
         for dealer in dealers {
             changed |= self.favorites.remove(dealer)
@@ -62,10 +94,12 @@ impl UserData {
 
 impl Default for UserData {
     fn default() -> Self {
-        println!("Initializing userdata...");
         UserData {
+            version: USERDATA_VERSION,
             favorites: HashSet::new(),
-            date_of_last_cache: Utc.timestamp_millis_opt(0).unwrap().date_naive(),
+            watchlist: Vec::new(),
+            shopping_list: Vec::new(),
+            synced_favorites: HashSet::new(),
         }
     }
 }
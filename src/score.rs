@@ -0,0 +1,197 @@
+#[cfg(feature = "native")]
+use std::collections::HashMap;
+
+#[cfg(feature = "native")]
+use crate::requests::history::History;
+use crate::requests::offer::Offer;
+
+/// Scores an offer from 0 to 100 by how good a deal it is for its product. With
+/// enough history the score is the share of earlier offers of the product with
+/// a higher cost per unit, i.e. a score of 90 means the offer is cheaper than
+/// 90% of them. Otherwise it is the discount compared to the price before the
+/// offer in percent.
+///
+/// Offers with neither are left without a score.
+pub fn deal_score(offer: &Offer, past_unit_prices: &[f64]) -> Option<f64> {
+    if !has_unit_price(offer) {
+        return None;
+    }
+    if past_unit_prices.len() >= MIN_HISTORY {
+        let more_expensive = past_unit_prices
+            .iter()
+            .filter(|&&price| price > offer.cost_per_unit)
+            .count();
+        return Some(100.0 * more_expensive as f64 / past_unit_prices.len() as f64);
+    }
+    offer.discount().map(|discount| 100.0 * discount)
+}
+
+/// The offers with their deal score, scored against their own history.
+#[cfg(feature = "native")]
+pub fn deal_scores<'a>(offers: &'a [Offer], history: Option<&History>) -> Vec<(&'a Offer, f64)> {
+    offers
+        .iter()
+        .filter_map(|offer| {
+            let past = history
+                .and_then(|history| history.past_unit_prices(offer).ok())
+                .unwrap_or_default();
+            Some((offer, deal_score(offer, &past)?))
+        })
+        .collect()
+}
+
+/// The `count` best scoring offers, best first. Without a price history the
+/// offers are only scored by their discount.
+#[cfg(feature = "native")]
+pub fn top_offers(offers: &[Offer], count: usize) -> Vec<(&Offer, f64)> {
+    let history = History::open()
+        .inspect_err(|err| tracing::warn!("{err:#}, scoring by discount only"))
+        .ok();
+    let mut scored = deal_scores(offers, history.as_ref());
+    scored.sort_by(|(a, a_score), (b, b_score)| {
+        b_score
+            .total_cmp(a_score)
+            .then(
+                b.discount()
+                    .unwrap_or(0.0)
+                    .total_cmp(&a.discount().unwrap_or(0.0)),
+            )
+            .then(a.cost_per_unit.total_cmp(&b.cost_per_unit))
+    });
+    scored.truncate(count);
+    scored
+}
+
+fn has_unit_price(offer: &Offer) -> bool {
+    offer.cost_per_unit.is_finite() && offer.cost_per_unit > 0.0
+}
+
+/// Offers costing less than this fraction of their usual unit price are
+/// flagged as anomalies.
+const ANOMALY_RATIO: f64 = 0.6;
+/// Past offers needed before a product has a usual price.
+const MIN_HISTORY: usize = 3;
+
+/// An offer that is dramatically cheaper than the same product used to be,
+/// either a pricing error or an exceptional deal.
+pub struct Anomaly<'a> {
+    pub offer: &'a Offer,
+    /// Median unit price of earlier offers for the product.
+    pub usual_unit_price: f64,
+}
+
+/// The offer as an anomaly when it costs far less per unit than the median
+/// of the past unit prices of its product.
+pub fn anomaly<'a>(offer: &'a Offer, past_unit_prices: &[f64]) -> Option<Anomaly<'a>> {
+    if !has_unit_price(offer) || past_unit_prices.len() < MIN_HISTORY {
+        return None;
+    }
+    let mut past = past_unit_prices.to_vec();
+    past.sort_by(f64::total_cmp);
+    let median = past[past.len() / 2];
+    (offer.cost_per_unit < median * ANOMALY_RATIO).then_some(Anomaly {
+        offer,
+        usual_unit_price: median,
+    })
+}
+
+#[cfg(feature = "native")]
+pub fn price_anomalies<'a>(offers: &'a [Offer], history: &History) -> Vec<Anomaly<'a>> {
+    let mut anomalies: Vec<Anomaly> = offers
+        .iter()
+        .filter(|offer| has_unit_price(offer))
+        .filter_map(|offer| anomaly(offer, &history.past_unit_prices(offer).ok()?))
+        .collect();
+    anomalies.sort_by(|a, b| a.ratio().total_cmp(&b.ratio()));
+    anomalies
+}
+
+impl Anomaly<'_> {
+    /// Current unit price relative to the usual one.
+    pub fn ratio(&self) -> f64 {
+        self.offer.cost_per_unit / self.usual_unit_price
+    }
+}
+
+/// The average unit price of earlier offers for the products of the offers,
+/// by offer id. Products without enough history are left out.
+#[cfg(feature = "native")]
+pub fn usual_unit_prices(offers: &[Offer]) -> HashMap<String, f64> {
+    let Ok(history) = History::open() else {
+        return HashMap::new();
+    };
+    offers
+        .iter()
+        .filter(|offer| has_unit_price(offer))
+        .filter_map(|offer| {
+            let past = history.past_unit_prices(offer).ok()?;
+            (past.len() >= MIN_HISTORY).then(|| {
+                (
+                    offer.id.clone(),
+                    past.iter().sum::<f64>() / past.len() as f64,
+                )
+            })
+        })
+        .collect()
+}
+
+/// Looks up price anomalies in the local history, ignoring a missing history.
+#[cfg(feature = "native")]
+pub fn find_anomalies(offers: &[Offer]) -> Vec<Anomaly<'_>> {
+    History::open()
+        .map(|history| price_anomalies(offers, &history))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer(cost_per_unit: f64, price: f64, pre_price: Option<f64>) -> Offer {
+        Offer {
+            cost_per_unit,
+            price,
+            pre_price,
+            ..Offer::default()
+        }
+    }
+
+    #[test]
+    fn scores_against_the_history_of_the_product() {
+        let past = [10.0, 20.0, 30.0, 40.0];
+        assert_eq!(deal_score(&offer(25.0, 25.0, None), &past), Some(50.0));
+        assert_eq!(deal_score(&offer(5.0, 5.0, None), &past), Some(100.0));
+        assert_eq!(deal_score(&offer(40.0, 40.0, None), &past), Some(0.0));
+    }
+
+    #[test]
+    fn history_beats_the_price_before() {
+        let past = [10.0, 10.0, 10.0];
+        assert_eq!(deal_score(&offer(20.0, 20.0, Some(40.0)), &past), Some(0.0));
+    }
+
+    #[test]
+    fn scores_by_discount_without_enough_history() {
+        assert_eq!(
+            deal_score(&offer(15.0, 15.0, Some(20.0)), &[10.0]),
+            Some(25.0)
+        );
+        assert_eq!(deal_score(&offer(15.0, 15.0, None), &[10.0]), None);
+    }
+
+    #[test]
+    fn cheap_staples_do_not_win_by_themselves() {
+        assert_eq!(deal_score(&offer(8.0, 8.0, None), &[]), None);
+        assert_eq!(deal_score(&offer(0.0, 8.0, Some(10.0)), &[]), None);
+    }
+
+    #[test]
+    fn flags_offers_far_below_the_median() {
+        let past = [30.0, 20.0, 22.0];
+        let cheap = offer(10.0, 10.0, None);
+        let flagged = anomaly(&cheap, &past);
+        assert_eq!(flagged.map(|anomaly| anomaly.usual_unit_price), Some(22.0));
+        assert!(anomaly(&offer(15.0, 15.0, None), &past).is_none());
+        assert!(anomaly(&offer(1.0, 1.0, None), &past[..2]).is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use crate::requests::offer::Offer;
+
+/// The text the way it is compared when searching: lowercase, with æ, ø and
+/// å spelled out as ae, oe and aa and accents left out. So "smoer" finds
+/// "Smør", "aeg" finds "Æg" and "creme" finds "Crème".
+pub fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        fold_char(c, &mut folded);
+    }
+    folded
+}
+
+fn fold_char(c: char, folded: &mut String) {
+    for c in c.to_lowercase() {
+        match c {
+            'æ' | 'ä' => folded.push_str("ae"),
+            'ø' | 'ö' => folded.push_str("oe"),
+            'å' => folded.push_str("aa"),
+            'á' | 'à' | 'â' | 'ã' => folded.push('a'),
+            'é' | 'è' | 'ê' | 'ë' => folded.push('e'),
+            'í' | 'ì' | 'î' | 'ï' => folded.push('i'),
+            'ó' | 'ò' | 'ô' | 'õ' => folded.push('o'),
+            'ú' | 'ù' | 'û' | 'ü' => folded.push('u'),
+            'ç' => folded.push('c'),
+            'ñ' => folded.push('n'),
+            c => folded.push(c),
+        }
+    }
+}
+
+/// Where the terms are in the text, as byte ranges of the text itself and
+/// not of the folded text. Overlapping matches are merged.
+pub fn match_ranges(text: &str, terms: &[impl AsRef<str>]) -> Vec<Range<usize>> {
+    // The range of the original character every folded byte comes from
+    let mut folded = String::with_capacity(text.len());
+    let mut origins = Vec::with_capacity(text.len());
+    for (at, c) in text.char_indices() {
+        let before = folded.len();
+        fold_char(c, &mut folded);
+        origins.extend(std::iter::repeat_n(
+            at..at + c.len_utf8(),
+            folded.len() - before,
+        ));
+    }
+    let mut ranges: Vec<Range<usize>> = terms
+        .iter()
+        .map(|term| fold(term.as_ref().trim()))
+        .filter(|term| !term.is_empty())
+        .flat_map(|term| {
+            folded
+                .match_indices(&term)
+                .map(|(at, found)| origins[at].start..origins[at + found.len() - 1].end)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Whether the term is part of the text, compared as folded by `fold`.
+pub fn contains(text: &str, term: &str) -> bool {
+    fold(text).contains(&fold(term.trim()))
+}
+
+/// How well a term matches a name, best first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Match {
+    /// The term is a whole word of the name, like "mælk" in "Let mælk"
+    Word,
+    /// A word of the name starts with the term, like "mælk" in "Mælkechokolade"
+    Prefix,
+    /// The term is somewhere inside a word, like "mælk" in "Minimælk"
+    Substring,
+}
+
+/// How well the term matches the name, or `None` when it is not part of it.
+pub fn match_quality(name: &str, term: &str) -> Option<Match> {
+    let name = fold(name);
+    let term = fold(term.trim());
+    if term.is_empty() {
+        return None;
+    }
+    let boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+    name.match_indices(&term)
+        .map(|(at, _)| {
+            let starts = boundary(name[..at].chars().next_back());
+            let ends = boundary(name[at + term.len()..].chars().next());
+            match (starts, ends) {
+                (true, true) => Match::Word,
+                (true, false) => Match::Prefix,
+                _ => Match::Substring,
+            }
+        })
+        .min()
+}
+
+/// Sorts the offers by how well they match the best of the terms, and then
+/// by cost per unit, cheapest first.
+pub fn rank(offers: &mut [Offer], terms: &[impl AsRef<str>]) {
+    let quality = |offer: &Offer| {
+        terms
+            .iter()
+            .filter_map(|term| match_quality(&offer.name, term.as_ref()))
+            .min()
+            .unwrap_or(Match::Substring)
+    };
+    offers.sort_by(|a, b| {
+        quality(a)
+            .cmp(&quality(b))
+            .then(a.cost_per_unit.total_cmp(&b.cost_per_unit))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_danish_letters() {
+        assert_eq!(fold("Smør"), "smoer");
+        assert_eq!(fold("ÆG"), "aeg");
+        assert_eq!(fold("Blåbær"), "blaabaer");
+        assert_eq!(fold("Crème fraîche"), "creme fraiche");
+        assert!(contains("Økologisk smør", "smoer"));
+        assert!(contains("Blåbær", " BLÅ "));
+        assert!(!contains("Smør", "smr"));
+    }
+
+    #[test]
+    fn ranges_are_of_the_original_text() {
+        let text = "Smør og æg";
+        assert_eq!(match_ranges(text, &["smoer"]), vec![0..5]);
+        assert_eq!(match_ranges(text, &["aeg"]), vec![9..12]);
+        assert_eq!(&text[9..12], "æg");
+        // Part of a folded letter highlights the whole letter
+        assert_eq!(match_ranges(text, &["oe"]), vec![2..4]);
+    }
+
+    #[test]
+    fn overlapping_ranges_are_merged() {
+        assert_eq!(match_ranges("Smør", &["smo", "oer"]), vec![0..5]);
+        assert_eq!(match_ranges("mælk mælk", &["mælk"]), vec![0..5, 6..11]);
+        assert!(match_ranges("Smør", &["", "  "]).is_empty());
+    }
+
+    #[test]
+    fn match_quality_orders_words_before_prefixes_before_substrings() {
+        assert_eq!(match_quality("Let mælk", "maelk"), Some(Match::Word));
+        assert_eq!(match_quality("Mælkechokolade", "mælk"), Some(Match::Prefix));
+        assert_eq!(match_quality("Minimælk", "mælk"), Some(Match::Substring));
+        assert_eq!(match_quality("Brød", "mælk"), None);
+        assert_eq!(match_quality("Minimælk og mælk", "mælk"), Some(Match::Word));
+        assert!(Match::Word < Match::Prefix && Match::Prefix < Match::Substring);
+    }
+}
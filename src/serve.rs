@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::RwLock;
+
+use etilbudsavis_cli::{
+    notify,
+    requests::{
+        branding::{self, Branding},
+        registry,
+    },
+    retrieve_offers, Dealer, Offer, UserData,
+};
+
+/// The offers being served, along with the branding of their dealers.
+pub(crate) struct Served {
+    pub(crate) offers: Vec<Offer>,
+    branding: HashMap<Dealer, Branding>,
+}
+
+pub(crate) type Shared = Arc<RwLock<Served>>;
+
+/// Serves the offers of the favorite dealers as JSON until stopped. Every
+/// `interval` the offers are retrieved again, which only fetches the dealers
+/// whose cache is outdated. With `dbus` the offers are served on the session
+/// bus too, announcing new matches of the watchlist. New catalogs of the
+/// dealers are sent as notifications.
+pub(crate) async fn run(
+    mut userdata: UserData,
+    addr: SocketAddr,
+    interval: Duration,
+    dbus: bool,
+) -> Result<()> {
+    notify::notify_new_catalogs();
+    let served: Shared = Arc::new(RwLock::new(retrieve(&mut userdata).await));
+    #[cfg(feature = "dbus")]
+    let mut bus = if dbus {
+        Some(crate::dbus::Bus::register(served.clone(), &userdata.watchlist).await?)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "dbus"))]
+    if dbus {
+        anyhow::bail!("Built without D-Bus support, build with `--features dbus` for it");
+    }
+    let refreshed = served.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let fresh = retrieve(&mut userdata).await;
+            #[cfg(feature = "dbus")]
+            if let Some(bus) = &mut bus {
+                bus.refreshed(&userdata.watchlist, &fresh.offers).await;
+            }
+            *refreshed.write().await = fresh;
+        }
+    });
+    let app = Router::new()
+        .route("/offers", get(list_offers))
+        .route("/dealers", get(list_dealers))
+        .with_state(served);
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Could not listen on {addr}"))?;
+    tracing::info!("Serving offers on http://{addr}");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn retrieve(userdata: &mut UserData) -> Served {
+    let offers = retrieve_offers(userdata, false).await;
+    let mut dealers: Vec<Dealer> = offers.iter().map(|offer| offer.dealer).collect();
+    dealers.sort();
+    dealers.dedup();
+    let branding = branding::get_branding(&dealers).await;
+    Served { offers, branding }
+}
+
+#[derive(Deserialize)]
+struct OfferQuery {
+    /// Dealers separated by commas
+    dealer: Option<String>,
+    q: Option<String>,
+}
+
+async fn list_offers(
+    State(served): State<Shared>,
+    Query(query): Query<OfferQuery>,
+) -> Result<Json<Vec<Offer>>, (StatusCode, String)> {
+    let dealers = match &query.dealer {
+        Some(dealers) => dealers
+            .split(',')
+            .map(Dealer::from_str)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?,
+        None => Vec::new(),
+    };
+    let offers = served
+        .read()
+        .await
+        .offers
+        .iter()
+        .filter(|offer| dealers.is_empty() || dealers.contains(&offer.dealer))
+        .filter(|offer| query.q.as_ref().is_none_or(|q| offer.matches(q)))
+        .cloned()
+        .collect();
+    Ok(Json(offers))
+}
+
+#[derive(Serialize)]
+struct ServedDealer {
+    name: &'static str,
+    slug: String,
+    id: &'static str,
+    offers: usize,
+    /// Brand color as `#rrggbb`
+    color: Option<String>,
+    logo: Option<String>,
+}
+
+/// The dealers there are offers of, with their brand colors and logos.
+async fn list_dealers(State(served): State<Shared>) -> Json<Vec<ServedDealer>> {
+    let served = served.read().await;
+    let mut counts: BTreeMap<Dealer, usize> = BTreeMap::new();
+    for offer in served.offers.iter() {
+        *counts.entry(offer.dealer).or_default() += 1;
+    }
+    Json(
+        counts
+            .into_iter()
+            .map(|(dealer, offers)| ServedDealer {
+                name: dealer.name(),
+                slug: registry::slug(dealer.name()),
+                id: dealer.id(),
+                offers,
+                color: served.branding.get(&dealer).and_then(Branding::css_color),
+                logo: served
+                    .branding
+                    .get(&dealer)
+                    .and_then(|branding| branding.logo.clone()),
+            })
+            .collect(),
+    )
+}
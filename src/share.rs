@@ -0,0 +1,90 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{path::Path, process::Command};
+
+use crate::requests::offer::{Offer, Source};
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct ShareConfig {
+    /// Shell command uploading the file given as `$1` and writing its URL to
+    /// stdout, e.g. `curl -sF "file=@$1" https://0x0.st`
+    pub upload_command: Option<String>,
+}
+
+/// Link to the offer on eTilbudsavis.
+pub fn offer_link(offer: &Offer) -> String {
+    if offer.link.is_empty() {
+        link_to(&offer.id)
+    } else {
+        offer.link.clone()
+    }
+}
+
+/// Link to the offer with the id on eTilbudsavis.
+pub fn link_to(id: &str) -> String {
+    format!("https://etilbudsavis.dk/offers/{id}")
+}
+
+/// Link to the catalog page the offer is on.
+pub fn catalog_link(source: &Source) -> String {
+    match source.page {
+        Some(page) => format!(
+            "https://etilbudsavis.dk/catalogs/{}?page={page}",
+            source.catalog_id
+        ),
+        None => format!("https://etilbudsavis.dk/catalogs/{}", source.catalog_id),
+    }
+}
+
+/// Opens the URL in `$BROWSER`, or else the default browser of the system.
+pub fn open(url: &str) -> Result<()> {
+    let mut command = match std::env::var("BROWSER") {
+        Ok(browser) if !browser.is_empty() => {
+            let mut command = Command::new("sh");
+            command.args(["-c", &format!("{browser} \"$1\""), "sh", url]);
+            command
+        }
+        _ if cfg!(target_os = "macos") => {
+            let mut command = Command::new("open");
+            command.arg(url);
+            command
+        }
+        _ if cfg!(windows) => {
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", "", url]);
+            command
+        }
+        _ => {
+            let mut command = Command::new("xdg-open");
+            command.arg(url);
+            command
+        }
+    };
+    let status = command.status().context("Could not start browser")?;
+    if !status.success() {
+        bail!("Browser exited with {status}");
+    }
+    Ok(())
+}
+
+/// Uploads a file with the configured command and returns the URL it printed.
+pub fn upload(config: &ShareConfig, path: &Path) -> Result<String> {
+    let command = config
+        .upload_command
+        .as_deref()
+        .context("No upload command set, add share.upload_command to the config")?;
+    let output = Command::new("sh")
+        .args(["-c", command, "sh"])
+        .arg(path)
+        .output()
+        .context("Could not start upload command")?;
+    if !output.status.success() {
+        bail!("Upload command exited with {}", output.status);
+    }
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if url.is_empty() {
+        bail!("Upload command did not print a URL");
+    }
+    Ok(url)
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use serde::Deserialize;
+use std::{io::Write, path::PathBuf};
+
+use crate::{requests::offer::Offer, week::Week};
+
+const PREFIX: &str = "offers-";
+const EXTENSION: &str = ".json.gz";
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// Directory to archive snapshots in [default: snapshots in the cache dir]
+    pub dir: Option<PathBuf>,
+    /// How many weekly snapshots to keep
+    pub keep: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        SnapshotConfig {
+            dir: None,
+            keep: 52,
+        }
+    }
+}
+
+fn snapshot_dir(config: &SnapshotConfig) -> Result<PathBuf> {
+    match &config.dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(dirs::cache_dir()
+            .context("Could not find cache dir")?
+            .join("etilbudsavis-cli/snapshots")),
+    }
+}
+
+/// Archives the offers as gzipped JSON named after the week, replacing an
+/// earlier snapshot of the same week, and deletes the oldest snapshots beyond
+/// `keep`. Returns the path written to and how many snapshots were deleted.
+pub fn archive(
+    config: &SnapshotConfig,
+    offers: &[Offer],
+    week: Week,
+    keep: usize,
+) -> Result<(PathBuf, usize)> {
+    let dir = snapshot_dir(config)?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    let path = dir.join(format!("{PREFIX}{}{EXTENSION}", week.iso()));
+    let file = std::fs::File::create(&path)
+        .with_context(|| format!("Could not create {}", path.display()))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    serde_json::to_writer(&mut encoder, offers).context("Failed to serialize offers to JSON")?;
+    encoder.finish()?.flush()?;
+
+    let mut snapshots: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(PREFIX) && name.ends_with(EXTENSION))
+        })
+        .collect();
+    // Names sort by week, so the oldest come first
+    snapshots.sort();
+    let expired = snapshots.len().saturating_sub(keep.max(1));
+    for old in &snapshots[..expired] {
+        std::fs::remove_file(old).with_context(|| format!("Could not delete {}", old.display()))?;
+    }
+    Ok((path, expired))
+}
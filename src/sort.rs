@@ -0,0 +1,34 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::requests::offer::Offer;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortKey {
+    Price,
+    UnitPrice,
+    Name,
+    Dealer,
+    EndDate,
+}
+
+impl SortKey {
+    /// Sorts the offers in ascending order of the key, e.g. cheapest first.
+    pub fn sort(&self, offers: &mut [Offer], reverse: bool) {
+        offers.sort_by(|a, b| {
+            let ordering = match self {
+                SortKey::Price => a.price.total_cmp(&b.price),
+                SortKey::UnitPrice => a.cost_per_unit.total_cmp(&b.cost_per_unit),
+                SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                SortKey::Dealer => a.dealer.name().cmp(b.dealer.name()),
+                SortKey::EndDate => a.run_till.cmp(&b.run_till),
+            };
+            if reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
@@ -0,0 +1,341 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use comfy_table::{Cell, CellAlignment, Table};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    catalogs, locale,
+    requests::{cache::CacheEntry, dealer::Dealer, history::PricePoint, offer::Offer},
+    search, style,
+    unit::{self, Unit},
+};
+
+/// Summary of a dealer's currently active offers.
+#[derive(Serialize)]
+pub struct DealerStats {
+    pub dealer: Dealer,
+    pub active_offers: usize,
+    /// Mean discount of the offers that list a price before the offer.
+    pub average_discount: Option<f64>,
+    /// Mean cost per unit for every unit the dealer sells in.
+    pub average_unit_prices: BTreeMap<Unit, f64>,
+    pub valid_from: NaiveDate,
+    pub valid_till: NaiveDate,
+}
+
+pub fn dealer_stats(offers: &[Offer]) -> Vec<DealerStats> {
+    let mut by_dealer: BTreeMap<Dealer, Vec<&Offer>> = BTreeMap::new();
+    for offer in offers.iter().filter(|offer| offer.is_active()) {
+        by_dealer.entry(offer.dealer).or_default().push(offer);
+    }
+
+    by_dealer
+        .into_iter()
+        .map(|(dealer, offers)| {
+            let discounts: Vec<f64> = offers.iter().filter_map(|offer| offer.discount()).collect();
+            let mut unit_prices: BTreeMap<Unit, Vec<f64>> = BTreeMap::new();
+            for offer in offers
+                .iter()
+                .filter(|offer| offer.cost_per_unit.is_finite())
+            {
+                unit_prices
+                    .entry(offer.unit)
+                    .or_default()
+                    .push(offer.cost_per_unit);
+            }
+            DealerStats {
+                dealer,
+                active_offers: offers.len(),
+                average_discount: mean(&discounts),
+                average_unit_prices: unit_prices
+                    .into_iter()
+                    .filter_map(|(unit, prices)| Some((unit, mean(&prices)?)))
+                    .collect(),
+                valid_from: offers
+                    .iter()
+                    .map(|offer| offer.run_from)
+                    .min()
+                    .unwrap_or_default(),
+                valid_till: offers
+                    .iter()
+                    .map(|offer| offer.run_till)
+                    .max()
+                    .unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+pub fn dealer_stats_table(stats: &[DealerStats]) -> Table {
+    let mut table = style::table();
+    table.set_header(vec![
+        locale::text("Dealer", "Butik"),
+        locale::text("Active offers", "Aktive tilbud"),
+        locale::text("Avg. discount", "Gns. rabat"),
+        locale::text("Avg. cost/unit", "Gns. pris/enhed"),
+        locale::text("Valid", "Gyldig"),
+    ]);
+    for stat in stats {
+        let discount = match stat.average_discount {
+            Some(discount) => format!("{:.0}%", discount * 100.0),
+            None => "-".to_string(),
+        };
+        let unit_prices: Vec<String> = stat
+            .average_unit_prices
+            .iter()
+            .map(|(unit, price)| unit::cost(*price, unit))
+            .collect();
+        table.add_row(vec![
+            Cell::new(stat.dealer.to_string()),
+            Cell::new(stat.active_offers).set_alignment(CellAlignment::Right),
+            Cell::new(discount).set_alignment(CellAlignment::Right),
+            Cell::new(unit_prices.join("\n")).set_alignment(CellAlignment::Right),
+            Cell::new(format!(
+                "{} - {}",
+                stat.valid_from.format("%d/%m"),
+                stat.valid_till.format("%d/%m")
+            )),
+        ]);
+    }
+    table
+}
+
+/// Offer counts, unit prices and catalogs of all offers, along with how
+/// fresh their cache is, to check the data at a glance.
+#[derive(Serialize)]
+pub struct Summary {
+    pub dealers: Vec<DealerSummary>,
+    pub unit_prices: Vec<UnitPriceSummary>,
+    pub offers: usize,
+    pub catalogs: usize,
+}
+
+#[derive(Serialize)]
+pub struct DealerSummary {
+    pub dealer: Dealer,
+    pub offers: usize,
+    pub catalogs: usize,
+    /// When the offers were fetched, if they are cached
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// How long ago the offers were fetched, e.g. "5 hours"
+    pub age: Option<String>,
+    /// Whether the cached offers are used without fetching them again
+    pub fresh: Option<bool>,
+}
+
+/// The unit prices of the offers sold in a unit.
+#[derive(Serialize)]
+pub struct UnitPriceSummary {
+    pub unit: Unit,
+    pub offers: usize,
+    pub average: f64,
+    pub median: f64,
+}
+
+pub fn summary(offers: &[Offer], cache: &[CacheEntry]) -> Summary {
+    let listings = catalogs::listings(offers);
+    let mut by_dealer: BTreeMap<Dealer, usize> = BTreeMap::new();
+    for offer in offers {
+        *by_dealer.entry(offer.dealer).or_default() += 1;
+    }
+    let dealers = by_dealer
+        .into_iter()
+        .map(|(dealer, count)| {
+            let cached = cache
+                .iter()
+                .find(|entry| entry.dealer == dealer.to_string());
+            DealerSummary {
+                dealer,
+                offers: count,
+                catalogs: listings
+                    .iter()
+                    .filter(|listing| listing.dealer == dealer)
+                    .count(),
+                fetched_at: cached.map(|entry| entry.fetched_at),
+                age: cached.map(|entry| entry.age.clone()),
+                fresh: cached.map(|entry| entry.fresh),
+            }
+        })
+        .collect();
+
+    let mut by_unit: BTreeMap<Unit, Vec<f64>> = BTreeMap::new();
+    for offer in offers
+        .iter()
+        .filter(|offer| offer.cost_per_unit.is_finite() && offer.cost_per_unit > 0.0)
+    {
+        by_unit
+            .entry(offer.unit)
+            .or_default()
+            .push(offer.cost_per_unit);
+    }
+    let unit_prices = by_unit
+        .into_iter()
+        .filter_map(|(unit, mut prices)| {
+            Some(UnitPriceSummary {
+                unit,
+                offers: prices.len(),
+                average: mean(&prices)?,
+                median: median(&mut prices),
+            })
+        })
+        .collect();
+
+    Summary {
+        dealers,
+        unit_prices,
+        offers: offers.len(),
+        catalogs: listings.len(),
+    }
+}
+
+/// A table of the dealers and a table of the unit prices.
+pub fn summary_tables(summary: &Summary) -> (Table, Table) {
+    let mut dealers = style::table();
+    dealers.set_header(vec![
+        locale::text("Dealer", "Butik"),
+        locale::text("Offers", "Tilbud"),
+        locale::text("Catalogs", "Kataloger"),
+        locale::text("Fetched", "Hentet"),
+        locale::text("Fresh", "Frisk"),
+    ]);
+    for dealer in summary.dealers.iter() {
+        dealers.add_row(vec![
+            Cell::new(dealer.dealer.to_string()),
+            Cell::new(dealer.offers).set_alignment(CellAlignment::Right),
+            Cell::new(dealer.catalogs).set_alignment(CellAlignment::Right),
+            Cell::new(match &dealer.age {
+                Some(age) => format!("{age} {}", locale::text("ago", "siden")),
+                None => locale::text("not cached", "ikke gemt").to_string(),
+            }),
+            Cell::new(match dealer.fresh {
+                Some(true) => locale::text("yes", "ja"),
+                Some(false) => locale::text("no", "nej"),
+                None => "-",
+            }),
+        ]);
+    }
+    let mut unit_prices = style::table();
+    unit_prices.set_header(vec![
+        locale::text("Unit", "Enhed"),
+        locale::text("Offers", "Tilbud"),
+        locale::text("Avg. cost/unit", "Gns. pris/enhed"),
+        locale::text("Median cost/unit", "Median pris/enhed"),
+    ]);
+    for prices in summary.unit_prices.iter() {
+        unit_prices.add_row(vec![
+            Cell::new(prices.unit.symbol()),
+            Cell::new(prices.offers).set_alignment(CellAlignment::Right),
+            Cell::new(unit::cost(prices.average, prices.unit)).set_alignment(CellAlignment::Right),
+            Cell::new(unit::cost(prices.median, prices.unit)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    (dealers, unit_prices)
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Weighted price index of the basket for one dealer, with the first month
+/// each product was seen in as 100.
+#[derive(Serialize)]
+pub struct InflationIndex {
+    pub dealer: String,
+    /// Index by month (`YYYY-MM`), along with how many products of the
+    /// basket it is based on
+    pub months: BTreeMap<String, (f64, usize)>,
+}
+
+/// Computes the basket's price index per dealer and month from the price
+/// history. A product's price in a month is the median cost per unit of the
+/// offers matching it, and products missing in a month are left out of it.
+pub fn inflation_index(
+    points: &[PricePoint],
+    basket: &BTreeMap<String, f64>,
+) -> Vec<InflationIndex> {
+    // Unit prices by dealer, product and month
+    let mut prices: BTreeMap<&str, BTreeMap<&str, BTreeMap<&str, Vec<f64>>>> = BTreeMap::new();
+    for point in points
+        .iter()
+        .filter(|point| point.cost_per_unit.is_finite())
+    {
+        let name = search::fold(&point.name);
+        let Some(month) = point.run_from.get(..7) else {
+            continue;
+        };
+        for product in basket.keys() {
+            if name.contains(&search::fold(product)) {
+                prices
+                    .entry(&point.dealer)
+                    .or_default()
+                    .entry(product)
+                    .or_default()
+                    .entry(month)
+                    .or_default()
+                    .push(point.cost_per_unit);
+            }
+        }
+    }
+
+    prices
+        .into_iter()
+        .map(|(dealer, products)| {
+            // Weighted price relatives by month
+            let mut relatives: BTreeMap<String, (f64, f64, usize)> = BTreeMap::new();
+            for (product, months) in products {
+                let weight = basket[product];
+                let medians: Vec<(&str, f64)> = months
+                    .into_iter()
+                    .map(|(month, mut prices)| (month, median(&mut prices)))
+                    .collect();
+                let base = medians[0].1;
+                for (month, price) in medians {
+                    let entry = relatives.entry(month.to_string()).or_default();
+                    entry.0 += weight * price / base;
+                    entry.1 += weight;
+                    entry.2 += 1;
+                }
+            }
+            InflationIndex {
+                dealer: dealer.to_string(),
+                months: relatives
+                    .into_iter()
+                    .map(|(month, (sum, weights, count))| (month, (sum / weights * 100.0, count)))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+pub fn inflation_table(indices: &[InflationIndex], basket_size: usize) -> Table {
+    let mut table = style::table();
+    let mut header = vec![locale::text("Month", "Måned").to_string()];
+    header.extend(indices.iter().map(|index| index.dealer.clone()));
+    table.set_header(header);
+    let months: BTreeSet<&String> = indices
+        .iter()
+        .flat_map(|index| index.months.keys())
+        .collect();
+    for month in months {
+        let mut row = vec![Cell::new(month)];
+        for index in indices {
+            let text = match index.months.get(month) {
+                Some((value, count)) => format!("{value:.1} ({count}/{basket_size})"),
+                None => "-".to_string(),
+            };
+            row.push(Cell::new(text).set_alignment(CellAlignment::Right));
+        }
+        table.add_row(row);
+    }
+    table
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
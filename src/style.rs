@@ -0,0 +1,195 @@
+use clap::ValueEnum;
+#[cfg(feature = "native")]
+use comfy_table::{modifiers::UTF8_ROUND_CORNERS, presets, ContentArrangement, Table};
+use std::{
+    io::IsTerminal,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+/// Set by `--ascii` for plain output that works with screen readers.
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Whether to color output, from `--color`, `NO_COLOR` and the terminal.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--relative-dates` or the config.
+static RELATIVE_DATES: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--ascii-borders`, or when the terminal is dumb.
+static ASCII_BORDERS: AtomicBool = AtomicBool::new(false);
+
+/// Set once from `--wide` or `--narrow`.
+static WIDTH: OnceLock<TableWidth> = OnceLock::new();
+
+/// How tables are fit to the terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableWidth {
+    /// Wrap cells so the table is as wide as the terminal, or 100 columns
+    /// when not printing to one
+    Fit,
+    /// Never wrap cells, however wide the table gets
+    Wide,
+    /// At most 80 columns, with long offer names cut short
+    Narrow,
+}
+
+/// When to color the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// When printing to a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+/// Offer names are cut to this many characters in narrow tables.
+pub const NARROW_NAME_LENGTH: usize = 28;
+
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+/// Chooses whether to color the output. Call after `set_ascii`, as ASCII
+/// output is never colored.
+pub fn set_color(choice: ColorChoice) {
+    let color = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && std::io::stdout().is_terminal()
+        }
+    };
+    COLOR.store(color && !is_ascii(), Ordering::Relaxed);
+}
+
+pub fn use_color() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+pub fn set_relative_dates(relative: bool) {
+    RELATIVE_DATES.store(relative, Ordering::Relaxed);
+}
+
+/// Whether offer periods are shown like "ends tomorrow" instead of dates.
+pub fn relative_dates() -> bool {
+    RELATIVE_DATES.load(Ordering::Relaxed)
+}
+
+pub fn set_ascii_borders(ascii_borders: bool) {
+    let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    ASCII_BORDERS.store(ascii_borders || dumb, Ordering::Relaxed);
+}
+
+pub fn set_table_width(width: TableWidth) {
+    let _ = WIDTH.set(width);
+}
+
+pub fn table_width() -> TableWidth {
+    WIDTH.get().copied().unwrap_or(TableWidth::Fit)
+}
+
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// The symbol to use in the current output mode.
+pub fn symbol(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if is_ascii() {
+        ascii
+    } else {
+        unicode
+    }
+}
+
+/// An empty table in the current output mode. In ASCII mode columns are only
+/// separated by spaces, rows are never wrapped and nothing is colored.
+#[cfg(feature = "native")]
+pub fn table() -> Table {
+    let mut table = Table::new();
+    if is_ascii() {
+        table
+            .load_preset(presets::NOTHING)
+            .set_content_arrangement(ContentArrangement::Disabled)
+            .force_no_tty();
+    } else if ASCII_BORDERS.load(Ordering::Relaxed) {
+        table.load_preset(presets::ASCII_FULL);
+    } else {
+        table
+            .load_preset(presets::UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+    if use_color() {
+        table.enforce_styling();
+    } else {
+        table.force_no_tty();
+    }
+    table
+}
+
+/// The width of the terminal output is printed to, if it is one.
+#[cfg(feature = "native")]
+fn terminal_width() -> Option<u16> {
+    Table::new().width()
+}
+
+/// The text with the byte ranges in bold yellow, when coloring output.
+pub fn highlight(text: &str, ranges: &[Range<usize>]) -> String {
+    if !use_color() || ranges.is_empty() {
+        return text.to_string();
+    }
+    let mut highlighted = String::with_capacity(text.len());
+    let mut at = 0;
+    for range in ranges {
+        highlighted.push_str(&text[at..range.start]);
+        highlighted.push_str("\x1b[1;33m");
+        highlighted.push_str(&text[range.clone()]);
+        highlighted.push_str("\x1b[0m");
+        at = range.end;
+    }
+    highlighted.push_str(&text[at..]);
+    highlighted
+}
+
+/// Fits the table to the terminal as chosen with `--wide` or `--narrow`.
+/// Tables in ASCII mode are left as they are, as they never wrap.
+#[cfg(feature = "native")]
+pub fn fit(table: &mut Table) {
+    if is_ascii() {
+        return;
+    }
+    match table_width() {
+        TableWidth::Wide => {
+            table.set_content_arrangement(ContentArrangement::Disabled);
+        }
+        TableWidth::Fit => {
+            table
+                .set_content_arrangement(ContentArrangement::Dynamic)
+                .set_width(terminal_width().unwrap_or(100));
+        }
+        TableWidth::Narrow => {
+            let width = terminal_width().map_or(80, |width| width.min(80));
+            table
+                .set_content_arrangement(ContentArrangement::Dynamic)
+                .set_width(width);
+        }
+    }
+}
+
+/// The text cut to at most `length` characters, ending with an ellipsis
+/// when it was cut.
+pub fn truncate(text: &str, length: usize) -> String {
+    if text.chars().count() <= length {
+        return text.to_string();
+    }
+    let ellipsis = symbol("…", "...");
+    let kept = length.saturating_sub(ellipsis.chars().count());
+    let mut text: String = text.chars().take(kept).collect();
+    text.truncate(text.trim_end().len());
+    text.push_str(ellipsis);
+    text
+}
@@ -0,0 +1,38 @@
+use std::collections::HashSet;
+
+use crate::requests::offer::Offer;
+
+/// Lowest normalized similarity for a word to be suggested.
+const MIN_SIMILARITY: f64 = 0.75;
+
+/// Words from the offer names that are close to `term`, best match first.
+pub fn did_you_mean(term: &str, offers: &[Offer]) -> Vec<String> {
+    let term = term.trim().to_lowercase();
+    let words: HashSet<String> = offers
+        .iter()
+        .flat_map(|offer| {
+            offer
+                .name
+                .to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| word.chars().count() > 1)
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    closest(&term, words.iter().map(String::as_str))
+}
+
+/// The candidates closest to `term` by edit distance, at most three.
+pub fn closest<'a>(term: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut scored: Vec<(f64, &str)> = candidates
+        .map(|candidate| (strsim::normalized_levenshtein(term, candidate), candidate))
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(b.1)));
+    scored
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
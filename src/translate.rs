@@ -0,0 +1,186 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::requests::offer::Offer;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct TranslateConfig {
+    /// Shell command reading a Danish name on stdin and writing the English
+    /// translation to stdout, e.g. `trans -b da:en`
+    pub command: Option<String>,
+    /// Extra words for the built-in dictionary, taking precedence over it
+    pub words: HashMap<String, String>,
+}
+
+/// Common words of Danish offer names.
+const DICTIONARY: &[(&str, &str)] = &[
+    ("agurk", "cucumber"),
+    ("and", "duck"),
+    ("appelsin", "orange"),
+    ("appelsinjuice", "orange juice"),
+    ("bacon", "bacon"),
+    ("bananer", "bananas"),
+    ("brød", "bread"),
+    ("bønner", "beans"),
+    ("chokolade", "chocolate"),
+    ("citron", "lemon"),
+    ("fisk", "fish"),
+    ("fløde", "cream"),
+    ("frikadeller", "meatballs"),
+    ("frugt", "fruit"),
+    ("fuldkorn", "wholegrain"),
+    ("gulerødder", "carrots"),
+    ("hakket", "minced"),
+    ("havregryn", "rolled oats"),
+    ("hvidløg", "garlic"),
+    ("is", "ice cream"),
+    ("juice", "juice"),
+    ("kaffe", "coffee"),
+    ("kaffebønner", "coffee beans"),
+    ("kage", "cake"),
+    ("kalkun", "turkey"),
+    ("kartofler", "potatoes"),
+    ("kiks", "biscuits"),
+    ("kylling", "chicken"),
+    ("kyllingebryst", "chicken breast"),
+    ("kød", "meat"),
+    ("laks", "salmon"),
+    ("lam", "lamb"),
+    ("let", "light"),
+    ("letmælk", "semi-skimmed milk"),
+    ("leverpostej", "liver pâté"),
+    ("løg", "onions"),
+    ("mel", "flour"),
+    ("mælk", "milk"),
+    ("minimælk", "skimmed milk"),
+    ("nødder", "nuts"),
+    ("okse", "beef"),
+    ("oksekød", "beef"),
+    ("olie", "oil"),
+    ("ost", "cheese"),
+    ("pølser", "sausages"),
+    ("rejer", "shrimp"),
+    ("ris", "rice"),
+    ("rugbrød", "rye bread"),
+    ("saft", "cordial"),
+    ("salat", "lettuce"),
+    ("sild", "herring"),
+    ("skinke", "ham"),
+    ("skiveskåret", "sliced"),
+    ("smør", "butter"),
+    ("sodavand", "soft drink"),
+    ("stk", "pcs"),
+    ("sukker", "sugar"),
+    ("svinekød", "pork"),
+    ("svinemørbrad", "pork tenderloin"),
+    ("sødmælk", "whole milk"),
+    ("tomater", "tomatoes"),
+    ("torsk", "cod"),
+    ("vand", "water"),
+    ("vin", "wine"),
+    ("æbler", "apples"),
+    ("æg", "eggs"),
+    ("økologisk", "organic"),
+    ("øl", "beer"),
+];
+
+pub struct Translator<'a> {
+    config: &'a TranslateConfig,
+    dictionary: HashMap<&'a str, &'a str>,
+    cache: HashMap<String, String>,
+}
+
+impl<'a> Translator<'a> {
+    pub fn new(config: &'a TranslateConfig) -> Self {
+        let mut dictionary: HashMap<&str, &str> = DICTIONARY.iter().copied().collect();
+        for (danish, english) in config.words.iter() {
+            dictionary.insert(danish.as_str(), english.as_str());
+        }
+        Translator {
+            config,
+            dictionary,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Translates an offer name with the configured command, falling back to
+    /// the dictionary when there is no command or it fails.
+    pub fn translate(&mut self, name: &str) -> String {
+        if let Some(translation) = self.cache.get(name) {
+            return translation.clone();
+        }
+        let translation = match self.config.command.as_deref() {
+            Some(command) => run_command(command, name).unwrap_or_else(|err| {
+                tracing::warn!("Translation command failed: {err:#}");
+                self.translate_words(name)
+            }),
+            None => self.translate_words(name),
+        };
+        self.cache.insert(name.to_string(), translation.clone());
+        translation
+    }
+
+    /// Word by word translation, leaving unknown words as they are.
+    fn translate_words(&self, name: &str) -> String {
+        name.split(' ')
+            .map(|word| {
+                let trimmed = word.trim_matches(|c: char| !c.is_alphanumeric());
+                match self.dictionary.get(trimmed.to_lowercase().as_str()) {
+                    Some(english) if !trimmed.is_empty() => {
+                        word.replacen(trimmed, &match_case(trimmed, english), 1)
+                    }
+                    _ => word.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Capitalizes the translation when the original word was.
+fn match_case(original: &str, translation: &str) -> String {
+    let mut chars = translation.chars();
+    match (original.chars().next(), chars.next()) {
+        (Some(first), Some(translated_first)) if first.is_uppercase() => {
+            translated_first.to_uppercase().chain(chars).collect()
+        }
+        _ => translation.to_string(),
+    }
+}
+
+fn run_command(command: &str, name: &str) -> Result<String> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Could not start translation command")?;
+    child
+        .stdin
+        .take()
+        .context("Could not write to translation command")?
+        .write_all(name.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("exited with {}", output.status);
+    }
+    let translation = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if translation.is_empty() {
+        bail!("no translation for {name}");
+    }
+    Ok(translation)
+}
+
+/// Replaces the offer names with English translations.
+pub fn translate_offers(offers: &mut [Offer], config: &TranslateConfig) {
+    let mut translator = Translator::new(config);
+    for offer in offers.iter_mut() {
+        offer.name = translator.translate(&offer.name);
+    }
+}
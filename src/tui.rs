@@ -0,0 +1,261 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    DefaultTerminal, Frame,
+};
+use std::collections::{HashMap, HashSet};
+
+use etilbudsavis_cli::{
+    locale, requests::branding::Branding, share, sort::SortKey, unit, Dealer, Offer,
+};
+
+/// Browses the offers in a scrollable table until the user quits. Dealers
+/// are shown in their brand colors.
+pub(crate) fn run(offers: Vec<Offer>, branding: HashMap<Dealer, Branding>) -> Result<()> {
+    let mut app = App::new(offers, branding);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+struct App {
+    offers: Vec<Offer>,
+    /// The offers shown, in the shown order
+    visible: Vec<Offer>,
+    state: TableState,
+    search: String,
+    searching: bool,
+    sort: SortKey,
+    reverse: bool,
+    /// Dealers with offers, toggled with their number
+    dealers: Vec<Dealer>,
+    hidden: HashSet<Dealer>,
+    branding: HashMap<Dealer, Branding>,
+}
+
+impl App {
+    fn new(offers: Vec<Offer>, branding: HashMap<Dealer, Branding>) -> Self {
+        let mut dealers: Vec<Dealer> = offers.iter().map(|offer| offer.dealer).collect();
+        dealers.sort();
+        dealers.dedup();
+        let mut app = App {
+            offers,
+            visible: Vec::new(),
+            state: TableState::default(),
+            search: String::new(),
+            searching: false,
+            sort: SortKey::UnitPrice,
+            reverse: false,
+            dealers,
+            hidden: HashSet::new(),
+            branding,
+        };
+        app.refresh();
+        app
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.searching {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.searching = false,
+                    KeyCode::Backspace => {
+                        self.search.pop();
+                        self.refresh();
+                    }
+                    KeyCode::Char(c) => {
+                        self.search.push(c);
+                        self.refresh();
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                KeyCode::PageDown => self.state.scroll_down_by(10),
+                KeyCode::PageUp => self.state.scroll_up_by(10),
+                KeyCode::Home | KeyCode::Char('g') => self.state.select_first(),
+                KeyCode::End | KeyCode::Char('G') => self.state.select_last(),
+                KeyCode::Char('/') => self.searching = true,
+                KeyCode::Char('s') => {
+                    let keys = SortKey::value_variants();
+                    let current = keys.iter().position(|key| *key == self.sort).unwrap_or(0);
+                    self.sort = keys[(current + 1) % keys.len()];
+                    self.refresh();
+                }
+                KeyCode::Char('r') => {
+                    self.reverse = !self.reverse;
+                    self.refresh();
+                }
+                KeyCode::Char(c @ '1'..='9') => {
+                    let index = c as usize - '1' as usize;
+                    if let Some(&dealer) = self.dealers.get(index) {
+                        if !self.hidden.remove(&dealer) {
+                            self.hidden.insert(dealer);
+                        }
+                        self.refresh();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Filters and sorts the offers again after the search, sorting or
+    /// dealers changed.
+    fn refresh(&mut self) {
+        self.visible = self
+            .offers
+            .iter()
+            .filter(|offer| !self.hidden.contains(&offer.dealer))
+            .filter(|offer| self.search.is_empty() || offer.matches(&self.search))
+            .cloned()
+            .collect();
+        self.sort.sort(&mut self.visible, self.reverse);
+        self.state.select((!self.visible.is_empty()).then_some(0));
+    }
+
+    /// The dealer's name in its brand color, if it has one.
+    fn dealer_style(&self, dealer: &Dealer) -> Style {
+        match self.branding.get(dealer).and_then(Branding::rgb) {
+            Some((r, g, b)) => Style::new().fg(Color::Rgb(r, g, b)),
+            None => Style::new(),
+        }
+    }
+
+    fn selected(&self) -> Option<&Offer> {
+        self.visible.get(self.state.selected()?)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(2)]).areas(frame.area());
+        let [table_area, details_area] =
+            Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .areas(main);
+
+        let rows = self.visible.iter().map(|offer| {
+            Row::new(vec![
+                Cell::from(offer.dealer.to_string()).style(self.dealer_style(&offer.dealer)),
+                Cell::from(offer.name.clone()),
+                Cell::from(locale::decimal(offer.price, 2)),
+                Cell::from(unit::cost(offer.cost_per_unit, offer.unit).replace(" kr", "")),
+                Cell::from(offer.run_till.format("%d/%m").to_string()),
+            ])
+        });
+        let sort = format!(
+            "{:?}{}",
+            self.sort,
+            if self.reverse { ", reversed" } else { "" }
+        );
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(12),
+                Constraint::Length(6),
+            ],
+        )
+        .header(
+            Row::new(vec!["Dealer", "Product", "Price", "Cost/unit", "Ends"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::new()
+                .borders(Borders::ALL)
+                .title(format!(" {} offers, sorted by {sort} ", self.visible.len())),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, table_area, &mut self.state);
+
+        let details = match self.selected() {
+            Some(offer) => details(offer),
+            None => vec![Line::from("No offers")],
+        };
+        frame.render_widget(
+            Paragraph::new(details)
+                .wrap(Wrap { trim: false })
+                .block(Block::new().borders(Borders::ALL).title(" Details ")),
+            details_area,
+        );
+
+        let mut dealers: Vec<Span> = Vec::new();
+        for (index, dealer) in self.dealers.iter().take(9).enumerate() {
+            let mark = if self.hidden.contains(dealer) {
+                " "
+            } else {
+                "x"
+            };
+            if index > 0 {
+                dealers.push(Span::raw("  "));
+            }
+            dealers.push(Span::raw(format!("{}[{mark}] ", index + 1)));
+            dealers.push(Span::styled(dealer.to_string(), self.dealer_style(dealer)));
+        }
+        let search = if self.searching {
+            format!("Search: {}_", self.search)
+        } else if self.search.is_empty() {
+            "/ search  s sort  r reverse  1-9 toggle dealer  q quit".to_string()
+        } else {
+            format!("Search: {}  (/ to edit)", self.search)
+        };
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(search), Line::from(dealers)]),
+            status,
+        );
+    }
+}
+
+fn details(offer: &Offer) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(offer.name.clone()).style(Style::new().add_modifier(Modifier::BOLD)),
+        Line::from(format!("Dealer: {}", offer.dealer)),
+        Line::from(format!(
+            "Period: {} - {}",
+            offer.run_from.format("%d/%m"),
+            offer.run_till.format("%d/%m")
+        )),
+        Line::from(format!("Price: {}", locale::money(offer.price))),
+    ];
+    if let Some(pre_price) = offer.pre_price {
+        lines.push(Line::from(format!(
+            "Price before: {}",
+            locale::money(pre_price)
+        )));
+    }
+    lines.push(Line::from(format!(
+        "Cost/unit: {}",
+        unit::cost(offer.cost_per_unit, offer.unit)
+    )));
+    if offer.is_sized() {
+        lines.push(Line::from(format!(
+            "Size: {} {}",
+            offer.max_size, offer.unit
+        )));
+    }
+    lines.push(Line::from(format!(
+        "Count: {}-{}",
+        offer.min_amount, offer.max_amount
+    )));
+    lines.push(Line::from(""));
+    lines.push(Line::from(share::offer_link(offer)));
+    lines
+}
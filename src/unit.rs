@@ -0,0 +1,132 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::OnceLock};
+
+use crate::locale;
+
+/// A unit offers are priced per. Whatever unit the dealer gives a size in
+/// is normalized to one of these while parsing.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Kg,
+    L,
+    #[default]
+    Stk,
+}
+
+impl Unit {
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Kg => "kg",
+            Unit::L => "l",
+            Unit::Stk => "stk",
+        }
+    }
+
+    /// Whether offers in the unit are sold by weight or volume rather than
+    /// per piece.
+    pub fn is_sized(&self) -> bool {
+        *self != Unit::Stk
+    }
+
+    /// The unit a symbol like `g`, `cl` or `pcs` measures in, along with how
+    /// much of the unit one of the symbol is.
+    pub fn parse(symbol: &str) -> Option<(Unit, f64)> {
+        let unit = match symbol.trim().trim_end_matches('.').to_lowercase().as_str() {
+            "mg" => (Unit::Kg, 0.000_001),
+            "g" | "gr" | "gram" => (Unit::Kg, 0.001),
+            "kg" | "kilo" => (Unit::Kg, 1.0),
+            "ml" => (Unit::L, 0.001),
+            "cl" => (Unit::L, 0.01),
+            "dl" => (Unit::L, 0.1),
+            "l" | "ltr" | "liter" => (Unit::L, 1.0),
+            "pcs" | "pc" | "stk" | "st" | "piece" | "pieces" => (Unit::Stk, 1.0),
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+impl AsRef<str> for Unit {
+    fn as_ref(&self) -> &str {
+        self.symbol()
+    }
+}
+
+/// What unit prices are shown per. Prices are kept per kg, l and stk, and
+/// only converted when shown, so sorting and JSON output are not affected.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+pub enum UnitBasis {
+    /// Per 100 g, like many Danish shelf labels
+    #[value(name = "100g")]
+    #[serde(rename = "100g")]
+    Per100g,
+    #[serde(rename = "kg")]
+    Kg,
+    #[value(name = "100ml")]
+    #[serde(rename = "100ml")]
+    Per100ml,
+    #[serde(rename = "l")]
+    L,
+    #[serde(rename = "stk")]
+    Stk,
+}
+
+impl UnitBasis {
+    /// The unit of the offers the basis is for, and how much of it the basis
+    /// is.
+    fn base(&self) -> (Unit, f64) {
+        match self {
+            UnitBasis::Per100g => (Unit::Kg, 0.1),
+            UnitBasis::Kg => (Unit::Kg, 1.0),
+            UnitBasis::Per100ml => (Unit::L, 0.1),
+            UnitBasis::L => (Unit::L, 1.0),
+            UnitBasis::Stk => (Unit::Stk, 1.0),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            UnitBasis::Per100g => "100g",
+            UnitBasis::Per100ml => "100ml",
+            UnitBasis::Kg | UnitBasis::L | UnitBasis::Stk => self.base().0.symbol(),
+        }
+    }
+}
+
+/// Set once from `--unit-basis` or the config.
+static BASES: OnceLock<Vec<UnitBasis>> = OnceLock::new();
+
+pub fn set_bases(bases: Vec<UnitBasis>) {
+    let _ = BASES.set(bases);
+}
+
+/// A cost per kg, l or stk converted to the chosen basis for the unit, along
+/// with the unit it is per now.
+pub fn convert(cost_per_unit: f64, unit: &str) -> (f64, &str) {
+    let basis = BASES
+        .get()
+        .into_iter()
+        .flatten()
+        .find(|basis| basis.base().0.symbol() == unit);
+    match basis {
+        Some(basis) => (cost_per_unit * basis.base().1, basis.label()),
+        None => (cost_per_unit, unit),
+    }
+}
+
+/// A cost per unit like "2.45 kr/100g", in the chosen basis for the unit.
+/// The unit is a `Unit`, or the unit of a price from the history.
+pub fn cost(cost_per_unit: f64, unit: impl AsRef<str>) -> String {
+    let (cost, unit) = convert(cost_per_unit, unit.as_ref());
+    format!("{}/{unit}", locale::money(cost))
+}
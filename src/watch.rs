@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use crate::requests::offer::Offer;
+
+/// A product to watch for, with the highest cost per unit worth alerting on.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Watch {
+    /// Matched against offer names like a search term
+    pub term: String,
+    /// Highest cost per unit in kr
+    pub max: f64,
+    /// Only match offers in this unit, e.g. `kg`
+    #[serde(default)]
+    pub unit: Option<String>,
+}
+
+impl Watch {
+    pub fn matches(&self, offer: &Offer) -> bool {
+        offer.matches(&self.term)
+            && offer.cost_per_unit <= self.max
+            && self
+                .unit
+                .as_ref()
+                .is_none_or(|unit| offer.unit.symbol() == unit)
+    }
+}
+
+/// Every offer at or below the threshold of a watch, along with the watch.
+pub fn check<'a>(watchlist: &'a [Watch], offers: &'a [Offer]) -> Vec<(&'a Watch, &'a Offer)> {
+    watchlist
+        .iter()
+        .flat_map(|watch| {
+            offers
+                .iter()
+                .filter(|offer| watch.matches(offer))
+                .map(move |offer| (watch, offer))
+        })
+        .collect()
+}
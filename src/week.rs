@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
+use std::str::FromStr;
+
+use crate::{locale, requests::offer::Offer};
+
+/// An ISO week, the unit Danish leaflets are published and discussed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Week {
+    year: i32,
+    week: u32,
+}
+
+impl Week {
+    pub fn of(date: NaiveDate) -> Self {
+        let week = date.iso_week();
+        Week {
+            year: week.year(),
+            week: week.week(),
+        }
+    }
+
+    pub fn current() -> Self {
+        Week::of(Utc::now().date_naive())
+    }
+
+    pub fn next(&self) -> Self {
+        Week::of(self.first_day() + Duration::days(7))
+    }
+
+    pub fn first_day(&self) -> NaiveDate {
+        NaiveDate::from_isoywd_opt(self.year, self.week, Weekday::Mon)
+            .expect("week was constructed from a valid date")
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        self.first_day() + Duration::days(6)
+    }
+
+    /// The week as `<year>-W<week>`, which sorts chronologically.
+    pub fn iso(&self) -> String {
+        format!("{}-W{:02}", self.year, self.week)
+    }
+
+    /// Whether the offer runs on any day of the week.
+    pub fn overlaps(&self, offer: &Offer) -> bool {
+        offer.run_from <= self.last_day() && offer.run_till >= self.first_day()
+    }
+}
+
+impl std::fmt::Display for Week {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({} - {})",
+            locale::text("Week", "Uge"),
+            self.week,
+            self.first_day().format("%d/%m"),
+            self.last_day().format("%d/%m/%Y")
+        )
+    }
+}
+
+impl FromStr for Week {
+    type Err = anyhow::Error;
+
+    /// Accepts `current`, `next`, a week number or `<year>-W<week>`. A bare week
+    /// number more than half a year in the past refers to next year.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let invalid = || anyhow!("Invalid week: {s}. Use a week number, `current` or `next`.");
+        let (year, week) = match s.as_str() {
+            "current" | "this" => return Ok(Week::current()),
+            "next" => return Ok(Week::current().next()),
+            _ => match s.split_once("-w") {
+                Some((year, week)) => (
+                    year.parse().map_err(|_| invalid())?,
+                    week.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let week: u32 = s.parse().map_err(|_| invalid())?;
+                    let current = Week::current();
+                    if week + 26 < current.week {
+                        (current.year + 1, week)
+                    } else {
+                        (current.year, week)
+                    }
+                }
+            },
+        };
+        NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)
+            .map(Week::of)
+            .ok_or_else(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parses_iso_weeks() {
+        let week: Week = "2025-W07".parse().unwrap();
+        assert_eq!(week.first_day(), date(2025, 2, 10));
+        assert_eq!(week.last_day(), date(2025, 2, 16));
+        assert_eq!(week.iso(), "2025-W07");
+        assert_eq!(" 2025-w7 ".parse::<Week>().unwrap(), week);
+    }
+
+    #[test]
+    fn week_53() {
+        let week: Week = "2020-W53".parse().unwrap();
+        assert_eq!(week.first_day(), date(2020, 12, 28));
+        assert_eq!(week.last_day(), date(2021, 1, 3));
+        assert_eq!(week.next().iso(), "2021-W01");
+        assert!("2021-W53".parse::<Week>().is_err());
+    }
+
+    #[test]
+    fn weeks_across_new_year() {
+        assert_eq!(Week::of(date(2024, 12, 30)).iso(), "2025-W01");
+        assert_eq!(Week::of(date(2021, 1, 3)).iso(), "2020-W53");
+    }
+
+    #[test]
+    fn rejects_malformed_weeks() {
+        for input in [
+            "", "week", "0", "54", "-1", "2025-W", "2025-Wx", "x-W10", "2025-W00",
+        ] {
+            assert!(input.parse::<Week>().is_err(), "{input} parsed");
+        }
+    }
+}